@@ -0,0 +1,548 @@
+//! Solver-independent analyses of a cocktail dataset
+//!
+//! These work directly on the numeric encoding used by [`BranchBound`](crate::BranchBound),
+//! so callers are responsible for mapping ingredient ids back to names.
+use crate::metadata::{IngredientInfo, GARNISH, MIXER};
+use crate::{IngredientSeti, Ingredienti};
+use rustc_hash::{FxHashMap, FxHashSet};
+use std::collections::BTreeMap;
+
+/// Pairwise ingredient co-occurrence counts across a set of cocktails
+#[derive(Debug, Clone, Default)]
+pub struct CoOccurrence {
+    /// Number of cocktails in the dataset
+    pub cocktails: usize,
+    /// Number of cocktails each ingredient appears in
+    pub frequencies: FxHashMap<Ingredienti, u32>,
+    /// Number of cocktails each (unordered) ingredient pair appears in together.
+    /// Keys are ordered so that the smaller id comes first
+    pub counts: FxHashMap<(Ingredienti, Ingredienti), u32>,
+}
+
+impl CoOccurrence {
+    /// How many cocktails use both `a` and `b`
+    #[must_use]
+    pub fn count(&self, a: Ingredienti, b: Ingredienti) -> u32 {
+        *self.counts.get(&ordered(a, b)).unwrap_or(&0)
+    }
+
+    /// Pointwise mutual information (in bits) of `a` and `b`
+    ///
+    /// Positive values mean the pair appears together more often than their individual
+    /// frequencies would predict. Pairs that never co-occur have no defined PMI,
+    /// so `None` is returned for them.
+    #[must_use]
+    pub fn pmi(&self, a: Ingredienti, b: Ingredienti) -> Option<f64> {
+        let joint = self.count(a, b);
+        if joint == 0 {
+            return None;
+        }
+        let n = self.cocktails as f64;
+        let p_a = f64::from(*self.frequencies.get(&a)?) / n;
+        let p_b = f64::from(*self.frequencies.get(&b)?) / n;
+        let p_ab = f64::from(joint) / n;
+        Some((p_ab / (p_a * p_b)).log2())
+    }
+
+    /// All co-occurring pairs with their counts and PMI, most frequent first
+    #[must_use]
+    pub fn pairs(&self) -> Vec<((Ingredienti, Ingredienti), u32, f64)> {
+        let mut pairs = self
+            .counts
+            .iter()
+            .map(|(&(a, b), &count)| ((a, b), count, self.pmi(a, b).unwrap()))
+            .collect::<Vec<_>>();
+        pairs.sort_unstable_by(|x, y| y.1.cmp(&x.1).then(x.0.cmp(&y.0)));
+        pairs
+    }
+}
+
+fn ordered(a: Ingredienti, b: Ingredienti) -> (Ingredienti, Ingredienti) {
+    if a <= b {
+        (a, b)
+    } else {
+        (b, a)
+    }
+}
+
+/// Count how often each ingredient, and each pair of ingredients, is used across `cocktails`
+#[must_use]
+pub fn cooccurrence(cocktails: &FxHashSet<IngredientSeti>) -> CoOccurrence {
+    let mut result = CoOccurrence {
+        cocktails: cocktails.len(),
+        ..Default::default()
+    };
+    for cocktail in cocktails {
+        for (i, &a) in cocktail.iter().enumerate() {
+            *result.frequencies.entry(a).or_insert(0) += 1;
+            // BTreeSet iteration is ordered, so every pair is already (smaller, larger)
+            for &b in cocktail.iter().skip(i + 1) {
+                *result.counts.entry((a, b)).or_insert(0) += 1;
+            }
+        }
+    }
+    result
+}
+
+/// Jaccard similarity of two cocktails: shared ingredients over all ingredients used by either
+#[must_use]
+pub fn jaccard(a: &IngredientSeti, b: &IngredientSeti) -> f64 {
+    let union = a.union(b).count();
+    if union == 0 {
+        return 0.0;
+    }
+    a.intersection(b).count() as f64 / union as f64
+}
+
+/// Overlap (Szymkiewicz–Simpson) similarity of two cocktails: shared ingredients over the
+/// size of the smaller cocktail, so a cocktail is maximally similar to any of its variants
+/// that only add ingredients
+#[must_use]
+pub fn overlap(a: &IngredientSeti, b: &IngredientSeti) -> f64 {
+    let smallest = a.len().min(b.len());
+    if smallest == 0 {
+        return 0.0;
+    }
+    a.intersection(b).count() as f64 / smallest as f64
+}
+
+/// The `n` cocktails most similar to `target` according to `similarity`, most similar first
+///
+/// `target` itself is excluded, as are cocktails sharing no ingredients with it.
+/// Use [`jaccard`] or [`overlap`] as the similarity function.
+#[must_use]
+pub fn most_similar<'a>(
+    target: &IngredientSeti,
+    cocktails: &'a FxHashSet<IngredientSeti>,
+    n: usize,
+    similarity: fn(&IngredientSeti, &IngredientSeti) -> f64,
+) -> Vec<(&'a IngredientSeti, f64)> {
+    let mut scored = cocktails
+        .iter()
+        .filter(|&cocktail| cocktail != target)
+        .map(|cocktail| (cocktail, similarity(target, cocktail)))
+        .filter(|(_, score)| *score > 0.0)
+        .collect::<Vec<_>>();
+    scored.sort_unstable_by(|x, y| y.1.total_cmp(&x.1).then(x.0.cmp(y.0)));
+    scored.truncate(n);
+    scored
+}
+
+/// A cocktails × ingredients incidence matrix: row `i` marks which of `ingredients`
+/// the `i`th cocktail uses
+///
+/// Rows and columns keep the order they're given in, so callers can sort them by name first.
+#[must_use]
+pub fn coverage_matrix<'a>(
+    cocktails: impl IntoIterator<Item = &'a IngredientSeti>,
+    ingredients: &[Ingredienti],
+) -> Vec<Vec<bool>> {
+    cocktails
+        .into_iter()
+        .map(|cocktail| {
+            ingredients
+                .iter()
+                .map(|ingredient| cocktail.contains(ingredient))
+                .collect()
+        })
+        .collect()
+}
+
+/// How hard a cocktail is to justify spending ingredients on, from [`difficulty`]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Difficulty {
+    /// The cocktail's share of the cost of its ingredients, if every cocktail were made:
+    /// each ingredient costs 1 divided by the number of cocktails using it. For example,
+    /// a cocktail with a unique ingredient and two ingredients shared with one other
+    /// cocktail costs 1/1 + 1/2 + 1/2 = 2. This is a lower bound on what the cocktail
+    /// will ever cost, in ingredients
+    pub amortized_cost: f64,
+    /// The number of cocktails using its least-used ingredient. A cocktail with a min
+    /// cover of 1 has an ingredient no other cocktail uses. 0 if it has no ingredients
+    pub min_cover: i32,
+}
+
+/// The [`Difficulty`] of each of `cocktails`, relative to the others
+///
+/// These are the per-cocktail costs [`BranchBound`](crate::BranchBound) uses to choose
+/// what to branch on and to bound the search, so cocktails with a high amortized cost
+/// are the ones dragging on an ingredient budget.
+pub fn difficulty<'a>(
+    cocktails: impl Iterator<Item = &'a IngredientSeti> + Clone,
+) -> FxHashMap<IngredientSeti, Difficulty> {
+    let mut cardinality: FxHashMap<Ingredienti, i32> = FxHashMap::default();
+    cocktails
+        .clone()
+        .flatten()
+        .for_each(|ingredient| *cardinality.entry(*ingredient).or_insert(0) += 1);
+    cocktails
+        .map(|cocktail| {
+            let counts = cocktail
+                .iter()
+                .map(|ingredient| cardinality[ingredient])
+                .collect::<Vec<i32>>();
+            let difficulty = Difficulty {
+                amortized_cost: counts.iter().map(|&count| 1f64 / f64::from(count)).sum(),
+                min_cover: counts.iter().copied().min().unwrap_or(0),
+            };
+            (cocktail.clone(), difficulty)
+        })
+        .collect()
+}
+
+/// What it would take to make every cocktail, from [`full_cover`]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FullCover {
+    /// Every ingredient used by any cocktail
+    pub ingredients: IngredientSeti,
+    /// Each cocktail with ingredients no other cocktail uses, paired with those
+    /// ingredients, most first
+    pub forced: Vec<(IngredientSeti, IngredientSeti)>,
+}
+
+/// The fewest ingredients making every one of `cocktails`, and which cocktails force
+/// rare ingredients into that set
+///
+/// A cocktail needs all of its ingredients, so covering every cocktail takes every
+/// ingredient any of them uses: unlike the search's problem, this one has exactly one
+/// answer. What's worth knowing is which cocktails drive its size. An ingredient used by
+/// a single cocktail is bought for that cocktail alone, so dropping it saves them all.
+#[must_use]
+pub fn full_cover(cocktails: &FxHashSet<IngredientSeti>) -> FullCover {
+    let mut cardinality: FxHashMap<Ingredienti, i32> = FxHashMap::default();
+    cocktails
+        .iter()
+        .flatten()
+        .for_each(|ingredient| *cardinality.entry(*ingredient).or_insert(0) += 1);
+    let mut forced = cocktails
+        .iter()
+        .map(|cocktail| {
+            let unique = cocktail
+                .iter()
+                .copied()
+                .filter(|ingredient| cardinality[ingredient] == 1)
+                .collect::<IngredientSeti>();
+            (cocktail.clone(), unique)
+        })
+        .filter(|(_, unique)| !unique.is_empty())
+        .collect::<Vec<(IngredientSeti, IngredientSeti)>>();
+    forced.sort_unstable_by(|a, b| b.1.len().cmp(&a.1.len()).then(a.0.cmp(&b.0)));
+    FullCover {
+        ingredients: cardinality.into_keys().collect(),
+        forced,
+    }
+}
+
+/// Centrality scores of a single ingredient in the ingredient co-occurrence graph
+#[derive(Debug, Clone, PartialEq)]
+pub struct Centrality {
+    pub ingredient: Ingredienti,
+    /// Number of distinct ingredients this one is used alongside
+    pub degree: usize,
+    /// Number of (cocktail, partner ingredient) pairs this one appears in:
+    /// the degree, weighted by co-occurrence counts
+    pub strength: u32,
+    /// Normalised betweenness: the share of shortest paths between other ingredients
+    /// that pass through this one
+    pub betweenness: f64,
+}
+
+/// Degree and betweenness centrality over the ingredient projection of the recipe graph
+///
+/// Two ingredients are joined by an edge if any cocktail uses both. Ingredients that
+/// bridge otherwise-separate families of drinks score highly on betweenness even if
+/// they're not used often. Results are sorted by descending betweenness.
+#[must_use]
+pub fn centrality(cocktails: &FxHashSet<IngredientSeti>) -> Vec<Centrality> {
+    let cooccurrence = cooccurrence(cocktails);
+    let mut ingredients = cooccurrence
+        .frequencies
+        .keys()
+        .copied()
+        .collect::<Vec<Ingredienti>>();
+    ingredients.sort_unstable();
+    let index = ingredients
+        .iter()
+        .enumerate()
+        .map(|(i, &ingredient)| (ingredient, i))
+        .collect::<FxHashMap<Ingredienti, usize>>();
+    let n = ingredients.len();
+    let mut neighbours = vec![vec![]; n];
+    let mut strength = vec![0u32; n];
+    for (&(a, b), &count) in &cooccurrence.counts {
+        let (a, b) = (index[&a], index[&b]);
+        neighbours[a].push(b);
+        neighbours[b].push(a);
+        strength[a] += count;
+        strength[b] += count;
+    }
+
+    // Brandes' algorithm: one BFS per source, accumulating dependencies on the way back
+    let mut betweenness = vec![0f64; n];
+    for source in 0..n {
+        let mut order = Vec::with_capacity(n);
+        let mut predecessors = vec![vec![]; n];
+        let mut paths = vec![0f64; n];
+        let mut distance = vec![usize::MAX; n];
+        paths[source] = 1.0;
+        distance[source] = 0;
+        let mut queue = std::collections::VecDeque::from([source]);
+        while let Some(v) = queue.pop_front() {
+            order.push(v);
+            for &w in &neighbours[v] {
+                if distance[w] == usize::MAX {
+                    distance[w] = distance[v] + 1;
+                    queue.push_back(w);
+                }
+                if distance[w] == distance[v] + 1 {
+                    paths[w] += paths[v];
+                    predecessors[w].push(v);
+                }
+            }
+        }
+        let mut dependency = vec![0f64; n];
+        for &w in order.iter().rev() {
+            for &v in &predecessors[w] {
+                dependency[v] += paths[v] / paths[w] * (1.0 + dependency[w]);
+            }
+            if w != source {
+                betweenness[w] += dependency[w];
+            }
+        }
+    }
+    // every undirected path was counted from both ends
+    let pairs = if n > 2 {
+        ((n - 1) * (n - 2)) as f64
+    } else {
+        1.0
+    };
+
+    let mut result = ingredients
+        .iter()
+        .enumerate()
+        .map(|(i, &ingredient)| Centrality {
+            ingredient,
+            degree: neighbours[i].len(),
+            strength: strength[i],
+            betweenness: betweenness[i] / pairs,
+        })
+        .collect::<Vec<Centrality>>();
+    result.sort_unstable_by(|a, b| {
+        b.betweenness
+            .total_cmp(&a.betweenness)
+            .then(b.degree.cmp(&a.degree))
+            .then(a.ingredient.cmp(&b.ingredient))
+    });
+    result
+}
+
+/// Aggregate style and strength statistics for a menu of cocktails
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct MenuStats {
+    pub cocktails: usize,
+    /// Cocktails made only of alcoholic ingredients (ignoring garnishes), e.g. a Negroni
+    pub spirit_forward: usize,
+    /// Cocktails using at least one [`MIXER`], e.g. a Gin and tonic
+    pub long_drinks: usize,
+    /// Everything else, including sours and cocktails lacking metadata
+    pub other: usize,
+    /// Mean estimated ABV across cocktails containing at least one ingredient of known ABV
+    pub average_abv: Option<f64>,
+    /// Number of cocktails per base spirit: the category of each cocktail's strongest
+    /// ingredient of at least 20% ABV
+    pub base_spirits: BTreeMap<String, usize>,
+}
+
+fn category<'a>(meta: &Option<&'a IngredientInfo>) -> Option<&'a str> {
+    meta.and_then(|m| m.category.as_deref())
+}
+
+fn abv(meta: &Option<&IngredientInfo>) -> Option<f64> {
+    meta.and_then(|m| m.abv)
+}
+
+/// Summarise the balance of a menu using per-ingredient metadata
+///
+/// A cocktail's ABV is estimated by assuming equal parts of each non-garnish ingredient,
+/// with ingredients of unknown strength treated as non-alcoholic. That's crude, but it's
+/// consistent, so it's good enough to compare one menu with another.
+#[must_use]
+pub fn menu_stats<'a>(
+    cocktails: impl IntoIterator<Item = &'a IngredientSeti>,
+    info: &FxHashMap<Ingredienti, IngredientInfo>,
+) -> MenuStats {
+    let mut stats = MenuStats::default();
+    let mut abv_total = 0.0;
+    let mut abv_cocktails = 0;
+    for cocktail in cocktails {
+        stats.cocktails += 1;
+        let ingredients = cocktail
+            .iter()
+            .map(|ingredient| info.get(ingredient))
+            .filter(|meta| category(meta) != Some(GARNISH))
+            .collect::<Vec<Option<&IngredientInfo>>>();
+
+        if ingredients.iter().any(|meta| category(meta) == Some(MIXER)) {
+            stats.long_drinks += 1;
+        } else if !ingredients.is_empty()
+            && ingredients
+                .iter()
+                .all(|meta| abv(meta).is_some_and(|abv| abv > 0.0))
+        {
+            stats.spirit_forward += 1;
+        } else {
+            stats.other += 1;
+        }
+
+        if ingredients.iter().any(|meta| abv(meta).is_some()) {
+            abv_total += ingredients.iter().filter_map(abv).sum::<f64>() / ingredients.len() as f64;
+            abv_cocktails += 1;
+        }
+
+        let base = ingredients
+            .iter()
+            .filter(|meta| abv(meta).is_some_and(|abv| abv >= 20.0))
+            .max_by(|a, b| abv(a).unwrap().total_cmp(&abv(b).unwrap()));
+        if let Some(base) = base {
+            let name = category(base).unwrap_or("uncategorised").to_owned();
+            *stats.base_spirits.entry(name).or_insert(0) += 1;
+        }
+    }
+    if abv_cocktails > 0 {
+        stats.average_abv = Some(abv_total / f64::from(abv_cocktails));
+    }
+    stats
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cocktails(sets: &[&[Ingredienti]]) -> FxHashSet<IngredientSeti> {
+        sets.iter()
+            .map(|set| set.iter().copied().collect())
+            .collect()
+    }
+
+    #[test]
+    fn counts_pairs_in_either_order() {
+        let cooccurrence = cooccurrence(&cocktails(&[&[1, 2, 3], &[1, 2], &[2, 4]]));
+        assert_eq!(cooccurrence.cocktails, 3);
+        assert_eq!(cooccurrence.frequencies[&2], 3);
+        assert_eq!(cooccurrence.count(1, 2), 2);
+        assert_eq!(cooccurrence.count(2, 1), 2);
+        assert_eq!(cooccurrence.count(1, 4), 0);
+        // 1 is always with 2, which is in every cocktail, so tells nothing about it
+        assert_eq!(cooccurrence.pmi(1, 2), Some(0.0));
+        assert_eq!(cooccurrence.pmi(3, 1), Some(1.5f64.log2()));
+        assert_eq!(cooccurrence.pmi(1, 4), None);
+        let pairs = cooccurrence.pairs();
+        assert_eq!(pairs.len(), 4);
+        assert_eq!(pairs[0], ((1, 2), 2, 0.0));
+    }
+
+    #[test]
+    fn scores_similarity() {
+        let negroni = IngredientSeti::from([1, 2, 3]);
+        let americano = IngredientSeti::from([2, 3, 4]);
+        assert_eq!(jaccard(&negroni, &americano), 0.5);
+        assert_eq!(overlap(&negroni, &americano), 2.0 / 3.0);
+        // a variant adding an ingredient overlaps completely
+        let variant = IngredientSeti::from([1, 2, 3, 5]);
+        assert_eq!(overlap(&negroni, &variant), 1.0);
+        assert_eq!(jaccard(&negroni, &variant), 0.75);
+        assert_eq!(jaccard(&IngredientSeti::new(), &IngredientSeti::new()), 0.0);
+        assert_eq!(overlap(&negroni, &IngredientSeti::new()), 0.0);
+    }
+
+    #[test]
+    fn ranks_the_most_similar() {
+        let target = IngredientSeti::from([1, 2]);
+        let cocktails = cocktails(&[&[1, 2], &[1, 2, 3], &[2, 4], &[5]]);
+        let similar = most_similar(&target, &cocktails, 5, overlap);
+        // leaving out the target, and anything sharing nothing with it
+        assert_eq!(
+            similar,
+            [
+                (&IngredientSeti::from([1, 2, 3]), 1.0),
+                (&IngredientSeti::from([2, 4]), 0.5)
+            ]
+        );
+        assert_eq!(most_similar(&target, &cocktails, 1, jaccard).len(), 1);
+    }
+
+    #[test]
+    fn marks_the_ingredients_each_cocktail_uses() {
+        let negroni = IngredientSeti::from([1, 2, 3]);
+        let gimlet = IngredientSeti::from([1, 4]);
+        // in the order given, not by id
+        let matrix = coverage_matrix([&gimlet, &negroni], &[4, 1, 2]);
+        assert_eq!(matrix, [[true, true, false], [false, true, true]]);
+    }
+
+    #[test]
+    fn bridges_score_highest() {
+        // a chain: 2 and 3 each lie on two of the three paths between the others
+        let chain = centrality(&cocktails(&[&[1, 2], &[2, 3], &[3, 4]]));
+        let order = chain
+            .iter()
+            .map(|c| c.ingredient)
+            .collect::<Vec<Ingredienti>>();
+        assert_eq!(order, [2, 3, 1, 4]);
+        assert_eq!((chain[0].degree, chain[0].strength), (2, 2));
+        assert!((chain[0].betweenness - 2.0 / 3.0).abs() < 1e-9);
+        assert_eq!((chain[2].degree, chain[2].betweenness), (1, 0.0));
+        // in a single cocktail, every ingredient is next to every other
+        let triangle = centrality(&cocktails(&[&[1, 2, 3]]));
+        assert!(triangle
+            .iter()
+            .all(|c| c.degree == 2 && c.betweenness == 0.0));
+    }
+
+    #[test]
+    fn summarises_style_and_strength() {
+        let meta = |abv, category: Option<&str>| IngredientInfo {
+            abv,
+            category: category.map(str::to_owned),
+        };
+        let info = FxHashMap::from_iter([
+            (1, meta(Some(40.0), Some("gin"))),
+            (2, meta(Some(25.0), Some("bitter"))),
+            (3, meta(Some(16.0), Some("vermouth"))),
+            (4, meta(None, Some(MIXER))),
+            (6, meta(None, Some(GARNISH))),
+        ]);
+        // a Negroni with its peel, a Gin and tonic, a Gimlet, a Campari soda and a
+        // lime soda; 5, the lime, has no metadata
+        let menu = [&[1, 2, 3, 6][..], &[1, 4], &[1, 5], &[2, 4], &[4, 5]]
+            .map(|cocktail| cocktail.iter().copied().collect::<IngredientSeti>());
+        let stats = menu_stats(&menu, &info);
+        assert_eq!(stats.cocktails, 5);
+        assert_eq!(
+            (stats.spirit_forward, stats.long_drinks, stats.other),
+            (1, 3, 1)
+        );
+        // the lime soda has no ingredient of known strength, so isn't averaged
+        let average = (27.0 + 20.0 + 20.0 + 12.5) / 4.0;
+        assert!((stats.average_abv.unwrap() - average).abs() < 1e-9);
+        assert_eq!(
+            stats.base_spirits,
+            BTreeMap::from([("bitter".to_owned(), 1), ("gin".to_owned(), 3)])
+        );
+        assert_eq!(menu_stats([], &info), MenuStats::default());
+    }
+
+    #[test]
+    fn shares_out_each_ingredient_between_its_cocktails() {
+        let cocktails = cocktails(&[&[1, 2], &[2, 3, 4], &[2], &[]]);
+        let difficulty = difficulty(cocktails.iter());
+        let of = |set: &[Ingredienti]| difficulty[&set.iter().copied().collect::<IngredientSeti>()];
+        // 2 is in three cocktails, so each bears a third of it
+        assert!((of(&[1, 2]).amortized_cost - 4.0 / 3.0).abs() < 1e-9);
+        assert!((of(&[2, 3, 4]).amortized_cost - 7.0 / 3.0).abs() < 1e-9);
+        assert!((of(&[2]).amortized_cost - 1.0 / 3.0).abs() < 1e-9);
+        assert_eq!(of(&[1, 2]).min_cover, 1);
+        assert_eq!(of(&[2]).min_cover, 3);
+        assert_eq!((of(&[]).amortized_cost, of(&[]).min_cover), (0.0, 0));
+    }
+}
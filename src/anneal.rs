@@ -0,0 +1,236 @@
+//! Simulated annealing over ingredient sets, for problems too large to search exactly
+//!
+//! Starting from the [greedy](crate::BranchBound::greedy) set, each step swaps an
+//! ingredient in the set for one outside it, or just adds one while there's room. A
+//! step that makes more cocktails is always taken; one that makes fewer is taken with
+//! probability `exp(-loss / temperature)`, and the temperature cools geometrically from
+//! [`initial_temperature`](Annealing::initial_temperature) to
+//! [`final_temperature`](Annealing::final_temperature) over the run, so that the search
+//! wanders early on and settles later. The best set seen is kept.
+use crate::portfolio::Shared;
+use crate::problem::Problem;
+use crate::solution::Solution;
+use crate::solver::Solver;
+use crate::{Error, IngredientSeti, Ingredienti, TIME_CHECK_INTERVAL};
+use rand::rngs::StdRng;
+use rand::seq::{IteratorRandom, SliceRandom};
+use rand::{Rng, SeedableRng};
+use rustc_hash::{FxHashMap, FxHashSet};
+
+/// A simulated annealing [`Solver`]. Fast on any size of problem, but with no
+/// guarantee of finding the optimum: the solution's [gap](Solution::gap) gives the
+/// most it could be short by
+#[derive(Debug, Clone, PartialEq)]
+pub struct Annealing {
+    /// How many steps to take
+    pub iterations: u64,
+    /// The starting temperature, in units of [score](crate::BranchBound::score): a step losing
+    /// this much is taken with probability 1/e
+    pub initial_temperature: f64,
+    /// The temperature by the last step
+    pub final_temperature: f64,
+    /// Seeds the random choice of steps, for a reproducible run
+    pub seed: Option<u64>,
+}
+
+impl Default for Annealing {
+    fn default() -> Self {
+        Annealing {
+            iterations: 100_000,
+            initial_temperature: 2.0,
+            final_temperature: 0.05,
+            seed: None,
+        }
+    }
+}
+
+impl Annealing {
+    /// Anneal for `iterations` steps, at the default temperatures
+    #[must_use]
+    pub fn new(iterations: u64) -> Annealing {
+        Annealing {
+            iterations,
+            ..Annealing::default()
+        }
+    }
+
+    /// Cool from `initial` to `last` over the run
+    #[must_use]
+    pub fn with_temperatures(mut self, initial: f64, last: f64) -> Annealing {
+        self.initial_temperature = initial;
+        self.final_temperature = last;
+        self
+    }
+
+    /// Seed the random choice of steps
+    #[must_use]
+    pub fn with_seed(mut self, seed: u64) -> Annealing {
+        self.seed = Some(seed);
+        self
+    }
+
+    /// The temperature `step` steps into the run
+    fn temperature(&self, step: u64) -> f64 {
+        let progress = step as f64 / self.iterations.max(1) as f64;
+        self.initial_temperature
+            * (self.final_temperature / self.initial_temperature).powf(progress)
+    }
+}
+
+impl Solver for Annealing {
+    fn solve(&mut self, problem: &Problem) -> Result<Solution, Error> {
+        self.anneal(problem, None)
+    }
+}
+
+impl Annealing {
+    /// Anneal, sharing the best set found with a [portfolio](crate::portfolio), if
+    /// running in one, and stopping when it does
+    pub(crate) fn anneal(
+        &self,
+        problem: &Problem,
+        shared: Option<&Shared>,
+    ) -> Result<Solution, Error> {
+        let mut bb = problem.builder(1).try_build()?;
+        let mut cocktails = problem.cocktail_sets();
+        // start from the greedy set, which also bounds how far from optimal we can be
+        bb.greedy_search(&cocktails);
+        bb.reduce(&mut cocktails);
+        let mut rng = match self.seed {
+            Some(seed) => StdRng::seed_from_u64(seed),
+            None => StdRng::from_entropy(),
+        };
+        let pool = cocktails
+            .iter()
+            .flatten()
+            .copied()
+            .collect::<IngredientSeti>()
+            .into_iter()
+            .collect::<Vec<Ingredienti>>();
+        let mut state = State::new(&cocktails);
+        for &ingredient in &bb.highest_ingredients {
+            state.add(ingredient);
+        }
+        let mut score = bb.score(&state.made);
+        let mut best = (score, state.made.clone(), bb.ingredients(&state.made));
+        for step in 0..self.iterations {
+            if step.is_multiple_of(u64::from(TIME_CHECK_INTERVAL))
+                && shared.is_some_and(Shared::stopped)
+            {
+                break;
+            }
+            let Some(&added) = pool.choose(&mut rng) else {
+                break;
+            };
+            if state.ingredients.contains(&added) {
+                continue;
+            }
+            let mut extended = state.ingredients.clone();
+            extended.insert(added);
+            // swap, unless there's room just to add
+            let dropped = if bb.fits(&extended) && rng.gen_bool(0.5) {
+                None
+            } else {
+                let Some(dropped) = state
+                    .ingredients
+                    .difference(&bb.required)
+                    .copied()
+                    .choose(&mut rng)
+                else {
+                    continue;
+                };
+                Some(dropped)
+            };
+            if let Some(dropped) = dropped {
+                state.remove(dropped);
+            }
+            state.add(added);
+            let next = bb.score(&state.made);
+            let accepted = bb.admits(&state.ingredients)
+                && (next >= score
+                    || rng.gen::<f64>()
+                        < ((next as f64 - score as f64) / self.temperature(step)).exp());
+            if !accepted {
+                state.remove(added);
+                if let Some(dropped) = dropped {
+                    state.add(dropped);
+                }
+                continue;
+            }
+            score = next;
+            if score >= best.0 {
+                // ingredients making nothing only lengthen the shopping list
+                let trimmed = bb.ingredients(&state.made);
+                if bb.improves(score, &trimmed, best.0, bb.padded_len(&best.2)) {
+                    if let Some(shared) = shared {
+                        shared.offer(&bb, score, &state.made, &trimmed);
+                    }
+                    best = (score, state.made.clone(), trimmed);
+                }
+            }
+        }
+        let (best_score, best_cocktails, best_ingredients) = best;
+        let highest_len = bb.padded_len(&bb.highest_ingredients);
+        if bb.improves(best_score, &best_ingredients, bb.highest_score, highest_len) {
+            bb.highest = best_cocktails;
+            bb.highest_score = best_score;
+            bb.highest_ingredients = best_ingredients;
+        }
+        Ok(problem.result(&bb, &bb.highest))
+    }
+}
+
+/// An ingredient set and the cocktails it makes, updated one ingredient at a time
+struct State<'a> {
+    cocktails: Vec<&'a IngredientSeti>,
+    /// The cocktails using each ingredient, by index into `cocktails`
+    uses: FxHashMap<Ingredienti, Vec<usize>>,
+    /// How many of each cocktail's ingredients are missing from the set
+    missing: Vec<usize>,
+    ingredients: IngredientSeti,
+    made: FxHashSet<IngredientSeti>,
+}
+
+impl<'a> State<'a> {
+    /// The empty set, making any of `cocktails`, which must all be makeable
+    fn new(cocktails: &'a FxHashSet<IngredientSeti>) -> State<'a> {
+        let cocktails = cocktails.iter().collect::<Vec<&IngredientSeti>>();
+        let mut uses = FxHashMap::<Ingredienti, Vec<usize>>::default();
+        for (i, cocktail) in cocktails.iter().enumerate() {
+            for &ingredient in *cocktail {
+                uses.entry(ingredient).or_default().push(i);
+            }
+        }
+        State {
+            missing: cocktails.iter().map(|cocktail| cocktail.len()).collect(),
+            cocktails,
+            uses,
+            ingredients: IngredientSeti::new(),
+            made: FxHashSet::default(),
+        }
+    }
+
+    fn add(&mut self, ingredient: Ingredienti) {
+        if !self.ingredients.insert(ingredient) {
+            return;
+        }
+        for &i in self.uses.get(&ingredient).into_iter().flatten() {
+            self.missing[i] -= 1;
+            if self.missing[i] == 0 {
+                self.made.insert(self.cocktails[i].clone());
+            }
+        }
+    }
+
+    fn remove(&mut self, ingredient: Ingredienti) {
+        if !self.ingredients.remove(&ingredient) {
+            return;
+        }
+        for &i in self.uses.get(&ingredient).into_iter().flatten() {
+            if self.missing[i] == 0 {
+                self.made.remove(self.cocktails[i]);
+            }
+            self.missing[i] += 1;
+        }
+    }
+}
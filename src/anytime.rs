@@ -0,0 +1,139 @@
+//! Running a search in the background, and watching its incumbents arrive
+//!
+//! [`BranchBound::incumbents`] moves a search onto its own thread and returns an
+//! [`Incumbents`] iterator, which yields each better ingredient set as soon as the search
+//! finds it. The iterator ends when the search does; [`Incumbents::finish`] then hands
+//! back the finished search, with its statistics and final result. It's built on the
+//! same hooks as any other [`SearchObserver`], so an observer registered with the
+//! builder still sees everything.
+use crate::observer::{Observer, Prune, SearchObserver};
+use crate::{BranchBound, IngredientSeti};
+use rustc_hash::FxHashSet;
+use std::ops::ControlFlow;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+
+/// A better ingredient set, as found by a running search
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Incumbent {
+    pub score: usize,
+    pub ingredients: IngredientSeti,
+}
+
+/// The incumbents of a search running in the background, in the order it finds them.
+/// Dropping this stops the search
+#[derive(Debug)]
+pub struct Incumbents {
+    receiver: Receiver<Incumbent>,
+    search: Option<JoinHandle<BranchBound>>,
+    cancelled: Arc<AtomicBool>,
+}
+
+impl Iterator for Incumbents {
+    type Item = Incumbent;
+
+    /// Wait for the next incumbent, or for the search to end
+    fn next(&mut self) -> Option<Incumbent> {
+        self.receiver.recv().ok()
+    }
+}
+
+impl Incumbents {
+    /// Stop the search at its next node, keeping the best set found so far
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    /// Wait for the search to end, and return it. Any incumbents not yet taken from the
+    /// iterator are discarded, but the best is in [`BranchBound::highest`]
+    #[must_use]
+    pub fn finish(mut self) -> BranchBound {
+        let search = self.search.take().expect("a search is only finished once");
+        search
+            .join()
+            .unwrap_or_else(|panic| std::panic::resume_unwind(panic))
+    }
+}
+
+impl Drop for Incumbents {
+    fn drop(&mut self) {
+        self.cancel();
+    }
+}
+
+/// Forwards incumbents to an [`Incumbents`], and everything to any registered observer
+struct Stream {
+    sender: Sender<Incumbent>,
+    inner: Option<Observer>,
+    cancelled: Arc<AtomicBool>,
+}
+
+impl SearchObserver for Stream {
+    fn on_new_incumbent(&mut self, score: usize, ingredients: &IngredientSeti) {
+        if let Some(inner) = &self.inner {
+            inner.with(|observer| observer.on_new_incumbent(score, ingredients));
+        }
+        // if nobody's listening any more, the search is about to be cancelled anyway
+        let _ = self.sender.send(Incumbent {
+            score,
+            ingredients: ingredients.clone(),
+        });
+    }
+
+    fn on_node_expanded(&mut self, depth: usize, calls_remaining: i32) -> ControlFlow<()> {
+        if self.cancelled.load(Ordering::Relaxed) {
+            return ControlFlow::Break(());
+        }
+        match &self.inner {
+            Some(inner) => inner.with(|observer| observer.on_node_expanded(depth, calls_remaining)),
+            None => ControlFlow::Continue(()),
+        }
+    }
+
+    fn on_prune(&mut self, reason: Prune) {
+        if let Some(inner) = &self.inner {
+            inner.with(|observer| observer.on_prune(reason));
+        }
+    }
+}
+
+impl BranchBound {
+    /// Search `candidates` on a background thread, yielding each better ingredient set
+    /// as it's found. The search is as [`search`](Self::search) would run it
+    ///
+    /// ```
+    /// use branchbound::{BranchBound, IngredientSeti};
+    /// use rustc_hash::FxHashSet;
+    ///
+    /// let cocktails = FxHashSet::from_iter([IngredientSeti::from([0, 1]), IngredientSeti::from([1, 2])]);
+    /// let mut incumbents = BranchBound::new(1000, 2).incumbents(cocktails);
+    /// let last = incumbents.by_ref().last().unwrap();
+    /// assert_eq!(last.score, 1);
+    /// assert_eq!(incumbents.finish().highest_score, 1);
+    /// ```
+    #[must_use]
+    pub fn incumbents(mut self, candidates: FxHashSet<IngredientSeti>) -> Incumbents {
+        let (sender, receiver) = mpsc::channel();
+        let cancelled = Arc::new(AtomicBool::new(false));
+        let inner = self.observer.clone();
+        self.observer = Some(Observer::new(Stream {
+            sender,
+            inner: inner.clone(),
+            cancelled: Arc::clone(&cancelled),
+        }));
+        let search = thread::spawn(move || {
+            let mut candidates = candidates;
+            self.search(&mut candidates, &mut FxHashSet::default(), &mut None);
+            // dropping the stream closes the channel, ending the iterator
+            self.observer = inner;
+            self
+        });
+        Incumbents {
+            receiver,
+            search: Some(search),
+            cancelled,
+        }
+    }
+}
@@ -0,0 +1,66 @@
+//! Comparing search configurations on the same problem
+//!
+//! [`compare`] runs each of several configured searches on the same cocktails, a few
+//! times over, and reports how many nodes each expanded and how long it took, so that
+//! bounds, branching strategies and seeds can be tuned by measurement rather than
+//! guesswork. A sequential search expands the same nodes every time it's run with the
+//! same configuration, but its time varies with whatever else the machine is doing, so
+//! only the fastest run is kept.
+use crate::{BranchBoundBuilder, ConfigError, IngredientSeti, SearchStatus};
+use rustc_hash::FxHashSet;
+use std::time::{Duration, Instant};
+
+/// How one configuration fared
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Trial {
+    /// What the configuration was called
+    pub label: String,
+    /// Nodes expanded
+    pub nodes: u32,
+    /// Nodes pruned, by any bound
+    pub pruned: u32,
+    /// The fastest of the runs
+    pub time: Duration,
+    /// What the best set found [scores](crate::BranchBound::score)
+    pub score: usize,
+    /// How the last run ended. Comparing incomplete searches is comparing their call
+    /// budgets
+    pub status: SearchStatus,
+}
+
+/// Search `candidates` `runs` times with each of `configurations`, labelled, in the
+/// order given
+pub fn compare(
+    configurations: impl IntoIterator<Item = (String, BranchBoundBuilder)>,
+    candidates: &FxHashSet<IngredientSeti>,
+    runs: usize,
+) -> Result<Vec<Trial>, ConfigError> {
+    configurations
+        .into_iter()
+        .map(|(label, builder)| {
+            let mut trial = Trial {
+                label,
+                nodes: 0,
+                pruned: 0,
+                time: Duration::MAX,
+                score: 0,
+                status: SearchStatus::Complete,
+            };
+            for _ in 0..runs.max(1) {
+                let mut bb = builder.clone().try_build()?;
+                let started = Instant::now();
+                bb.search(
+                    &mut candidates.clone(),
+                    &mut FxHashSet::default(),
+                    &mut None,
+                );
+                trial.time = trial.time.min(started.elapsed());
+                trial.nodes = bb.stats.nodes;
+                trial.pruned = bb.stats.pruned();
+                trial.score = bb.highest_score;
+                trial.status = bb.status;
+            }
+            Ok(trial)
+        })
+        .collect()
+}
@@ -0,0 +1,174 @@
+//! How the search chooses which candidate cocktail to branch on
+//!
+//! At each node, the search branches on one candidate: first including it, then
+//! excluding it. A strategy can instead branch on an
+//! [ingredient](BranchingStrategy::ingredient), first buying it, then ruling it out, as
+//! [`MostCommonIngredient`] does. Which one it picks doesn't change the answer, but it changes how soon
+//! a good incumbent turns up and how much of the tree the bounds can prune. The default,
+//! [`MinAmortizedCost`], has worked best on the IBA dataset; the others are there to
+//! experiment with, by passing one to
+//! [`BranchBoundBuilder::branching`](crate::BranchBoundBuilder::branching).
+use crate::{BranchBound, IngredientSeti, Ingredienti};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::cmp::Reverse;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::sync::{Mutex, PoisonError};
+
+/// A rule for choosing the candidate to branch on
+///
+/// The search branches on the candidate costing least, breaking ties with its
+/// [seed](crate::BranchBoundBuilder::with_seed) if it has one. Strategies are shared
+/// between the threads of a [parallel](crate::BranchBoundBuilder::parallel) search
+pub trait BranchingStrategy: fmt::Debug + Send + Sync {
+    /// How much branching on `cocktail` next would cost, given the node's other
+    /// `candidates` and the ingredients its cocktails already use
+    fn cost(
+        &self,
+        bb: &BranchBound,
+        cocktail: &IngredientSeti,
+        candidates: &[&IngredientSeti],
+        partial_ingredients: &IngredientSeti,
+    ) -> f64;
+
+    /// The ingredient to branch on instead of a candidate, if the strategy branches on
+    /// ingredients: one that the node's `candidates` need beyond the ingredients its
+    /// cocktails already use. None, by default, to branch on the candidate costing
+    /// least. A [parallel](crate::BranchBoundBuilder::parallel) search always branches
+    /// on candidates
+    fn ingredient(
+        &self,
+        _bb: &BranchBound,
+        _candidates: &[&IngredientSeti],
+        _partial_ingredients: &IngredientSeti,
+    ) -> Option<Ingredienti> {
+        None
+    }
+}
+
+/// Branch on the cocktail with the lowest [amortized
+/// cost](crate::analysis::Difficulty::amortized_cost): the one whose ingredients are
+/// shared most widely. The default
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct MinAmortizedCost;
+
+impl BranchingStrategy for MinAmortizedCost {
+    fn cost(
+        &self,
+        bb: &BranchBound,
+        cocktail: &IngredientSeti,
+        _candidates: &[&IngredientSeti],
+        _partial_ingredients: &IngredientSeti,
+    ) -> f64 {
+        bb.amortized_cost(cocktail)
+    }
+}
+
+/// Branch on the cocktail whose ingredients would make the most candidates, so that
+/// good incumbents turn up early. This looks at every pair of candidates, so each node
+/// takes longer
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct MaxCoverage;
+
+impl BranchingStrategy for MaxCoverage {
+    fn cost(
+        &self,
+        _bb: &BranchBound,
+        cocktail: &IngredientSeti,
+        candidates: &[&IngredientSeti],
+        partial_ingredients: &IngredientSeti,
+    ) -> f64 {
+        let ingredients = cocktail | partial_ingredients;
+        let covered = candidates
+            .iter()
+            .filter(|candidate| candidate.is_subset(&ingredients))
+            .count();
+        -(covered as f64)
+    }
+}
+
+/// Branch on the cocktail using the rarest ingredient, as measured by its [min
+/// cover](crate::analysis::Difficulty::min_cover), so that the most constrained
+/// decisions are made first
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct MostConstrainedIngredient;
+
+impl BranchingStrategy for MostConstrainedIngredient {
+    fn cost(
+        &self,
+        bb: &BranchBound,
+        cocktail: &IngredientSeti,
+        _candidates: &[&IngredientSeti],
+        _partial_ingredients: &IngredientSeti,
+    ) -> f64 {
+        bb.min_cover
+            .get(cocktail)
+            .map_or(f64::INFINITY, |&cover| f64::from(cover))
+    }
+}
+
+/// Branch on the ingredient the most candidates still need, rather than on a cocktail:
+/// buying it in one branch, and dropping every candidate needing it in the other.
+/// Ingredients shared by many candidates split them more evenly than any one cocktail
+/// does, so the tree is often more balanced. Where the search has to branch on a
+/// candidate, it takes the one with the lowest amortized cost, as [`MinAmortizedCost`]
+/// does
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct MostCommonIngredient;
+
+impl BranchingStrategy for MostCommonIngredient {
+    fn cost(
+        &self,
+        bb: &BranchBound,
+        cocktail: &IngredientSeti,
+        _candidates: &[&IngredientSeti],
+        _partial_ingredients: &IngredientSeti,
+    ) -> f64 {
+        bb.amortized_cost(cocktail)
+    }
+
+    fn ingredient(
+        &self,
+        _bb: &BranchBound,
+        candidates: &[&IngredientSeti],
+        partial_ingredients: &IngredientSeti,
+    ) -> Option<Ingredienti> {
+        let mut needed = BTreeMap::<Ingredienti, usize>::new();
+        for ingredient in candidates
+            .iter()
+            .flat_map(|&cocktail| cocktail - partial_ingredients)
+        {
+            *needed.entry(ingredient).or_default() += 1;
+        }
+        // ties go to the lowest ingredient, so the choice doesn't depend on hash order
+        needed
+            .into_iter()
+            .max_by_key(|&(ingredient, count)| (count, Reverse(ingredient)))
+            .map(|(ingredient, _)| ingredient)
+    }
+}
+
+/// Branch on a candidate chosen at random: a baseline for the other strategies
+#[derive(Debug)]
+pub struct Random(Mutex<StdRng>);
+
+impl Random {
+    /// Choose with a generator seeded with `seed`, for a reproducible sequential search
+    #[must_use]
+    pub fn new(seed: u64) -> Random {
+        Random(Mutex::new(StdRng::seed_from_u64(seed)))
+    }
+}
+
+impl BranchingStrategy for Random {
+    fn cost(
+        &self,
+        _bb: &BranchBound,
+        _cocktail: &IngredientSeti,
+        _candidates: &[&IngredientSeti],
+        _partial_ingredients: &IngredientSeti,
+    ) -> f64 {
+        self.0.lock().unwrap_or_else(PoisonError::into_inner).gen()
+    }
+}
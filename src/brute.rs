@@ -0,0 +1,108 @@
+//! An exhaustive search over ingredient sets, for small problems
+//!
+//! [`BranchBound::brute_force`] tries every combination of the candidate ingredients,
+//! subject to the same constraints as the search, so its answer is optimal by
+//! construction. That makes it practical for a small bar, and an oracle to test the
+//! search's pruning against, but the number of combinations doubles with each
+//! ingredient, so it refuses more than [`MAX_INGREDIENTS`].
+use crate::problem::Problem;
+use crate::solution::Solution;
+use crate::solver::Solver;
+use crate::{BranchBound, Error, IngredientSeti, Ingredienti};
+use rustc_hash::FxHashSet;
+
+/// The most candidate ingredients, beyond the required ones, an exhaustive search will
+/// try every combination of
+pub const MAX_INGREDIENTS: usize = 20;
+
+impl BranchBound {
+    /// Find the best cocktails among `candidates` by trying every combination of their
+    /// ingredients, returning them as [`search`](Self::search) would. Fails if there are
+    /// more than [`MAX_INGREDIENTS`] to choose from
+    pub fn brute_force(
+        &mut self,
+        candidates: &FxHashSet<IngredientSeti>,
+    ) -> Result<FxHashSet<IngredientSeti>, Error> {
+        let mut candidates = candidates.clone();
+        let mut partial = FxHashSet::default();
+        if self.initial {
+            self.prepare(&mut candidates, &mut partial, &mut None);
+        }
+        let pool = candidates
+            .iter()
+            .flatten()
+            .copied()
+            .filter(|ingredient| !self.required.contains(ingredient))
+            .collect::<IngredientSeti>()
+            .into_iter()
+            .collect::<Vec<Ingredienti>>();
+        if pool.len() > MAX_INGREDIENTS {
+            return Err(Error::TooLarge {
+                ingredients: pool.len(),
+                limit: MAX_INGREDIENTS,
+            });
+        }
+        // each cocktail as a mask of the pool ingredients it still needs
+        let needs = candidates
+            .iter()
+            .map(|cocktail| {
+                let mask = pool
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, ingredient)| cocktail.contains(ingredient))
+                    .fold(0u32, |mask, (bit, _)| mask | 1 << bit);
+                (cocktail, mask)
+            })
+            .collect::<Vec<(&IngredientSeti, u32)>>();
+        let affordable = self.max_size.saturating_sub(self.bought(&self.required));
+        for chosen in 0..1u32 << pool.len() {
+            if chosen.count_ones() as usize > affordable {
+                continue;
+            }
+            let mut ingredients = self.required.clone();
+            ingredients.extend(
+                (0..pool.len())
+                    .filter(|bit| chosen & 1 << bit != 0)
+                    .map(|bit| pool[bit]),
+            );
+            if !self.admits(&ingredients) {
+                continue;
+            }
+            let mut made = partial.clone();
+            made.extend(
+                needs
+                    .iter()
+                    .filter(|(_, mask)| mask & !chosen == 0)
+                    .map(|(cocktail, _)| (*cocktail).clone()),
+            );
+            let score = self.score(&made);
+            // ingredients making nothing only lengthen the shopping list
+            let trimmed = self.ingredients(&made);
+            let ingredients = if self.admits(&trimmed) {
+                trimmed
+            } else {
+                ingredients
+            };
+            let highest_len = self.padded_len(&self.highest_ingredients);
+            if self.improves(score, &ingredients, self.highest_score, highest_len) {
+                self.highest = made;
+                self.highest_score = score;
+                self.highest_ingredients = ingredients;
+            }
+        }
+        Ok(self.highest.clone())
+    }
+}
+
+/// A [`Solver`] trying every combination of ingredients: guaranteed optimal, but only
+/// feasible for up to [`MAX_INGREDIENTS`] candidate ingredients
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct BruteForce;
+
+impl Solver for BruteForce {
+    fn solve(&mut self, problem: &Problem) -> Result<Solution, Error> {
+        let mut bb = problem.builder(1).try_build()?;
+        let best = bb.brute_force(&problem.cocktail_sets())?;
+        Ok(problem.result(&bb, &best))
+    }
+}
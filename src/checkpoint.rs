@@ -0,0 +1,296 @@
+//! Saving a search to disk, so that it can be resumed after being interrupted
+//!
+//! A [checkpointing](crate::BranchBoundBuilder::checkpoint) search periodically writes
+//! its configuration, incumbents and per-cocktail caches, together with the frontier: the
+//! node being searched, and the exclude branch still pending at each of its ancestors.
+//! [`BranchBound::resume_from`] reads them back, and [`BranchBound::resume`] searches
+//! the frontier in the order the interrupted search would have.
+//!
+//! The observer, profile, statistics, branching strategy and seeded tie-breaking aren't
+//! saved, and a parallel search doesn't checkpoint.
+use crate::branching::MinAmortizedCost;
+use crate::constraints::{CategoryMaximum, CategoryMinimum, SoftLimit};
+use crate::frontier::SearchOrder;
+use crate::profile::Branch;
+use crate::stats::SearchStats;
+use crate::table::{CocktailSet, CocktailTable};
+use crate::{
+    consulted, tiered_order, Alternative, BranchBound, Error, IngredientSeti, Ingredienti,
+    SearchStatus, Unmet,
+};
+use rustc_hash::{FxHashMap, FxHashSet};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// Where to save checkpoints, and how often
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub(crate) struct Checkpointing {
+    pub(crate) path: PathBuf,
+    /// Save before every this many nodes
+    pub(crate) every: u32,
+}
+
+/// A node of the search tree yet to be explored: what [`BranchBound::search`] would be
+/// called with to explore it, and where it is in the tree. See [`step`](crate::step)
+///
+/// Its candidates and forbidden cocktails are numbered by the search that made it, so
+/// it only makes sense to that search, or one [resumed](BranchBound::resume_from) from
+/// its checkpoint
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Node {
+    pub(crate) candidates: CocktailSet,
+    pub(crate) partial: FxHashSet<IngredientSeti>,
+    /// The ingredients the cocktails in `partial` need, along with the required ones,
+    /// passed down rather than worked out again at every node
+    pub(crate) partial_ingredients: IngredientSeti,
+    pub(crate) forbidden: CocktailSet,
+    /// Ingredients bought by branching on them, whether or not the cocktails in
+    /// `partial` use them yet
+    pub(crate) bought: IngredientSeti,
+    pub(crate) depth: usize,
+    /// How many exclude branches lead here from the root: how often the path deviates
+    /// from the branching strategy's choice
+    pub(crate) discrepancies: usize,
+    pub(crate) branch: Branch,
+}
+
+impl Node {
+    /// The cocktails made on the way here
+    #[must_use]
+    pub fn partial(&self) -> &FxHashSet<IngredientSeti> {
+        &self.partial
+    }
+
+    /// How many levels below the initial call this node is
+    #[must_use]
+    pub fn depth(&self) -> usize {
+        self.depth
+    }
+
+    /// Which branch of its parent this node is
+    #[must_use]
+    pub fn branch(&self) -> Branch {
+        self.branch
+    }
+}
+
+/// Everything needed to carry on a search
+#[derive(Serialize, Deserialize)]
+pub(crate) struct Checkpoint {
+    calls: i32,
+    max_size: usize,
+    highest_score: usize,
+    highest: FxHashSet<IngredientSeti>,
+    highest_ingredients: IngredientSeti,
+    pub(crate) counter: u32,
+    min_cover: FxHashMap<IngredientSeti, i32>,
+    min_amortized_cost: FxHashMap<IngredientSeti, f64>,
+    category_minimums: Vec<CategoryMinimum>,
+    category_maximums: Vec<CategoryMaximum>,
+    soft_limits: Vec<SoftLimit>,
+    max_depth: Option<usize>,
+    depth_truncations: u32,
+    open_bound: usize,
+    epsilon: f64,
+    required: IngredientSeti,
+    owned: IngredientSeti,
+    excluded: IngredientSeti,
+    forbidden_combinations: Vec<IngredientSeti>,
+    variants: FxHashMap<IngredientSeti, usize>,
+    house: FxHashSet<IngredientSeti>,
+    house_weight: usize,
+    weights: FxHashMap<IngredientSeti, usize>,
+    budget: Option<f64>,
+    prices: FxHashMap<Ingredienti, f64>,
+    time_limit: Option<Duration>,
+    top_k: usize,
+    alternatives: Vec<Alternative>,
+    max_ties: usize,
+    ties: Vec<Alternative>,
+    pub(crate) checkpointing: Option<Checkpointing>,
+    /// Stacked, so the next node to search is last
+    frontier: Vec<Node>,
+    /// What the frontier's nodes number their cocktails by
+    cocktails: CocktailTable,
+}
+
+impl BranchBound {
+    /// Save the search, about to expand `current`, to the checkpoint file. A failure is
+    /// kept in [`checkpoint_error`](Self::checkpoint_error) rather than ending the search
+    pub(crate) fn save_checkpoint(&mut self, current: Node) {
+        let Some(path) = self
+            .checkpointing
+            .as_ref()
+            .map(|checkpointing| checkpointing.path.clone())
+        else {
+            return;
+        };
+        let mut frontier = self.frontier.clone();
+        frontier.push(current);
+        if let Err(e) = write(&path, &self.checkpoint(frontier)) {
+            self.checkpoint_error = Some(e);
+        }
+    }
+
+    /// Everything needed to carry on the search from `frontier`
+    pub(crate) fn checkpoint(&self, frontier: Vec<Node>) -> Checkpoint {
+        Checkpoint {
+            calls: self.calls,
+            max_size: self.max_size,
+            highest_score: self.highest_score,
+            highest: self.highest.clone(),
+            highest_ingredients: self.highest_ingredients.clone(),
+            counter: self.counter,
+            min_cover: self.min_cover.clone(),
+            min_amortized_cost: self.min_amortized_cost.clone(),
+            category_minimums: self.category_minimums.clone(),
+            category_maximums: self.category_maximums.clone(),
+            soft_limits: self.soft_limits.clone(),
+            max_depth: self.max_depth,
+            depth_truncations: self.depth_truncations,
+            open_bound: self.open_bound,
+            epsilon: self.epsilon,
+            required: self.required.clone(),
+            owned: self.owned.clone(),
+            excluded: self.excluded.clone(),
+            forbidden_combinations: self.forbidden_combinations.clone(),
+            variants: self.variants.clone(),
+            house: self.house.clone(),
+            house_weight: self.house_weight,
+            weights: self.weights.clone(),
+            budget: self.budget,
+            prices: self.prices.clone(),
+            time_limit: self.time_limit,
+            top_k: self.top_k,
+            alternatives: self.alternatives.clone(),
+            max_ties: self.max_ties,
+            ties: self.ties.clone(),
+            checkpointing: self.checkpointing.clone(),
+            frontier,
+            cocktails: (*self.cocktails).clone(),
+        }
+    }
+
+    /// Load a search saved by a [checkpointing](crate::BranchBoundBuilder::checkpoint)
+    /// search, ready to [`resume`](Self::resume). It goes on checkpointing to the same
+    /// file, with the calls it had left and a fresh time limit
+    pub fn resume_from(path: impl AsRef<Path>) -> Result<BranchBound, Error> {
+        read(path.as_ref()).map(BranchBound::from_checkpoint)
+    }
+
+    /// The search saved in `checkpoint`, ready to [`resume`](Self::resume)
+    pub(crate) fn from_checkpoint(checkpoint: Checkpoint) -> BranchBound {
+        BranchBound {
+            calls: checkpoint.calls,
+            max_size: checkpoint.max_size,
+            highest_score: checkpoint.highest_score,
+            highest: checkpoint.highest,
+            highest_ingredients: checkpoint.highest_ingredients,
+            random: None,
+            counter: checkpoint.counter,
+            min_cover: checkpoint.min_cover,
+            min_amortized_cost: checkpoint.min_amortized_cost,
+            initial: false,
+            category_minimums: checkpoint.category_minimums,
+            category_maximums: checkpoint.category_maximums,
+            soft_limits: checkpoint.soft_limits,
+            max_depth: checkpoint.max_depth,
+            depth: 0,
+            depth_truncations: checkpoint.depth_truncations,
+            open_bound: checkpoint.open_bound,
+            current_candidates: 0,
+            current_forbidden: 0,
+            epsilon: checkpoint.epsilon,
+            required: checkpoint.required,
+            owned: checkpoint.owned,
+            excluded: checkpoint.excluded,
+            forbidden_combinations: checkpoint.forbidden_combinations,
+            variants: checkpoint.variants,
+            house: checkpoint.house,
+            house_weight: checkpoint.house_weight,
+            weights: checkpoint.weights,
+            budget: checkpoint.budget,
+            prices: checkpoint.prices,
+            profile: None,
+            threads: None,
+            time_limit: checkpoint.time_limit,
+            deadline: None,
+            status: SearchStatus::Complete,
+            observer: None,
+            top_k: checkpoint.top_k,
+            alternatives: checkpoint.alternatives,
+            max_ties: checkpoint.max_ties,
+            ties: checkpoint.ties,
+            tally: None,
+            adaptive_bounds: false,
+            bound_order: tiered_order(),
+            consulted: consulted(None, false),
+            probing: false,
+            restarts: None,
+            tie_slack: 0.0,
+            checkpointing: checkpoint.checkpointing,
+            checkpoint_error: None,
+            frontier: checkpoint.frontier,
+            cocktails: Arc::new(checkpoint.cocktails),
+            unmet: Unmet::default(),
+            portfolio: None,
+            stop_at: None,
+            initial_solution: None,
+            transpositions: None,
+            subtrees: None,
+            open_subtrees: vec![],
+            discrepancy_limit: None,
+            discrepancy_cuts: 0,
+            stats: SearchStats::default(),
+            branching: Arc::new(MinAmortizedCost),
+            order: SearchOrder::DepthFirst,
+        }
+    }
+
+    /// Carry on a search that stopped early, or was [loaded](Self::resume_from) from a
+    /// checkpoint, returning the best cocktails found as [`search`](Self::search) does.
+    /// The time limit starts afresh, but a search that ran out of calls needs more
+    /// added to [`calls`](Self::calls) first
+    pub fn resume(&mut self) -> FxHashSet<IngredientSeti> {
+        self.deadline = self.time_limit.map(|limit| Instant::now() + limit);
+        self.stats.start();
+        self.status = SearchStatus::Complete;
+        self.depth_first(0)
+    }
+}
+
+/// Read what [`write`] wrote to `path`
+pub(crate) fn read<T: DeserializeOwned>(path: &Path) -> Result<T, Error> {
+    let f = File::open(path).map_err(|source| Error::Io {
+        path: path.to_path_buf(),
+        source,
+    })?;
+    Ok(bincode::deserialize_from(BufReader::new(f))?)
+}
+
+/// Write `checkpoint` to a temporary file beside `path`, then move it into place, so
+/// that an interrupted write leaves the previous checkpoint intact
+pub(crate) fn write(path: &Path, checkpoint: &impl Serialize) -> Result<(), Error> {
+    let mut tmp = path.as_os_str().to_owned();
+    tmp.push(".tmp");
+    let tmp = PathBuf::from(tmp);
+    let f = File::create(&tmp).map_err(|source| Error::Io {
+        path: tmp.clone(),
+        source,
+    })?;
+    let mut writer = BufWriter::new(f);
+    bincode::serialize_into(&mut writer, checkpoint)?;
+    writer.flush().map_err(|source| Error::Io {
+        path: tmp.clone(),
+        source,
+    })?;
+    std::fs::rename(&tmp, path).map_err(|source| Error::Io {
+        path: path.to_path_buf(),
+        source,
+    })
+}
@@ -0,0 +1,59 @@
+//! A single cocktail and everything known about it
+use crate::IngredientSeti;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+
+/// A named cocktail, with its ingredients encoded as ids
+///
+/// The ids are only meaningful alongside the [`IdMapper`](crate::mapper::IdMapper) that
+/// assigned them, e.g. the one owned by a [`Problem`](crate::problem::Problem).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Cocktail {
+    pub name: String,
+    pub ingredients: IngredientSeti,
+    /// Free-form labels, e.g. "tiki" or "iba"
+    #[serde(default)]
+    pub tags: BTreeSet<String>,
+    /// How much making this cocktail is worth, relative to others. 1 by default
+    #[serde(default = "default_weight")]
+    pub weight: f64,
+    #[serde(default)]
+    pub notes: Option<String>,
+    /// How to make it, as free text
+    #[serde(default)]
+    pub recipe: Option<String>,
+    /// Where the recipe came from, e.g. the files it was loaded from
+    #[serde(default)]
+    pub sources: BTreeSet<String>,
+}
+
+fn default_weight() -> f64 {
+    1.0
+}
+
+impl Cocktail {
+    /// An untagged cocktail of weight 1
+    #[must_use]
+    pub fn new(name: &str, ingredients: IngredientSeti) -> Cocktail {
+        Cocktail {
+            name: name.to_owned(),
+            ingredients,
+            tags: BTreeSet::new(),
+            weight: default_weight(),
+            notes: None,
+            recipe: None,
+            sources: BTreeSet::new(),
+        }
+    }
+
+    #[must_use]
+    pub fn has_tag(&self, tag: &str) -> bool {
+        self.tags.contains(tag)
+    }
+
+    /// Can this cocktail be made from `ingredients`?
+    #[must_use]
+    pub fn makeable_from(&self, ingredients: &IngredientSeti) -> bool {
+        self.ingredients.is_subset(ingredients)
+    }
+}
@@ -0,0 +1,230 @@
+//! Constraints on which ingredient sets are acceptable, beyond the ingredient budget
+use crate::{IngredientSeti, Ingredienti};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// Require at least `minimum` of an ingredient category (e.g. "at least one whiskey")
+///
+/// If the best cocktails don't happen to use enough of the category, the shortfall is made
+/// up from the remaining ingredient budget, so solutions satisfying the minimum never need
+/// more than `max_size` ingredients. Categories are assumed to be disjoint.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CategoryMinimum {
+    /// Used in error messages
+    pub category: String,
+    /// Every ingredient belonging to the category
+    pub ingredients: IngredientSeti,
+    pub minimum: usize,
+}
+
+impl CategoryMinimum {
+    /// How many more ingredients from this category `ingredients` would need
+    #[must_use]
+    pub fn deficit(&self, ingredients: &IngredientSeti) -> usize {
+        let present = self.ingredients.intersection(ingredients).count();
+        self.minimum.saturating_sub(present)
+    }
+
+    /// Ingredients from this category that could be added to `ingredients` to meet the minimum
+    pub fn padding<'a>(
+        &'a self,
+        ingredients: &'a IngredientSeti,
+    ) -> impl Iterator<Item = Ingredienti> + 'a {
+        self.ingredients
+            .difference(ingredients)
+            .copied()
+            .take(self.deficit(ingredients))
+    }
+}
+
+/// Allow at most `maximum` of an ingredient category (e.g. shelf space for four spirits)
+///
+/// Owned ingredients take up space too, as do any bought to meet a [`CategoryMinimum`]
+/// whose ingredients all belong to the category. Categories are assumed to be disjoint
+/// or nested.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CategoryMaximum {
+    /// Used in error messages
+    pub category: String,
+    /// Every ingredient belonging to the category
+    pub ingredients: IngredientSeti,
+    pub maximum: usize,
+}
+
+impl CategoryMaximum {
+    /// How many of `ingredients` belong to the category
+    #[must_use]
+    pub fn count(&self, ingredients: &IngredientSeti) -> usize {
+        self.ingredients.intersection(ingredients).count()
+    }
+
+    /// How many more of the category `ingredients` include than the maximum allows
+    #[must_use]
+    pub fn excess(&self, ingredients: &IngredientSeti) -> usize {
+        self.count(ingredients).saturating_sub(self.maximum)
+    }
+}
+
+/// Prefer at most `limit` of a set of ingredients (e.g. "no more than 2 vermouths"),
+/// without ruling more out
+///
+/// Each ingredient beyond the limit costs `penalty`, in the same units as
+/// [`score`](crate::BranchBound::score), so a set only goes over if the cocktails it
+/// gains are worth more. Adding ingredients never lowers a penalty, which is what lets
+/// the bounds treat a partial set's penalty as a floor for everything below it.
+/// Ingredients added only to meet a [`CategoryMinimum`] aren't counted.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SoftLimit {
+    /// Used in reports
+    pub category: String,
+    pub ingredients: IngredientSeti,
+    pub limit: usize,
+    /// The cost of each ingredient over the limit
+    pub penalty: usize,
+}
+
+impl SoftLimit {
+    /// How much `ingredients` are penalised for going over the limit
+    #[must_use]
+    pub fn penalty(&self, ingredients: &IngredientSeti) -> usize {
+        let present = self.ingredients.intersection(ingredients).count();
+        present.saturating_sub(self.limit) * self.penalty
+    }
+}
+
+/// A set of category minimums that no ingredient set within the budget can satisfy
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InfeasibleMinimums {
+    /// Human-readable descriptions of each conflicting constraint
+    pub conflicts: Vec<String>,
+}
+
+impl fmt::Display for InfeasibleMinimums {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "category minimums can't be satisfied: {}",
+            self.conflicts.join("; ")
+        )
+    }
+}
+
+impl std::error::Error for InfeasibleMinimums {}
+
+/// Check that `minimums` can all be satisfied by a set of at most `max_size` ingredients
+///
+/// Reports every category with fewer ingredients than its minimum, and, if the minimums
+/// add up to more than `max_size`, all the categories involved.
+pub fn check_minimums(
+    minimums: &[CategoryMinimum],
+    max_size: usize,
+) -> Result<(), InfeasibleMinimums> {
+    let mut conflicts = minimums
+        .iter()
+        .filter(|m| m.ingredients.len() < m.minimum)
+        .map(|m| {
+            format!(
+                "at least {} {} required, but only {} known",
+                m.minimum,
+                m.category,
+                m.ingredients.len()
+            )
+        })
+        .collect::<Vec<String>>();
+    let total = minimums.iter().map(|m| m.minimum).sum::<usize>();
+    if total > max_size {
+        let parts = minimums
+            .iter()
+            .filter(|m| m.minimum > 0)
+            .map(|m| format!("{} {}", m.minimum, m.category))
+            .collect::<Vec<String>>();
+        conflicts.push(format!(
+            "{} together need {total} ingredients, but only {max_size} are allowed",
+            parts.join(" + ")
+        ));
+    }
+    if conflicts.is_empty() {
+        Ok(())
+    } else {
+        Err(InfeasibleMinimums { conflicts })
+    }
+}
+
+/// Check that no category minimum needs more of a category than one of `maximums` allows
+///
+/// Only a minimum whose ingredients all fall within a maximum's category is compared
+/// with it, as that's the only way the two are bound to conflict.
+pub fn check_maximums(
+    minimums: &[CategoryMinimum],
+    maximums: &[CategoryMaximum],
+) -> Result<(), InfeasibleMinimums> {
+    let conflicts = maximums
+        .iter()
+        .flat_map(|maximum| {
+            minimums
+                .iter()
+                .filter(|minimum| {
+                    minimum.minimum > maximum.maximum
+                        && minimum.ingredients.is_subset(&maximum.ingredients)
+                })
+                .map(|minimum| {
+                    format!(
+                        "at least {} {} required, but at most {} {} allowed",
+                        minimum.minimum, minimum.category, maximum.maximum, maximum.category
+                    )
+                })
+        })
+        .collect::<Vec<String>>();
+    if conflicts.is_empty() {
+        Ok(())
+    } else {
+        Err(InfeasibleMinimums { conflicts })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn spirits(minimum: usize) -> CategoryMinimum {
+        CategoryMinimum {
+            category: "spirit".to_owned(),
+            ingredients: IngredientSeti::from([1, 2, 3]),
+            minimum,
+        }
+    }
+
+    #[test]
+    fn pads_a_shortfall_from_the_category() {
+        let minimum = spirits(2);
+        let ingredients = IngredientSeti::from([2, 7]);
+        assert_eq!(minimum.deficit(&ingredients), 1);
+        let padding = minimum.padding(&ingredients).collect::<IngredientSeti>();
+        assert_eq!(padding, IngredientSeti::from([1]));
+        let padded = &ingredients | &padding;
+        assert_eq!(minimum.deficit(&padded), 0);
+        assert_eq!(minimum.padding(&padded).count(), 0);
+    }
+
+    #[test]
+    fn rejects_unmeetable_minimums() {
+        assert!(check_minimums(&[spirits(3)], 3).is_ok());
+        // more than the category has
+        assert_eq!(
+            check_minimums(&[spirits(4)], 5)
+                .unwrap_err()
+                .conflicts
+                .len(),
+            1
+        );
+        // more than the budget allows, together
+        let juices = CategoryMinimum {
+            category: "juice".to_owned(),
+            ingredients: IngredientSeti::from([4, 5]),
+            minimum: 2,
+        };
+        let error = check_minimums(&[spirits(2), juices], 3).unwrap_err();
+        assert_eq!(error.conflicts.len(), 1);
+        assert!(error.conflicts[0].contains("2 spirit + 2 juice"));
+    }
+}
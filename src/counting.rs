@@ -0,0 +1,103 @@
+//! Counting the ingredient sets that make the most cocktails
+//!
+//! [`BranchBound::count_optima`] first searches for the best score as usual, then
+//! searches again, this time exploring every branch that could match the score rather
+//! than beat it. Each node scoring the optimum marks its ingredient set as one of the
+//! optima. Only a 128-bit fingerprint of each set is kept, so counting a great many
+//! takes little memory, and none of them is named.
+//!
+//! A set's cocktails are all those its ingredients make, since a branch leaving a
+//! cocktail out forbids making it, so a set is only ever reached with one score. Sets
+//! are the ingredients their cocktails need, with the required ones: a set making the
+//! optimum with room to spare counts once, not once for every ingredient it could be
+//! padded out with.
+use crate::{BranchBound, IngredientSeti};
+use rustc_hash::FxHashSet;
+use serde::Serialize;
+use std::collections::hash_map::RandomState;
+use std::collections::BTreeMap;
+use std::hash::BuildHasher;
+
+/// How many distinct ingredient sets make cocktails worth the optimum
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct OptimaCount {
+    /// What the best sets' cocktails [score](BranchBound::score)
+    pub score: usize,
+    /// How many sets need each number of ingredients, counting any needed to meet
+    /// category minimums
+    pub by_size: BTreeMap<usize, u64>,
+}
+
+impl OptimaCount {
+    /// How many sets there are, of any size
+    #[must_use]
+    pub fn total(&self) -> u64 {
+        self.by_size.values().sum()
+    }
+}
+
+/// The optimal ingredient sets counted so far
+#[derive(Debug, Clone)]
+pub(crate) struct Tally {
+    /// A pair of hashers, making up a 128-bit fingerprint between them
+    keys: [RandomState; 2],
+    seen: FxHashSet<u128>,
+    by_size: BTreeMap<usize, u64>,
+}
+
+impl Tally {
+    fn new() -> Tally {
+        Tally {
+            keys: [RandomState::new(), RandomState::new()],
+            seen: FxHashSet::default(),
+            by_size: BTreeMap::new(),
+        }
+    }
+
+    /// Count `ingredients`, needing `size` ingredients in all, unless already counted
+    pub(crate) fn add(&mut self, ingredients: &IngredientSeti, size: usize) {
+        let fingerprint = u128::from(self.keys[0].hash_one(ingredients)) << 64
+            | u128::from(self.keys[1].hash_one(ingredients));
+        if self.seen.insert(fingerprint) {
+            *self.by_size.entry(size).or_insert(0) += 1;
+        }
+    }
+}
+
+impl BranchBound {
+    /// Find the best score among `candidates`, then count the distinct ingredient sets
+    /// within the ingredient budget whose cocktails score as much
+    ///
+    /// Each of the two searches gets the full call budget, and the second is always
+    /// sequential. If either stops early the count may be short, and
+    /// [`status`](Self::status) is left as the first incomplete search's. Matching the
+    /// optimum leaves much less to prune than beating it, so the second search is
+    /// usually much the longer.
+    pub fn count_optima(&mut self, candidates: &FxHashSet<IngredientSeti>) -> OptimaCount {
+        let mut candidates = candidates.clone();
+        let mut partial = FxHashSet::default();
+        let mut forbidden = None;
+        if self.initial {
+            self.prepare(&mut candidates, &mut partial, &mut forbidden);
+        }
+        let calls = self.calls;
+        self.search_prepared(&candidates, &partial, &forbidden);
+        let status = self.status;
+        self.restart(self.max_size, calls);
+        let threads = self.threads.take();
+        self.tally = Some(Tally::new());
+        self.search_prepared(&candidates, &partial, &forbidden);
+        self.threads = threads;
+        if !status.completed() {
+            self.status = status;
+        }
+        OptimaCount {
+            score: self.highest_score,
+            by_size: self
+                .tally
+                .take()
+                .map(|tally| tally.by_size)
+                .unwrap_or_default(),
+        }
+    }
+}
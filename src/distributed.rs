@@ -0,0 +1,172 @@
+//! Splitting a search into work units that can be searched anywhere, and merging what
+//! they find
+//!
+//! [`BranchBound::split`] expands the search breadth-first from its root, fixing the
+//! first few include and exclude decisions, until there are enough nodes left to go
+//! round. Each becomes a [`WorkUnit`]: the node, together with the search's
+//! configuration, per-cocktail caches and incumbent, just as a
+//! [checkpoint](crate::checkpoint) holds them. The units' subtrees are disjoint and
+//! between them cover everything the split didn't rule out, so searching every unit and
+//! [merging](BranchBound::merge) the [`WorkResult`]s into the search that was split
+//! gives the same answer as searching it whole.
+//!
+//! Units and results can be [saved](WorkUnit::save) to a file and
+//! [loaded](WorkUnit::load) on another machine. Each unit starts with the incumbent
+//! found while splitting, and the calls and time limit the search had left, but the
+//! units don't share what they find while they run: one finding a good set can't help
+//! the others prune. What a checkpoint doesn't save, such as the observer and branching
+//! strategy, isn't in a unit either.
+//!
+//! ```
+//! use branchbound::{BranchBound, IngredientSeti};
+//! use rustc_hash::FxHashSet;
+//!
+//! let cocktails = FxHashSet::from_iter([
+//!     IngredientSeti::from([0, 1]),
+//!     IngredientSeti::from([1, 2]),
+//!     IngredientSeti::from([0, 2]),
+//!     IngredientSeti::from([3]),
+//! ]);
+//! let mut bb = BranchBound::new(1000, 3);
+//! let units = bb.split(&cocktails, 4);
+//! // on other machines
+//! let results = units.into_iter().map(|unit| unit.solve()).collect::<Vec<_>>();
+//! for result in &results {
+//!     bb.merge(result);
+//! }
+//! assert_eq!(bb.highest_score, 3);
+//! ```
+use crate::checkpoint::{read, write, Checkpoint};
+use crate::{Alternative, BranchBound, Error, IngredientSeti, SearchStatus};
+use rustc_hash::FxHashSet;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::path::Path;
+
+/// One of the disjoint subproblems a search was [split](BranchBound::split) into
+#[derive(Serialize, Deserialize)]
+pub struct WorkUnit(Checkpoint);
+
+impl WorkUnit {
+    /// Save the unit to `path`, to be [loaded](Self::load) elsewhere
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), Error> {
+        write(path.as_ref(), self)
+    }
+
+    /// Load a unit [saved](Self::save) to `path`
+    pub fn load(path: impl AsRef<Path>) -> Result<WorkUnit, Error> {
+        read(path.as_ref())
+    }
+
+    /// Search the unit's subtree, until it's exhausted or the search has to stop
+    #[must_use]
+    pub fn solve(self) -> WorkResult {
+        let nodes = self.0.counter;
+        let mut bb = BranchBound::from_checkpoint(self.0);
+        bb.resume();
+        WorkResult {
+            best: bb.best(),
+            alternatives: bb.alternatives.clone(),
+            status: bb.status,
+            upper_bound: bb.upper_bound(),
+            nodes: bb.counter - nodes,
+        }
+    }
+}
+
+/// What searching a [`WorkUnit`] found
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WorkResult {
+    /// The best set found, or the incumbent the unit started with if it found nothing
+    /// better
+    pub best: Alternative,
+    /// The best distinct sets found, when keeping the
+    /// [top k](crate::BranchBoundBuilder::with_top_k)
+    pub alternatives: Vec<Alternative>,
+    pub status: SearchStatus,
+    /// The highest score the unit couldn't rule out. See [`BranchBound::upper_bound`]
+    pub upper_bound: usize,
+    /// Nodes expanded searching the unit
+    pub nodes: u32,
+}
+
+impl WorkResult {
+    /// Save the result to `path`, to be [loaded](Self::load) elsewhere
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), Error> {
+        write(path.as_ref(), self)
+    }
+
+    /// Load a result [saved](Self::save) to `path`
+    pub fn load(path: impl AsRef<Path>) -> Result<WorkResult, Error> {
+        read(path.as_ref())
+    }
+}
+
+impl BranchBound {
+    /// Split the search for the best cocktails among `candidates` into at least
+    /// `units` [work units](WorkUnit), by expanding it breadth-first until that many
+    /// nodes are left. Fewer, if the search finishes or has to stop before then: none,
+    /// if it finishes, and the incumbent is then the answer. Once every unit's result
+    /// is [merged](Self::merge), this search's result is as if it had searched them
+    /// itself
+    pub fn split(&mut self, candidates: &FxHashSet<IngredientSeti>, units: usize) -> Vec<WorkUnit> {
+        let mut queue = VecDeque::from([self.root(candidates)]);
+        while queue.len() < units {
+            let Some(node) = queue.pop_front() else {
+                break;
+            };
+            let Some(expansion) = self.step(&node) else {
+                // out of calls or time, so hand over what's left as it is
+                queue.push_front(node);
+                break;
+            };
+            queue.extend(expansion.children);
+        }
+        queue
+            .into_iter()
+            .map(|node| {
+                let mut checkpoint = self.checkpoint(vec![node]);
+                // every unit would checkpoint to the same file
+                checkpoint.checkpointing = None;
+                WorkUnit(checkpoint)
+            })
+            .collect()
+    }
+
+    /// Fold what searching a [work unit](WorkUnit) split from this search found into
+    /// its result: adopting the unit's best set if it's better, and noting what the
+    /// unit left unexplored if it had to stop
+    pub fn merge(&mut self, result: &WorkResult) {
+        let best = &result.best;
+        let highest_len = self.padded_len(&self.highest_ingredients);
+        if self.improves(
+            best.score,
+            &best.ingredients,
+            self.highest_score,
+            highest_len,
+        ) {
+            self.highest.clone_from(&best.cocktails);
+            self.highest_score = best.score;
+            self.highest_ingredients.clone_from(&best.ingredients);
+        }
+        if self.top_k > 1 {
+            let mut alternatives = std::mem::take(&mut self.alternatives);
+            for alternative in &result.alternatives {
+                self.offer(
+                    &mut alternatives,
+                    alternative.score,
+                    &alternative.cocktails,
+                    &alternative.ingredients,
+                );
+            }
+            self.alternatives = alternatives;
+        }
+        if !result.status.completed() {
+            if self.status.completed() {
+                self.status = result.status;
+            }
+            self.open_bound = self.open_bound.max(result.upper_bound);
+        }
+        self.counter += result.nodes;
+    }
+}
@@ -0,0 +1,132 @@
+//! The crate-wide error type
+use crate::metadata::MetadataError;
+use crate::weights::LogError;
+use crate::ConfigError;
+use std::fmt;
+use std::path::PathBuf;
+
+/// Anything that can go wrong loading a problem or running a search
+#[derive(Debug)]
+pub enum Error {
+    /// A file couldn't be opened or created
+    Io {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    Csv(csv::Error),
+    /// A cocktail file row was unusable, e.g. because it had no name
+    Malformed {
+        /// 1-based
+        row: usize,
+        reason: String,
+    },
+    Json(serde_json::Error),
+    /// A [checkpoint](crate::checkpoint) couldn't be written or read
+    Checkpoint(bincode::Error),
+    Config(ConfigError),
+    Metadata(MetadataError),
+    Log(LogError),
+    /// The data passed to the search doesn't match what it was set up with, e.g. a
+    /// cocktail that wasn't among the candidates when the search started
+    Inconsistent(String),
+    /// Too many candidate ingredients for an [exhaustive search](crate::brute)
+    TooLarge {
+        ingredients: usize,
+        limit: usize,
+    },
+    /// The search uses something a solver can't handle, e.g. variant groups in an
+    /// [integer program](crate::ilp)
+    Unsupported(&'static str),
+    /// The [integer program](crate::ilp) solver failed, e.g. because the constraints
+    /// can't all be met
+    #[cfg(feature = "ilp")]
+    Ilp(microlp::Error),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Io { path, source } => write!(f, "{}: {source}", path.display()),
+            Error::Csv(e) => write!(f, "couldn't read cocktails: {e}"),
+            Error::Malformed { row, reason } => {
+                write!(f, "row {row} of the cocktail file is malformed: {reason}")
+            }
+            Error::Json(e) => write!(f, "invalid JSON: {e}"),
+            Error::Checkpoint(e) => write!(f, "bad checkpoint: {e}"),
+            Error::Config(e) => e.fmt(f),
+            Error::Metadata(e) => e.fmt(f),
+            Error::Log(e) => e.fmt(f),
+            Error::Inconsistent(reason) => write!(f, "inconsistent problem data: {reason}"),
+            Error::TooLarge { ingredients, limit } => write!(
+                f,
+                "{ingredients} candidate ingredients are too many to try every combination of; the most is {limit}"
+            ),
+            Error::Unsupported(what) => write!(f, "unsupported: {what}"),
+            #[cfg(feature = "ilp")]
+            Error::Ilp(e) => write!(f, "integer program solver failed: {e}"),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Io { source, .. } => Some(source),
+            Error::Csv(e) => Some(e),
+            Error::Json(e) => Some(e),
+            Error::Checkpoint(e) => Some(e),
+            Error::Config(e) => Some(e),
+            Error::Metadata(e) => Some(e),
+            Error::Log(e) => Some(e),
+            #[cfg(feature = "ilp")]
+            Error::Ilp(e) => Some(e),
+            Error::Malformed { .. }
+            | Error::Inconsistent(_)
+            | Error::TooLarge { .. }
+            | Error::Unsupported(_) => None,
+        }
+    }
+}
+
+impl From<csv::Error> for Error {
+    fn from(e: csv::Error) -> Self {
+        Error::Csv(e)
+    }
+}
+
+impl From<serde_json::Error> for Error {
+    fn from(e: serde_json::Error) -> Self {
+        Error::Json(e)
+    }
+}
+
+impl From<bincode::Error> for Error {
+    fn from(e: bincode::Error) -> Self {
+        Error::Checkpoint(e)
+    }
+}
+
+impl From<ConfigError> for Error {
+    fn from(e: ConfigError) -> Self {
+        Error::Config(e)
+    }
+}
+
+impl From<MetadataError> for Error {
+    fn from(e: MetadataError) -> Self {
+        Error::Metadata(e)
+    }
+}
+
+impl From<LogError> for Error {
+    fn from(e: LogError) -> Self {
+        Error::Log(e)
+    }
+}
+
+#[cfg(feature = "ilp")]
+impl From<microlp::Error> for Error {
+    fn from(e: microlp::Error) -> Self {
+        Error::Ilp(e)
+    }
+}
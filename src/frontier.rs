@@ -0,0 +1,205 @@
+//! Searching with an explicit frontier, in best-first or beam order
+//!
+//! [`BranchBound::search`] is depth-first, keeping the nodes still to explore on a stack.
+//! With a [`SearchOrder`] other than the default, it keeps them in a different frontier
+//! instead, and expands them in a different order:
+//!
+//! - [`BestFirst`](SearchOrder::BestFirst) always expands the node whose bound is
+//!   highest, so it finishes as soon as no node left can beat the incumbent. It's still
+//!   exact, but the frontier can grow much larger than the depth-first search's stack.
+//! - [`Beam`](SearchOrder::Beam) expands the tree a level at a time, keeping only the
+//!   most promising nodes of each level. It's approximate, but takes at most a beam's
+//!   width of nodes per level, so it copes with instances far too large to search
+//!   exactly. The highest bound of any node it drops is kept, so
+//!   [`gap`](BranchBound::gap) still says how far from optimal it may be.
+//! - [`LimitedDiscrepancy`](SearchOrder::LimitedDiscrepancy) searches depth-first, but
+//!   first only along the paths that always include the candidate the branching
+//!   strategy chooses, then along those excluding it at most once, then twice, and so
+//!   on, until a pass excludes nothing for being over the limit. The paths the strategy
+//!   favours are searched first, so good incumbents tend to turn up early, and the last
+//!   pass is exact. Nodes near the root are expanded once per pass.
+//!
+//! Checkpointing only applies to the depth-first search.
+use crate::checkpoint::Node;
+use crate::{BranchBound, IngredientSeti, SearchStatus};
+use rustc_hash::FxHashSet;
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+
+/// The order in which a search explores its nodes
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SearchOrder {
+    /// Include each candidate, then exclude it, recursively. Exact, and needs the least
+    /// memory
+    #[default]
+    DepthFirst,
+    /// Expand the node with the highest bound first. Exact
+    BestFirst,
+    /// Expand a level at a time, keeping at most this many nodes of each. Approximate
+    Beam(usize),
+    /// Depth-first, allowing ever more exclude branches on each path. Exact
+    LimitedDiscrepancy,
+}
+
+/// A frontier node, ordered by its bound, then by depth so that ties go deeper first,
+/// then by when it was queued so that the order doesn't depend on anything else
+struct Queued {
+    bound: usize,
+    sequence: u64,
+    node: Node,
+}
+
+impl Queued {
+    fn key(&self) -> (usize, usize, std::cmp::Reverse<u64>) {
+        (
+            self.bound,
+            self.node.depth,
+            std::cmp::Reverse(self.sequence),
+        )
+    }
+}
+
+impl PartialEq for Queued {
+    fn eq(&self, other: &Self) -> bool {
+        self.key() == other.key()
+    }
+}
+
+impl Eq for Queued {}
+
+impl PartialOrd for Queued {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Queued {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.key().cmp(&other.key())
+    }
+}
+
+impl BranchBound {
+    /// Search from the initial node, once it has been [prepared](BranchBound::prepare),
+    /// as a beam or with limited discrepancies if `order` says so, and best-first
+    /// otherwise
+    pub(crate) fn search_frontier(
+        &mut self,
+        order: SearchOrder,
+        candidates: &FxHashSet<IngredientSeti>,
+        partial: &FxHashSet<IngredientSeti>,
+        forbidden: FxHashSet<IngredientSeti>,
+    ) -> FxHashSet<IngredientSeti> {
+        let root = self.root_node(candidates, partial, &forbidden);
+        match order {
+            SearchOrder::Beam(width) => self.beam(root, width.max(1)),
+            SearchOrder::LimitedDiscrepancy => self.limited_discrepancy(root),
+            SearchOrder::DepthFirst | SearchOrder::BestFirst => self.best_first(root),
+        }
+    }
+
+    fn best_first(&mut self, root: Node) -> FxHashSet<IngredientSeti> {
+        let mut sequence = 0;
+        let mut frontier = BinaryHeap::new();
+        frontier.push(Queued {
+            bound: self.bound(&root),
+            sequence,
+            node: root,
+        });
+        while let Some(Queued { bound, node, .. }) = frontier.pop() {
+            let (bar_score, _) = self.bar(
+                self.highest_score,
+                &self.highest_ingredients,
+                &self.alternatives,
+                &self.ties,
+            );
+            // nothing left can even tie the incumbent
+            if bound < bar_score || self.reached_target() {
+                break;
+            }
+            let Some(children) = self.expand_node(&node) else {
+                let open = frontier.peek().map_or(0, |queued| queued.bound);
+                self.open_bound = self.open_bound.max(bound).max(open);
+                break;
+            };
+            for child in children {
+                sequence += 1;
+                frontier.push(Queued {
+                    bound: self.bound(&child),
+                    sequence,
+                    node: child,
+                });
+            }
+        }
+        self.highest.clone()
+    }
+
+    fn beam(&mut self, root: Node, width: usize) -> FxHashSet<IngredientSeti> {
+        let mut level = vec![root];
+        while !level.is_empty() && !self.reached_target() {
+            let mut next = vec![];
+            let mut nodes = level.into_iter();
+            for node in nodes.by_ref() {
+                match self.expand_node(&node) {
+                    Some(children) => next.extend(children),
+                    None => {
+                        let open = std::iter::once(&node)
+                            .chain(nodes.as_slice())
+                            .chain(&next)
+                            .map(|node| self.bound(node))
+                            .max()
+                            .unwrap_or(0);
+                        self.open_bound = self.open_bound.max(open);
+                        return self.highest.clone();
+                    }
+                }
+            }
+            let mut bounded = next
+                .into_iter()
+                .map(|node| (self.bound(&node), node))
+                .collect::<Vec<(usize, Node)>>();
+            // stable, so equally promising nodes stay in the order they were made
+            bounded.sort_by_key(|(bound, _)| std::cmp::Reverse(*bound));
+            if let Some(&(dropped, _)) = bounded.get(width) {
+                self.open_bound = self.open_bound.max(dropped);
+            }
+            bounded.truncate(width);
+            level = bounded.into_iter().map(|(_, node)| node).collect();
+        }
+        self.highest.clone()
+    }
+
+    /// Search depth-first from `root` allowing no exclude branches on any path, then
+    /// one, and so on, until a pass has nothing cut off by the limit or the search has
+    /// to stop. Each pass starts with the incumbents found by the last
+    fn limited_discrepancy(&mut self, root: Node) -> FxHashSet<IngredientSeti> {
+        for limit in 0.. {
+            self.discrepancy_limit = Some(limit);
+            self.discrepancy_cuts = 0;
+            self.open_bound = 0;
+            self.alternatives.clear();
+            self.ties.clear();
+            // what a pass saw below a node depends on how many discrepancies it had left
+            if let Some(table) = &mut self.transpositions {
+                table.clear();
+            }
+            self.frontier.push(root.clone());
+            self.depth_first(0);
+            if self.status != SearchStatus::Complete
+                || self.discrepancy_cuts == 0
+                || self.reached_target()
+            {
+                break;
+            }
+        }
+        self.discrepancy_limit = None;
+        self.highest.clone()
+    }
+
+    /// Has the incumbent reached the score the search is
+    /// [stopping at](BranchBound::min_ingredients_for)?
+    pub(crate) fn reached_target(&self) -> bool {
+        self.stop_at
+            .is_some_and(|target| self.highest_score >= target)
+    }
+}
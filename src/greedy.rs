@@ -0,0 +1,159 @@
+//! A fast greedy heuristic for a good, though not necessarily optimal, ingredient set
+//!
+//! This is the classic greedy algorithm for maximum coverage: starting from the required
+//! ingredients, repeatedly add the ingredient that completes the most valuable cocktails.
+//! A single ingredient often completes nothing on its own, so ties are broken by how much
+//! closer it brings the unfinished cocktails, each counting the reciprocal of the number
+//! of ingredients it's still missing. [`BranchBound::search`] starts from the greedy set,
+//! so that its bounds prune from the first node.
+//!
+//! The greedy set only bounds the optimum from below. Greedy maximum coverage is
+//! within a factor of 1 − 1/e of optimal when coverage is submodular, but a cocktail
+//! only counts once all of its ingredients are in, so adding an ingredient can be worth
+//! more after others than before them. With 4 ingredients, for instance, greedy makes
+//! 1 cocktail against an optimum of 2, under the 1.58 the factor would promise, so
+//! scaling the greedy value up can't serve as an upper bound either.
+use crate::{cmp_f64, Alternative, BranchBound, IngredientSeti};
+use rustc_hash::FxHashSet;
+use std::cmp::Ordering;
+
+impl BranchBound {
+    /// Build an ingredient set greedily from `candidates`, within the ingredient budget
+    /// and respecting the same constraints as the search, and report the cocktails it
+    /// makes
+    #[must_use]
+    pub fn greedy(&self, candidates: &FxHashSet<IngredientSeti>) -> Alternative {
+        let pool = candidates
+            .iter()
+            .flatten()
+            .copied()
+            .filter(|ingredient| !self.excluded.contains(ingredient))
+            .collect::<IngredientSeti>();
+        let mut ingredients = self.required.clone();
+        let mut made = self.made(candidates, &ingredients);
+        let mut score = self.score(&made);
+        loop {
+            let mut best = None;
+            for &ingredient in pool.difference(&ingredients) {
+                let mut extended = ingredients.clone();
+                extended.insert(ingredient);
+                if !self.admits(&extended) {
+                    continue;
+                }
+                let mut completed = made.clone();
+                let mut progress = 0.0;
+                for cocktail in candidates
+                    .iter()
+                    .filter(|cocktail| cocktail.contains(&ingredient) && !made.contains(*cocktail))
+                {
+                    let missing = cocktail.difference(&extended).count();
+                    if missing == 0 {
+                        completed.insert(cocktail.clone());
+                    } else {
+                        progress += 1.0 / missing as f64;
+                    }
+                }
+                let gain = (self.score(&completed), progress);
+                let better = best.as_ref().is_none_or(|(_, _, best_gain)| {
+                    let (best_score, best_progress) = *best_gain;
+                    gain.0.cmp(&best_score).then(cmp_f64(gain.1, best_progress))
+                        == Ordering::Greater
+                });
+                if better {
+                    best = Some((ingredient, completed, gain));
+                }
+            }
+            match best {
+                Some((ingredient, completed, gain)) if gain.0 > score || gain.1 > 0.0 => {
+                    ingredients.insert(ingredient);
+                    made = completed;
+                    score = gain.0;
+                }
+                _ => break,
+            }
+        }
+        // ingredients added in anticipation of cocktails that never got finished only
+        // lengthen the shopping list
+        Alternative {
+            score,
+            ingredients: self.ingredients(&made),
+            cocktails: made,
+        }
+    }
+
+    /// Take the [greedy](Self::greedy) set as the incumbent, if it's better
+    pub(crate) fn seed_greedy(&mut self, candidates: &FxHashSet<IngredientSeti>) {
+        let greedy = self.greedy(candidates);
+        let highest_len = self.padded_len(&self.highest_ingredients);
+        if self.improves(
+            greedy.score,
+            &greedy.ingredients,
+            self.highest_score,
+            highest_len,
+        ) {
+            self.stats
+                .improved(greedy.score, greedy.ingredients.len(), self.counter);
+            self.observe(|observer| observer.on_new_incumbent(greedy.score, &greedy.ingredients));
+            self.highest = greedy.cocktails;
+            self.highest_score = greedy.score;
+            self.highest_ingredients = greedy.ingredients;
+        }
+    }
+
+    /// Take the cocktails among `candidates` that `initial` makes as the incumbent, if
+    /// they're better. See
+    /// [`with_initial_solution`](crate::BranchBoundBuilder::with_initial_solution)
+    pub(crate) fn seed_initial(
+        &mut self,
+        candidates: &FxHashSet<IngredientSeti>,
+        initial: &IngredientSeti,
+    ) {
+        let cocktails = self.made(candidates, &(initial | &self.required));
+        let score = self.score(&cocktails);
+        let ingredients = self.ingredients(&cocktails);
+        let highest_len = self.padded_len(&self.highest_ingredients);
+        if self.improves(score, &ingredients, self.highest_score, highest_len) {
+            self.stats.improved(score, ingredients.len(), self.counter);
+            self.observe(|observer| observer.on_new_incumbent(score, &ingredients));
+            self.highest = cocktails;
+            self.highest_score = score;
+            self.highest_ingredients = ingredients;
+        }
+    }
+
+    /// Settle for the [greedy](Self::greedy) set rather than searching, returning the
+    /// cocktails it makes as [`search`](Self::search) would. How far it may be from
+    /// optimal is given by [`gap`](Self::gap)
+    pub fn greedy_search(
+        &mut self,
+        candidates: &FxHashSet<IngredientSeti>,
+    ) -> FxHashSet<IngredientSeti> {
+        let mut candidates = candidates.clone();
+        let mut partial = FxHashSet::default();
+        if self.initial {
+            // which seeds the incumbent
+            self.prepare(&mut candidates, &mut partial, &mut None);
+        } else {
+            self.seed_greedy(&candidates);
+        }
+        let candidates = candidates.iter().collect::<Vec<&IngredientSeti>>();
+        let partial_ingredients = self.ingredients(&partial);
+        self.open_bound =
+            self.open_bound
+                .max(self.node_bound(&candidates, &partial, &partial_ingredients));
+        self.highest.clone()
+    }
+
+    /// The cocktails among `candidates` that `ingredients` make
+    fn made(
+        &self,
+        candidates: &FxHashSet<IngredientSeti>,
+        ingredients: &IngredientSeti,
+    ) -> FxHashSet<IngredientSeti> {
+        candidates
+            .iter()
+            .filter(|cocktail| cocktail.is_subset(ingredients))
+            .cloned()
+            .collect()
+    }
+}
@@ -0,0 +1,450 @@
+//! The problem as an integer program, to check the search against
+//!
+//! [`BranchBound::integer_program`] states the search for the best cocktails as a
+//! linear integer program: a binary variable for each candidate ingredient that isn't
+//! required, 1 if it's bought, and one for each candidate cocktail, 1 if it's made,
+//! which it can only be if every ingredient it needs is bought or required. An integer
+//! variable for each [category minimum](crate::BranchBoundBuilder::category_minimum)
+//! counts the ingredients bought only to meet it. Category maximums, forbidden
+//! combinations and the spend budget become constraints on the bought ingredients, and
+//! the objective maximises what the cocktails made are worth, less one for each
+//! ingredient bought, weighted so that no number of ingredients outweighs a single
+//! point of score: the search's tie-break, as a single objective.
+//!
+//! With the `ilp` feature, `Ilp` solves the program with microlp, a mixed-integer
+//! solver in pure Rust, so no system libraries are needed. Its answer is proven
+//! optimal independently of the search's bounds, which makes it a cross-check on them
+//! for problems too large to [try every combination](crate::brute) of. Without it, the
+//! program can still be [written](IntegerProgram::write_lp) in LP or MPS format, e.g.
+//! by [`Problem::export_lp`](crate::problem::Problem::export_lp), for another solver
+//! to read.
+//!
+//! [Variant groups](crate::BranchBoundBuilder::variant_group) and
+//! [soft limits](crate::BranchBoundBuilder::soft_limit) aren't linear in these
+//! variables, so a problem using either is refused.
+#[cfg(feature = "ilp")]
+use crate::{problem::Problem, solution::Solution, solver::Solver};
+use crate::{BranchBound, Error, IngredientSeti, Ingredienti, COST_TOLERANCE};
+use rustc_hash::FxHashSet;
+use std::io::{self, Write};
+
+/// A variable of an [`IntegerProgram`], taking integer values from 0 to `upper`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Variable {
+    pub name: String,
+    pub upper: usize,
+    /// Its coefficient in the objective, which is maximised
+    pub objective: f64,
+}
+
+/// Which way a [`Constraint`] bounds its sum
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Sense {
+    AtMost,
+    AtLeast,
+}
+
+/// A linear constraint: the sum of each variable, by its index, times its coefficient
+/// is at most, or at least, `rhs`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Constraint {
+    pub name: String,
+    pub terms: Vec<(usize, f64)>,
+    pub sense: Sense,
+    pub rhs: f64,
+}
+
+/// A search for the best cocktails, as a linear integer program
+#[derive(Debug, Clone, PartialEq)]
+pub struct IntegerProgram {
+    /// The ingredient variables, then the cocktail variables, then one for each
+    /// category minimum
+    pub variables: Vec<Variable>,
+    pub constraints: Vec<Constraint>,
+    /// The ingredient each of the first variables stands for
+    ingredients: Vec<Ingredienti>,
+    /// The cocktail each of the next stands for
+    cocktails: Vec<IngredientSeti>,
+    /// Cocktails the required ingredients make alone, which have no variable
+    partial: FxHashSet<IngredientSeti>,
+}
+
+impl IntegerProgram {
+    /// The ingredient the variable at `index` stands for, if it's an ingredient
+    /// variable: 1 if the ingredient is bought
+    #[must_use]
+    pub fn ingredient(&self, index: usize) -> Option<Ingredienti> {
+        self.ingredients.get(index).copied()
+    }
+
+    /// The cocktail the variable at `index` stands for, if it's a cocktail variable: 1
+    /// if the cocktail is made
+    #[must_use]
+    pub fn cocktail(&self, index: usize) -> Option<&IngredientSeti> {
+        index
+            .checked_sub(self.ingredients.len())
+            .and_then(|index| self.cocktails.get(index))
+    }
+
+    /// Write the program in CPLEX LP format, after `comments`, one per line
+    pub fn write_lp(&self, mut writer: impl Write, comments: &[String]) -> io::Result<()> {
+        for comment in comments {
+            writeln!(writer, "\\ {comment}")?;
+        }
+        writeln!(writer, "Maximize")?;
+        write!(writer, " obj:")?;
+        let objective = self
+            .variables
+            .iter()
+            .enumerate()
+            .map(|(index, variable)| (index, variable.objective))
+            .collect::<Vec<(usize, f64)>>();
+        self.write_terms(&mut writer, &objective)?;
+        writeln!(writer)?;
+        writeln!(writer, "Subject To")?;
+        for constraint in &self.constraints {
+            write!(writer, " {}:", constraint.name)?;
+            self.write_terms(&mut writer, &constraint.terms)?;
+            let sense = match constraint.sense {
+                Sense::AtMost => "<=",
+                Sense::AtLeast => ">=",
+            };
+            writeln!(writer, " {sense} {}", constraint.rhs)?;
+        }
+        writeln!(writer, "Bounds")?;
+        for variable in self.variables.iter().filter(|variable| variable.upper != 1) {
+            writeln!(writer, " 0 <= {} <= {}", variable.name, variable.upper)?;
+        }
+        for (section, binary) in [("Binaries", true), ("Generals", false)] {
+            writeln!(writer, "{section}")?;
+            for variable in &self.variables {
+                if (variable.upper == 1) == binary {
+                    writeln!(writer, " {}", variable.name)?;
+                }
+            }
+        }
+        writeln!(writer, "End")
+    }
+
+    /// Write `terms` as a sum, a few to a line
+    fn write_terms(&self, writer: &mut impl Write, terms: &[(usize, f64)]) -> io::Result<()> {
+        for (position, &(index, coefficient)) in terms.iter().enumerate() {
+            if position > 0 && position % 8 == 0 {
+                write!(writer, "\n ")?;
+            }
+            let sign = if coefficient < 0.0 { '-' } else { '+' };
+            let name = &self.variables[index].name;
+            match coefficient.abs() {
+                1.0 => write!(writer, " {sign} {name}")?,
+                magnitude => write!(writer, " {sign} {magnitude} {name}")?,
+            }
+        }
+        Ok(())
+    }
+
+    /// Write the program in free MPS format, after `comments`, one per line. The
+    /// objective is maximised, which `OBJSENSE` says
+    pub fn write_mps(&self, mut writer: impl Write, comments: &[String]) -> io::Result<()> {
+        for comment in comments {
+            writeln!(writer, "* {comment}")?;
+        }
+        writeln!(writer, "NAME cocktails")?;
+        writeln!(writer, "OBJSENSE")?;
+        writeln!(writer, "    MAX")?;
+        writeln!(writer, "ROWS")?;
+        writeln!(writer, " N obj")?;
+        // each variable's coefficients, by constraint
+        let mut columns = vec![Vec::new(); self.variables.len()];
+        for constraint in &self.constraints {
+            let sense = match constraint.sense {
+                Sense::AtMost => 'L',
+                Sense::AtLeast => 'G',
+            };
+            writeln!(writer, " {sense} {}", constraint.name)?;
+            for &(index, coefficient) in &constraint.terms {
+                columns[index].push((&constraint.name, coefficient));
+            }
+        }
+        writeln!(writer, "COLUMNS")?;
+        writeln!(writer, "    MARKER 'MARKER' 'INTORG'")?;
+        for (variable, column) in self.variables.iter().zip(&columns) {
+            writeln!(writer, "    {} obj {}", variable.name, variable.objective)?;
+            for (row, coefficient) in column {
+                writeln!(writer, "    {} {row} {coefficient}", variable.name)?;
+            }
+        }
+        writeln!(writer, "    MARKER 'MARKER' 'INTEND'")?;
+        writeln!(writer, "RHS")?;
+        for constraint in &self.constraints {
+            writeln!(writer, "    RHS {} {}", constraint.name, constraint.rhs)?;
+        }
+        writeln!(writer, "BOUNDS")?;
+        for variable in &self.variables {
+            match variable.upper {
+                1 => writeln!(writer, " BV BND {}", variable.name)?,
+                upper => writeln!(writer, " UP BND {} {upper}", variable.name)?,
+            }
+        }
+        writeln!(writer, "ENDATA")
+    }
+}
+
+#[cfg(feature = "ilp")]
+impl IntegerProgram {
+    /// The cocktails made, and the ingredients needed, when each variable takes the
+    /// value `values` gives for its index
+    fn decode(
+        &self,
+        bb: &BranchBound,
+        values: impl Fn(usize) -> f64,
+    ) -> (FxHashSet<IngredientSeti>, IngredientSeti) {
+        let mut ingredients = bb.required.clone();
+        ingredients.extend(
+            self.ingredients
+                .iter()
+                .enumerate()
+                .filter(|&(index, _)| values(index) > 0.5)
+                .map(|(_, &ingredient)| ingredient),
+        );
+        let mut made = self.partial.clone();
+        made.extend(
+            self.cocktails
+                .iter()
+                .filter(|cocktail| cocktail.is_subset(&ingredients))
+                .cloned(),
+        );
+        (made, ingredients)
+    }
+}
+
+impl BranchBound {
+    /// The search for the best cocktails among `candidates` as an
+    /// [integer program](crate::ilp). The first time, this prepares the search as
+    /// [`search`](Self::search) would. Fails if the search uses variant groups or soft
+    /// limits
+    pub fn integer_program(
+        &mut self,
+        candidates: &FxHashSet<IngredientSeti>,
+    ) -> Result<IntegerProgram, Error> {
+        if !self.variants.is_empty() {
+            return Err(Error::Unsupported("variant groups in an integer program"));
+        }
+        if !self.soft_limits.is_empty() {
+            return Err(Error::Unsupported("soft limits in an integer program"));
+        }
+        let mut candidates = candidates.clone();
+        let mut partial = FxHashSet::default();
+        if self.initial {
+            self.prepare(&mut candidates, &mut partial, &mut None);
+        }
+        let pool = candidates
+            .iter()
+            .flatten()
+            .copied()
+            .filter(|ingredient| !self.required.contains(ingredient))
+            .collect::<IngredientSeti>()
+            .into_iter()
+            .collect::<Vec<Ingredienti>>();
+        let mut cocktails = candidates.into_iter().collect::<Vec<IngredientSeti>>();
+        // so that the same problem always numbers its variables the same way
+        cocktails.sort_unstable();
+        // no number of ingredients is worth a point of score
+        let weight = (pool.len()
+            + self
+                .category_minimums
+                .iter()
+                .map(|minimum| minimum.minimum)
+                .sum::<usize>()
+            + 1) as f64;
+        let mut variables = pool
+            .iter()
+            .map(|ingredient| Variable {
+                name: format!("buy_{ingredient}"),
+                upper: 1,
+                objective: -1.0,
+            })
+            .collect::<Vec<Variable>>();
+        variables.extend(
+            cocktails
+                .iter()
+                .enumerate()
+                .map(|(index, cocktail)| Variable {
+                    name: format!("make_{index}"),
+                    upper: 1,
+                    objective: weight * self.value(cocktail) as f64,
+                }),
+        );
+        let padding = variables.len();
+        variables.extend(
+            self.category_minimums
+                .iter()
+                .enumerate()
+                .map(|(index, minimum)| Variable {
+                    name: format!("pad_{index}"),
+                    upper: minimum.minimum,
+                    objective: -1.0,
+                }),
+        );
+        // the ingredient variables among `ingredients`, each with coefficient 1
+        let bought = |ingredients: &IngredientSeti| {
+            pool.iter()
+                .enumerate()
+                .filter(|(_, ingredient)| ingredients.contains(ingredient))
+                .map(|(index, _)| (index, 1.0))
+                .collect::<Vec<(usize, f64)>>()
+        };
+        let mut constraints = Vec::new();
+        for (index, cocktail) in cocktails.iter().enumerate() {
+            for (ingredient, _) in bought(cocktail) {
+                constraints.push(Constraint {
+                    name: format!("needs_{index}_{}", pool[ingredient]),
+                    terms: vec![(pool.len() + index, 1.0), (ingredient, -1.0)],
+                    sense: Sense::AtMost,
+                    rhs: 0.0,
+                });
+            }
+        }
+        constraints.push(Constraint {
+            name: "size".to_string(),
+            terms: (0..pool.len())
+                .chain(padding..variables.len())
+                .map(|index| (index, 1.0))
+                .collect(),
+            sense: Sense::AtMost,
+            rhs: self.max_size as f64 - self.bought(&self.required) as f64,
+        });
+        for (index, minimum) in self.category_minimums.iter().enumerate() {
+            let mut terms = bought(&minimum.ingredients);
+            terms.push((padding + index, 1.0));
+            constraints.push(Constraint {
+                name: format!("minimum_{index}"),
+                terms,
+                sense: Sense::AtLeast,
+                rhs: minimum.deficit(&self.required) as f64,
+            });
+        }
+        for (index, maximum) in self.category_maximums.iter().enumerate() {
+            let mut terms = bought(&maximum.ingredients);
+            // padding for a minimum within the category can only come from the category
+            terms.extend(
+                self.category_minimums
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, minimum)| minimum.ingredients.is_subset(&maximum.ingredients))
+                    .map(|(minimum, _)| (padding + minimum, 1.0)),
+            );
+            constraints.push(Constraint {
+                name: format!("maximum_{index}"),
+                terms,
+                sense: Sense::AtMost,
+                rhs: maximum.maximum as f64 - maximum.count(&self.required) as f64,
+            });
+        }
+        for (index, combination) in self.forbidden_combinations.iter().enumerate() {
+            let terms = bought(combination);
+            // a combination of required ingredients alone is refused when building
+            if terms.is_empty() {
+                continue;
+            }
+            let rhs = terms.len() as f64 - 1.0;
+            constraints.push(Constraint {
+                name: format!("forbidden_{index}"),
+                terms,
+                sense: Sense::AtMost,
+                rhs,
+            });
+        }
+        if let Some(budget) = self.budget {
+            constraints.push(Constraint {
+                name: "spend".to_string(),
+                terms: pool
+                    .iter()
+                    .enumerate()
+                    .filter_map(|(index, ingredient)| {
+                        self.prices.get(ingredient).map(|&price| (index, price))
+                    })
+                    .collect(),
+                sense: Sense::AtMost,
+                rhs: budget + COST_TOLERANCE - self.cost(self.required.iter()),
+            });
+        }
+        // constant, and already checked when building
+        constraints.retain(|constraint| !constraint.terms.is_empty());
+        Ok(IntegerProgram {
+            variables,
+            constraints,
+            ingredients: pool,
+            cocktails,
+            partial,
+        })
+    }
+
+    /// Find the best cocktails among `candidates` by solving the search's
+    /// [integer program](Self::integer_program), returning them as
+    /// [`search`](Self::search) would
+    #[cfg(feature = "ilp")]
+    pub fn solve_integer_program(
+        &mut self,
+        candidates: &FxHashSet<IngredientSeti>,
+    ) -> Result<FxHashSet<IngredientSeti>, Error> {
+        use microlp::{ComparisonOp, OptimizationDirection};
+
+        let program = self.integer_program(candidates)?;
+        let mut problem = microlp::Problem::new(OptimizationDirection::Maximize);
+        let variables = program
+            .variables
+            .iter()
+            .map(|variable| {
+                let upper = i32::try_from(variable.upper).unwrap_or(i32::MAX);
+                problem.add_integer_var(variable.objective, (0, upper))
+            })
+            .collect::<Vec<microlp::Variable>>();
+        for constraint in &program.constraints {
+            let op = match constraint.sense {
+                Sense::AtMost => ComparisonOp::Le,
+                Sense::AtLeast => ComparisonOp::Ge,
+            };
+            problem.add_constraint(
+                constraint
+                    .terms
+                    .iter()
+                    .map(|&(index, coefficient)| (variables[index], coefficient)),
+                op,
+                constraint.rhs,
+            );
+        }
+        let solution = problem.solve()?;
+        let (made, ingredients) =
+            program.decode(self, |index| *solution.var_value(variables[index]));
+        let score = self.score(&made);
+        // ingredients making nothing only lengthen the shopping list
+        let trimmed = self.ingredients(&made);
+        let ingredients = if self.admits(&trimmed) {
+            trimmed
+        } else {
+            ingredients
+        };
+        let highest_len = self.padded_len(&self.highest_ingredients);
+        if self.improves(score, &ingredients, self.highest_score, highest_len) {
+            self.highest = made;
+            self.highest_score = score;
+            self.highest_ingredients = ingredients;
+        }
+        Ok(self.highest.clone())
+    }
+}
+
+/// A [`Solver`] solving the problem as an [integer program](crate::ilp): proven
+/// optimal, though the time it takes is hard to predict
+#[cfg(feature = "ilp")]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Ilp;
+
+#[cfg(feature = "ilp")]
+impl Solver for Ilp {
+    fn solve(&mut self, problem: &Problem) -> Result<Solution, Error> {
+        let mut bb = problem.builder(1).try_build()?;
+        let best = bb.solve_integer_program(&problem.cocktail_sets())?;
+        Ok(problem.result(&bb, &best))
+    }
+}
@@ -0,0 +1,131 @@
+//! Solving again after the dataset changes a little
+//!
+//! A [`Solution`] names its ingredients and cocktails, so it still means something
+//! after cocktails or ingredients are added to or removed from the dataset.
+//! [`Problem::revalidate`] checks it against the problem as it is now, and repairs it
+//! by dropping the ingredients that are gone or excluded. [`Problem::resolve`] then
+//! searches again, [starting from](crate::BranchBoundBuilder::with_initial_solution)
+//! the repaired solution. After a small change the old best is usually close to the
+//! new one, so most of the tree is pruned straight away.
+use crate::problem::{Problem, Violation};
+use crate::solution::Solution;
+use crate::{Error, IngredientSeti, Ingredienti};
+
+/// How a previous solution fares against the problem as it is now
+#[derive(Debug, Clone, PartialEq)]
+pub struct Revalidation {
+    /// Cocktails its ingredients make now that it didn't list
+    pub gained: Vec<String>,
+    /// What's wrong with it now, e.g. ingredients or cocktails that have been removed
+    pub violations: Vec<Violation>,
+    /// Its ingredients that are still usable, with the required ones, and the cocktails
+    /// they make. `None` if that still isn't a valid solution
+    pub repaired: Option<Solution>,
+}
+
+impl Revalidation {
+    /// Is the previous solution exactly as good as it was?
+    #[must_use]
+    pub fn unchanged(&self) -> bool {
+        self.gained.is_empty() && self.violations.is_empty()
+    }
+}
+
+impl Problem {
+    /// Check `previous`, found for an earlier version of this problem, against the
+    /// problem as it is now, and repair it if it can be
+    #[must_use]
+    pub fn revalidate(&self, previous: &Solution) -> Revalidation {
+        let violations = self.validate(previous).err().unwrap_or_default();
+        let mut ingredients = previous
+            .ingredients
+            .iter()
+            .filter_map(|name| self.mapper().id(name))
+            .filter(|id| !self.excluded().contains(id))
+            .collect::<IngredientSeti>();
+        ingredients.extend(self.required().iter().copied());
+        let made = self
+            .cocktail_sets()
+            .into_iter()
+            .filter(|cocktail| cocktail.is_subset(&ingredients))
+            .collect();
+        let repaired = self.solution(&made, &ingredients);
+        let gained = repaired
+            .cocktails
+            .iter()
+            .filter(|name| !previous.cocktails.contains(name))
+            .cloned()
+            .collect();
+        Revalidation {
+            gained,
+            violations,
+            repaired: self.validate(&repaired).is_ok().then_some(repaired),
+        }
+    }
+
+    /// Find the best solution again after the dataset has changed, starting from the
+    /// [repaired](Self::revalidate) `previous` solution if there is one, and from
+    /// scratch otherwise
+    pub fn resolve(
+        &self,
+        previous: &Solution,
+        max_calls: i32,
+    ) -> Result<(Revalidation, Solution), Error> {
+        let revalidation = self.revalidate(previous);
+        let mut builder = self.builder(max_calls);
+        if let Some(repaired) = &revalidation.repaired {
+            let ingredients = repaired
+                .ingredients
+                .iter()
+                .filter_map(|name| self.mapper().id(name))
+                .collect::<Vec<Ingredienti>>();
+            builder = builder.with_initial_solution(&ingredients);
+        }
+        let mut bb = builder.try_build()?;
+        let solution = self.search(&mut bb)?;
+        Ok((revalidation, solution))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn names(names: &[&str]) -> Vec<String> {
+        names.iter().map(|&name| name.to_owned()).collect()
+    }
+
+    #[test]
+    fn repairs_and_resolves_after_a_change() {
+        let previous = Solution {
+            ingredients: names(&["campari", "gin", "lime", "vermouth"]),
+            cocktails: names(&["Gimlet", "Negroni"]),
+            ..Solution::default()
+        };
+        // the Negroni, and with it the campari and vermouth, are gone, and a cocktail
+        // needing only lime has been added
+        let mut problem = Problem::new(4);
+        problem.add_cocktail("Gimlet", ["gin", "lime"]);
+        problem.add_cocktail("Daiquiri", ["rum", "lime", "sugar"]);
+        problem.add_cocktail("Lime Shot", ["lime"]);
+        let revalidation = problem.revalidate(&previous);
+        assert_eq!(revalidation.violations.len(), 3);
+        for violation in [
+            Violation::UnknownIngredient("campari".to_owned()),
+            Violation::UnknownIngredient("vermouth".to_owned()),
+            Violation::UnknownCocktail("Negroni".to_owned()),
+        ] {
+            assert!(revalidation.violations.contains(&violation));
+        }
+        assert_eq!(revalidation.gained, ["Lime Shot"]);
+        let repaired = revalidation.repaired.unwrap();
+        assert_eq!(repaired.ingredients, ["gin", "lime"]);
+        assert_eq!(repaired.cocktails, ["Gimlet", "Lime Shot"]);
+
+        let (_, resolved) = problem.resolve(&previous, 10_000).unwrap();
+        let fresh = problem.solve(10_000).unwrap();
+        assert_eq!(resolved.ingredients, fresh.ingredients);
+        assert_eq!(resolved.cocktails, fresh.cocktails);
+        assert_eq!(resolved.cocktails, ["Daiquiri", "Gimlet", "Lime Shot"]);
+    }
+}
@@ -5,9 +5,72 @@
 //! Which 5 ingredients maximize the cocktail-making possibilities? What about 10 ingredients?
 //! Here's a branch and bound solution
 //! Original here: https://gist.github.com/tmcw/c6bdcfe505057ed6a0f356cfd02d4d52
-use rand::rngs::ThreadRng;
+pub mod analysis;
+pub mod anneal;
+pub mod anytime;
+pub mod bench;
+pub mod branching;
+pub mod brute;
+pub mod checkpoint;
+pub mod cocktail;
+pub mod constraints;
+pub mod counting;
+pub mod distributed;
+pub mod error;
+pub mod frontier;
+mod greedy;
+pub mod ilp;
+pub mod incremental;
+pub mod mapper;
+pub mod menu;
+pub mod metadata;
+pub mod observer;
+mod parallel;
+pub mod pareto;
+pub mod portfolio;
+pub mod preprocess;
+pub mod problem;
+pub mod profile;
+pub mod quantity;
+pub mod restarts;
+pub mod solution;
+pub mod solver;
+pub mod stats;
+pub mod step;
+pub mod subtree;
+mod table;
+pub mod transposition;
+pub mod weights;
+
+use branching::{BranchingStrategy, MinAmortizedCost};
+use checkpoint::{Checkpointing, Node};
+use constraints::{CategoryMaximum, CategoryMinimum, InfeasibleMinimums, SoftLimit};
+use counting::Tally;
+pub use error::Error;
+use frontier::SearchOrder;
+use observer::{Observer, Prune, SearchObserver};
+use profile::{Branch, Phase, Profile};
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::SeedableRng;
+use restarts::Restarts;
 use rustc_hash::{FxHashMap, FxHashSet};
-use std::{cmp::Ordering, collections::BTreeSet};
+use serde::{Deserialize, Serialize};
+use solution::Gap;
+use stats::SearchStats;
+use std::{
+    cell::OnceCell,
+    cmp::Ordering,
+    collections::BTreeSet,
+    fmt,
+    ops::{ControlFlow, RangeInclusive},
+    path::PathBuf,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+use subtree::SubtreeCache;
+use table::{CocktailSet, CocktailTable};
+use transposition::{Eviction, TranspositionTable};
 
 pub type Ingredient = String;
 pub type IngredientSet = BTreeSet<Ingredient>;
@@ -15,18 +78,1230 @@ pub type IngredientSet = BTreeSet<Ingredient>;
 pub type Ingredienti = i32;
 pub type IngredientSeti = BTreeSet<Ingredienti>;
 
-#[derive(Debug)]
+/// The signature of the bound functions consulted by [`BranchBound::keep_exploring`]
+type BoundFn = fn(&BranchBound, &BoundContext) -> i32;
+
+/// A node, as the bound functions see it. Anything more than one of them needs is
+/// worked out when first asked for, and kept for the rest while the node is bounded.
+/// What stays the same for the whole search, such as each cocktail's
+/// [`min_cover`](BranchBound::prepare), is worked out once before it starts
+pub(crate) struct BoundContext<'a> {
+    candidates: &'a [&'a IngredientSeti],
+    partial: &'a FxHashSet<IngredientSeti>,
+    partial_ingredients: &'a IngredientSeti,
+    increases: OnceCell<Vec<IngredientSeti>>,
+}
+
+impl<'a> BoundContext<'a> {
+    fn new(
+        candidates: &'a [&'a IngredientSeti],
+        partial: &'a FxHashSet<IngredientSeti>,
+        partial_ingredients: &'a IngredientSeti,
+    ) -> BoundContext<'a> {
+        BoundContext {
+            candidates,
+            partial,
+            partial_ingredients,
+            increases: OnceCell::new(),
+        }
+    }
+
+    /// The ingredients each candidate needs beyond the partial set's, in the
+    /// candidates' iteration order
+    fn increases(&self) -> &[IngredientSeti] {
+        self.increases.get_or_init(|| {
+            self.candidates
+                .iter()
+                .map(|&cocktail| cocktail - self.partial_ingredients)
+                .collect()
+        })
+    }
+}
+
+/// What each of a node's forbidden cocktails still needs beyond an include branch's
+/// ingredients, worked out once for all of the branch's candidates. The needs sit end
+/// to end in one buffer, which the search keeps from node to node, so that after the
+/// first few nodes filling it allocates nothing
+#[derive(Debug, Default)]
+pub(crate) struct Unmet {
+    ingredients: Vec<Ingredienti>,
+    ends: Vec<usize>,
+}
+
+impl Unmet {
+    /// Make room for the needs of another node's forbidden cocktails
+    fn clear(&mut self) {
+        self.ingredients.clear();
+        self.ends.clear();
+    }
+
+    fn push(&mut self, needs: impl IntoIterator<Item = Ingredienti>) {
+        self.ingredients.extend(needs);
+        self.ends.push(self.ingredients.len());
+    }
+
+    /// Would adding `cocktail` complete any of the forbidden cocktails?
+    fn completed_by(&self, cocktail: &IngredientSeti) -> bool {
+        let mut start = 0;
+        self.ends.iter().any(|&end| {
+            let needs = &self.ingredients[start..end];
+            start = end;
+            needs.iter().all(|ingredient| cocktail.contains(ingredient))
+        })
+    }
+}
+
+/// Roughly what a bound function costs to evaluate at a node. Cheaper tiers are
+/// always consulted first, so a dearer bound only runs where none of the cheaper ones
+/// could prune
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum BoundCost {
+    /// A single pass over the candidates, or less
+    Cheap,
+    /// A few passes over the candidates and their ingredients, and a sort
+    Medium,
+    /// Solving a problem of its own, such as a covering or a linear program. Only
+    /// consulted [if asked for](BranchBoundBuilder::with_expensive_bounds)
+    Expensive,
+}
+
+/// The bound functions, each with the reason it gives for pruning and what it costs.
+///
+/// Each must be an upper bound on what any branch below the node could go on to add,
+/// whatever the others say, so that they can be consulted in any order and the first
+/// to fall short of the incumbent settles it: only the time spent changes
+const BOUND_FUNCTIONS: [(Prune, BoundFn, BoundCost); 6] = [
+    (Prune::Total, BranchBound::total_bound, BoundCost::Cheap),
+    (
+        Prune::Singleton,
+        BranchBound::singleton_bound,
+        BoundCost::Cheap,
+    ),
+    (
+        Prune::Concentration,
+        BranchBound::concentration_bound,
+        BoundCost::Medium,
+    ),
+    (
+        Prune::Fractional,
+        BranchBound::fractional_bound,
+        BoundCost::Medium,
+    ),
+    (
+        Prune::Matching,
+        BranchBound::matching_bound,
+        BoundCost::Medium,
+    ),
+    (
+        Prune::Conflict,
+        BranchBound::conflict_bound,
+        BoundCost::Expensive,
+    ),
+];
+
+/// How long each of the [`BOUND_FUNCTIONS`] took at a node, if it was evaluated
+pub(crate) type BoundTimes = [Option<Duration>; BOUND_FUNCTIONS.len()];
+
+/// The order to consult the [`BOUND_FUNCTIONS`] in, by their indices
+pub(crate) type BoundOrder = [usize; BOUND_FUNCTIONS.len()];
+
+/// How many times a bound has to have been evaluated before its record is trusted
+/// when [adapting](BranchBoundBuilder::with_adaptive_bounds) the order of the bounds
+const ADAPTIVE_WARMUP: u32 = 64;
+
+/// How many nodes a [time-limited](BranchBoundBuilder::with_time_limit) search explores
+/// between checks of the clock
+pub const TIME_CHECK_INTERVAL: u32 = 256;
+
+/// What a node branches on: a candidate, made in one branch and forbidden in the other,
+/// or an ingredient, bought in one branch and ruled out in the other
+enum Pivot {
+    Cocktail(IngredientSeti),
+    Ingredient(Ingredienti),
+}
+
+/// Slack allowed when comparing costs to a [spend budget](BranchBoundBuilder::spend_budget),
+/// so that rounding errors in adding up prices don't reject a set costing exactly the budget
+pub(crate) const COST_TOLERANCE: f64 = 1e-9;
+
+/// A branch and bound search for the ingredient set making the most cocktails
+///
+/// `BranchBound` is `Send`, so a long search can be moved off the calling thread:
+///
+/// ```
+/// use branchbound::{BranchBound, IngredientSeti};
+/// use rustc_hash::FxHashSet;
+///
+/// let mut cocktails = FxHashSet::from_iter([IngredientSeti::from([0, 1]), IngredientSeti::from([1, 2])]);
+/// let mut bb = BranchBound::new(1000, 2);
+/// let best = std::thread::spawn(move || bb.search(&mut cocktails, &mut FxHashSet::default(), &mut None))
+///     .join()
+///     .unwrap();
+/// assert_eq!(best.len(), 1);
+/// ```
 pub struct BranchBound {
+    /// Search rounds left in the call budget. Each node expanded uses one
     pub calls: i32,
     pub max_size: usize,
+    /// What the incumbent's cocktails [score](Self::score)
     pub highest_score: usize,
+    /// The incumbent: the best cocktails found so far
     pub highest: FxHashSet<IngredientSeti>,
+    /// The ingredients the incumbent needs, including the required ones but not any
+    /// [padding](Self::padding). Kept up to date with `highest` by every solver
     pub highest_ingredients: BTreeSet<Ingredienti>,
-    pub random: ThreadRng,
+    /// Breaks ties between equally good cocktails to branch on, if
+    /// [seeded](BranchBoundBuilder::with_seed)
+    pub random: Option<StdRng>,
+    /// Nodes expanded so far, over every search this has run. See also
+    /// [`stats`](Self::stats)
     pub counter: u32,
     pub min_cover: FxHashMap<BTreeSet<i32>, i32>,
     pub min_amortized_cost: FxHashMap<IngredientSeti, f64>,
     pub initial: bool,
+    pub category_minimums: Vec<CategoryMinimum>,
+    pub category_maximums: Vec<CategoryMaximum>,
+    /// Preferences whose penalties [`score`](Self::score) subtracts
+    pub soft_limits: Vec<SoftLimit>,
+    /// How far the search may branch from the initial call, if limited
+    pub max_depth: Option<usize>,
+    /// The depth of the node currently being searched
+    pub depth: usize,
+    /// How many nodes went unexplored because of `max_depth`.
+    /// If this is non-zero, the result may not be optimal
+    pub depth_truncations: u32,
+    /// The highest score any node left unexplored, by running out of calls or time
+    /// or by the depth limit, could have led to. See [`BranchBound::gap`]
+    open_bound: usize,
+    current_candidates: usize,
+    current_forbidden: usize,
+    /// Prune branches that can't beat the incumbent by more than this fraction
+    pub epsilon: f64,
+    /// Ingredients every solution must include
+    pub required: IngredientSeti,
+    /// Required ingredients that are already owned, so cost nothing and don't count
+    /// against `max_size`
+    pub owned: IngredientSeti,
+    /// Ingredients no solution may include
+    pub excluded: IngredientSeti,
+    /// Combinations of ingredients no solution may include all of. See
+    /// [`BranchBoundBuilder::add_forbidden`]
+    pub forbidden_combinations: Vec<IngredientSeti>,
+    /// The variant group of each grouped cocktail. See [`BranchBound::score`]
+    pub variants: FxHashMap<IngredientSeti, usize>,
+    /// Cocktails to make if at all possible. See [`BranchBoundBuilder::house_cocktails`]
+    pub house: FxHashSet<IngredientSeti>,
+    /// The value of a house cocktail, set when the search starts to more than
+    /// every other cocktail together
+    pub house_weight: usize,
+    /// The value of each cocktail, if not 1. See [`BranchBoundBuilder::cocktail_weights`]
+    pub weights: FxHashMap<IngredientSeti, usize>,
+    /// The most the ingredients may cost in all, if limited. See
+    /// [`BranchBoundBuilder::spend_budget`]
+    pub budget: Option<f64>,
+    /// The price of each ingredient. Unpriced ingredients are free
+    pub prices: FxHashMap<Ingredienti, f64>,
+    /// Where the search spent its time, if [profiling](BranchBoundBuilder::profile)
+    pub profile: Option<Profile>,
+    /// Worker threads for a [parallel](BranchBoundBuilder::parallel) search
+    threads: Option<usize>,
+    /// How long the search may run for, if limited
+    pub time_limit: Option<Duration>,
+    /// When the search must stop, once it has started
+    deadline: Option<Instant>,
+    /// Whether the search finished, or why it stopped early
+    pub status: SearchStatus,
+    observer: Option<Observer>,
+    /// How many of the best distinct ingredient sets to keep. See
+    /// [`BranchBoundBuilder::with_top_k`]
+    pub top_k: usize,
+    /// The best distinct ingredient sets found so far, best first, when keeping more
+    /// than one
+    pub alternatives: Vec<Alternative>,
+    /// How many ingredient sets tied with the best to collect. See
+    /// [`BranchBoundBuilder::with_ties`]
+    pub max_ties: usize,
+    /// The distinct ingredient sets found scoring as much as the best, in the order
+    /// they were found, when collecting them
+    pub ties: Vec<Alternative>,
+    /// The optimal ingredient sets counted so far, while
+    /// [counting them](BranchBound::count_optima)
+    tally: Option<Tally>,
+    /// Whether to reorder the bounds as the search goes. See
+    /// [`BranchBoundBuilder::with_adaptive_bounds`]
+    adaptive_bounds: bool,
+    bound_order: BoundOrder,
+    /// Which of the [`BOUND_FUNCTIONS`] to consult
+    consulted: [bool; BOUND_FUNCTIONS.len()],
+    /// Whether to probe the ingredients before searching. See
+    /// [`BranchBoundBuilder::with_probing`]
+    probing: bool,
+    /// The short passes to make before searching, if any. See
+    /// [`BranchBoundBuilder::with_restarts`]
+    restarts: Option<Restarts>,
+    /// How far above the cheapest candidate's cost, relative to it, another's can be
+    /// and still be [chosen](BranchBound::choose) at random. Only nonzero during a
+    /// [restart](restarts) pass
+    tie_slack: f64,
+    checkpointing: Option<Checkpointing>,
+    /// The last failure to save a [checkpoint](BranchBoundBuilder::checkpoint), if any.
+    /// The search carries on regardless
+    pub checkpoint_error: Option<Error>,
+    /// The nodes still to be searched depth-first, the next one last. After stopping
+    /// early, those left to [resume](BranchBound::resume)
+    frontier: Vec<Node>,
+    /// Every cocktail the search has met, numbered, so that its nodes can hold sets of
+    /// indices. Shared, so a node can be expanded while the table is borrowed
+    cocktails: Arc<CocktailTable>,
+    /// Scratch space for the [include branch](BranchBound::include_branch), kept so
+    /// that its buffers are reused from node to node
+    unmet: Unmet,
+    /// The incumbent shared with the rest of a [portfolio](portfolio::Portfolio)
+    portfolio: Option<Arc<portfolio::Shared>>,
+    /// Stop as soon as the incumbent scores this much. See
+    /// [`BranchBound::min_ingredients_for`]
+    stop_at: Option<usize>,
+    /// The ingredients to start from, if given. See
+    /// [`BranchBoundBuilder::with_initial_solution`]
+    initial_solution: Option<IngredientSeti>,
+    /// The nodes already branched on, if remembered. See
+    /// [`BranchBoundBuilder::transposition_table`]
+    transpositions: Option<TranspositionTable>,
+    /// What the subtrees already searched could add, if remembered. See
+    /// [`BranchBoundBuilder::subtree_cache`]
+    subtrees: Option<SubtreeCache>,
+    /// The subtrees being searched depth-first, innermost last: where each one's root
+    /// left the frontier, what's left at it, and its score
+    open_subtrees: Vec<(usize, u128, usize)>,
+    /// The most exclude branches a path may take, during a pass of a
+    /// [limited discrepancy](SearchOrder::LimitedDiscrepancy) search
+    discrepancy_limit: Option<usize>,
+    /// How many branches the current pass has cut off for going over the limit
+    discrepancy_cuts: u32,
+    /// How the search went
+    pub stats: SearchStats,
+    /// Chooses the candidate to branch on. See [`BranchBoundBuilder::branching`]
+    branching: Arc<dyn BranchingStrategy>,
+    /// The order to explore nodes in. See [`BranchBoundBuilder::search_order`]
+    pub order: SearchOrder,
+}
+
+/// One of the best ingredient sets found by a search keeping the
+/// [top k](BranchBoundBuilder::with_top_k)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Alternative {
+    pub score: usize,
+    pub cocktails: FxHashSet<IngredientSeti>,
+    pub ingredients: IngredientSeti,
+}
+
+/// How a search ended
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum SearchStatus {
+    /// Every node was explored or pruned (short of any depth limit)
+    #[default]
+    Complete,
+    /// The call budget ran out
+    OutOfCalls,
+    /// The [time limit](BranchBoundBuilder::with_time_limit) was reached
+    TimedOut,
+    /// An [observer](BranchBoundBuilder::observer) stopped it
+    Aborted,
+}
+
+impl SearchStatus {
+    /// Did the search run to completion, so that its result is as good as its
+    /// bounds guarantee?
+    #[must_use]
+    pub fn completed(self) -> bool {
+        self == SearchStatus::Complete
+    }
+}
+
+/// A point-in-time summary of a [`BranchBound`] search, from [`BranchBound::snapshot`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct SearchSnapshot {
+    /// Depth of the node being searched
+    pub depth: usize,
+    /// Nodes searched so far
+    pub nodes: u32,
+    pub calls_remaining: i32,
+    /// Score of the best ingredient set found so far
+    pub incumbent_score: usize,
+    /// Number of ingredients in the best set found so far
+    pub incumbent_ingredients: usize,
+    /// Number of candidate cocktails at the node being searched
+    pub candidates: usize,
+    /// Number of forbidden cocktails at the node being searched
+    pub forbidden: usize,
+    /// Number of cocktails in the per-cocktail caches
+    pub cached_cocktails: usize,
+    /// Rough estimate of the heap memory held by the caches, and by the candidate and
+    /// forbidden sets of every node between the root and the current one, in bytes
+    pub estimated_bytes: usize,
+}
+
+/// A [`BranchBoundBuilder`] configuration that can't produce a useful search
+#[derive(Debug, Clone, PartialEq)]
+pub enum ConfigError {
+    /// `max_size` was zero, so no cocktail could ever be made
+    ZeroSize,
+    /// The call budget wasn't positive
+    NoCalls(i32),
+    /// The approximation parameter was negative or not a number
+    InvalidApproximation(f64),
+    /// The required ingredients (plus any needed to meet category minimums)
+    /// exceed the budget
+    TooManyRequired {
+        required: usize,
+        max_size: usize,
+    },
+    /// The initial incumbent needs more ingredients than the budget allows
+    IncumbentTooLarge {
+        ingredients: usize,
+        max_size: usize,
+    },
+    InfeasibleMinimums(InfeasibleMinimums),
+    /// The required (or owned) ingredients include more of a category than its
+    /// maximum allows
+    RequiredOverMaximum {
+        category: String,
+        required: usize,
+        maximum: usize,
+    },
+    /// An ingredient is both required (or owned) and excluded
+    ExcludedRequired(Ingredienti),
+    /// The required (or owned) ingredients include a forbidden combination
+    ForbiddenRequired(IngredientSeti),
+    /// The initial solution includes an excluded ingredient
+    ExcludedInitial(Ingredienti),
+    /// The initial solution includes a forbidden combination
+    ForbiddenInitial(IngredientSeti),
+    /// The initial solution costs more than the spend budget
+    InitialOverSpend {
+        cost: f64,
+        budget: f64,
+    },
+    /// A reason given to [`BranchBoundBuilder::bounds`] isn't one of the bounds'
+    NotABound(Prune),
+    /// A [checkpoint](BranchBoundBuilder::checkpoint) was asked of a search that
+    /// doesn't explore depth-first, and so would never save one
+    UncheckpointedOrder(SearchOrder),
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigError::ZeroSize => write!(f, "the ingredient budget must be at least 1"),
+            ConfigError::NoCalls(calls) => {
+                write!(f, "the call budget must be positive, not {calls}")
+            }
+            ConfigError::InvalidApproximation(epsilon) => write!(
+                f,
+                "the approximation parameter must be a non-negative number, not {epsilon}"
+            ),
+            ConfigError::TooManyRequired { required, max_size } => write!(
+                f,
+                "{required} ingredients are required, but only {max_size} are allowed"
+            ),
+            ConfigError::IncumbentTooLarge {
+                ingredients,
+                max_size,
+            } => write!(
+                f,
+                "the initial solution needs {ingredients} ingredients, but only {max_size} are allowed"
+            ),
+            ConfigError::InfeasibleMinimums(e) => e.fmt(f),
+            ConfigError::RequiredOverMaximum {
+                category,
+                required,
+                maximum,
+            } => write!(
+                f,
+                "{required} {category} are required, but at most {maximum} are allowed"
+            ),
+            ConfigError::ForbiddenRequired(combination) => write!(
+                f,
+                "the required ingredients include the forbidden combination {combination:?}"
+            ),
+            ConfigError::ExcludedRequired(ingredient) => write!(
+                f,
+                "ingredient {ingredient} is both required and excluded"
+            ),
+            ConfigError::ExcludedInitial(ingredient) => write!(
+                f,
+                "the initial solution includes excluded ingredient {ingredient}"
+            ),
+            ConfigError::ForbiddenInitial(combination) => write!(
+                f,
+                "the initial solution includes the forbidden combination {combination:?}"
+            ),
+            ConfigError::InitialOverSpend { cost, budget } => write!(
+                f,
+                "the initial solution costs {cost:.2}, over the budget of {budget:.2}"
+            ),
+            ConfigError::NotABound(reason) => {
+                write!(f, "{} isn't the name of a bound", reason.name())
+            }
+            ConfigError::UncheckpointedOrder(order) => write!(
+                f,
+                "only a depth-first search can be checkpointed, not a {order:?} one"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ConfigError::InfeasibleMinimums(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<InfeasibleMinimums> for ConfigError {
+    fn from(e: InfeasibleMinimums) -> Self {
+        ConfigError::InfeasibleMinimums(e)
+    }
+}
+
+/// Configures a [`BranchBound`] search
+#[derive(Debug, Clone)]
+pub struct BranchBoundBuilder {
+    max_calls: i32,
+    max_size: usize,
+    category_minimums: Vec<CategoryMinimum>,
+    category_maximums: Vec<CategoryMaximum>,
+    soft_limits: Vec<SoftLimit>,
+    max_depth: Option<usize>,
+    epsilon: f64,
+    required: IngredientSeti,
+    owned: IngredientSeti,
+    excluded: IngredientSeti,
+    /// The ingredients of the cocktails that must be made
+    must_make: IngredientSeti,
+    forbidden_combinations: Vec<IngredientSeti>,
+    incumbent: FxHashSet<IngredientSeti>,
+    initial_solution: Option<IngredientSeti>,
+    variants: FxHashMap<IngredientSeti, usize>,
+    house: FxHashSet<IngredientSeti>,
+    weights: FxHashMap<IngredientSeti, usize>,
+    budget: Option<f64>,
+    prices: FxHashMap<Ingredienti, f64>,
+    profile: bool,
+    threads: Option<usize>,
+    seed: Option<u64>,
+    time_limit: Option<Duration>,
+    observer: Option<Observer>,
+    top_k: usize,
+    max_ties: usize,
+    adaptive_bounds: bool,
+    expensive_bounds: bool,
+    bounds: Option<Vec<Prune>>,
+    probing: bool,
+    restarts: Option<(usize, i32)>,
+    checkpointing: Option<Checkpointing>,
+    branching: Arc<dyn BranchingStrategy>,
+    order: SearchOrder,
+    transpositions: Option<(usize, Eviction)>,
+    subtree_cache: Option<usize>,
+}
+
+impl BranchBoundBuilder {
+    /// Search for the best set of `max_size` ingredients, giving up after `max_calls` rounds
+    #[must_use]
+    pub fn new(max_calls: i32, max_size: usize) -> BranchBoundBuilder {
+        BranchBoundBuilder {
+            max_calls,
+            max_size,
+            category_minimums: vec![],
+            category_maximums: vec![],
+            soft_limits: vec![],
+            max_depth: None,
+            epsilon: 0.0,
+            required: IngredientSeti::new(),
+            owned: IngredientSeti::new(),
+            excluded: IngredientSeti::new(),
+            must_make: IngredientSeti::new(),
+            forbidden_combinations: vec![],
+            incumbent: FxHashSet::default(),
+            initial_solution: None,
+            variants: FxHashMap::default(),
+            house: FxHashSet::default(),
+            weights: FxHashMap::default(),
+            budget: None,
+            prices: FxHashMap::default(),
+            profile: false,
+            threads: None,
+            seed: None,
+            time_limit: None,
+            observer: None,
+            top_k: 1,
+            max_ties: 0,
+            adaptive_bounds: false,
+            expensive_bounds: false,
+            bounds: None,
+            probing: false,
+            restarts: None,
+            checkpointing: None,
+            branching: Arc::new(MinAmortizedCost),
+            order: SearchOrder::DepthFirst,
+            transpositions: None,
+            subtree_cache: None,
+        }
+    }
+
+    /// Break ties between equally good cocktails to branch on at random, seeded by
+    /// `seed`, so that two runs with the same seed explore the same nodes in the same
+    /// order. Without a seed, ties are broken by the candidates' iteration order.
+    /// The [parallel](Self::parallel) search ignores the seed
+    #[must_use]
+    pub fn with_seed(mut self, seed: u64) -> BranchBoundBuilder {
+        self.seed = Some(seed);
+        self
+    }
+
+    /// Record the time spent in each phase of the search at each depth, in
+    /// [`BranchBound::profile`]. This slows the search down slightly
+    #[must_use]
+    pub fn profile(mut self, profile: bool) -> BranchBoundBuilder {
+        self.profile = profile;
+        self
+    }
+
+    /// Explore the two branches of each node concurrently, on a pool of `threads` worker
+    /// threads (0 for one per CPU), sharing the best score found so far between them.
+    ///
+    /// The parallel search finds a solution as good as the sequential one, but among
+    /// equally good solutions which one it returns can vary from run to run, and it
+    /// doesn't support [profiling](Self::profile)
+    #[must_use]
+    pub fn parallel(mut self, threads: usize) -> BranchBoundBuilder {
+        self.threads = Some(threads);
+        self
+    }
+
+    /// Mark `cocktails` as house cocktails, and optimise lexicographically: first make
+    /// as many house cocktails as possible, then as many cocktails overall. No number
+    /// of other cocktails makes up for losing a house cocktail
+    #[must_use]
+    pub fn house_cocktails(
+        mut self,
+        cocktails: impl IntoIterator<Item = IngredientSeti>,
+    ) -> BranchBoundBuilder {
+        self.house.extend(cocktails);
+        self
+    }
+
+    /// Maximise the total weight of the cocktails made, rather than their number.
+    /// Cocktails without a weight are worth 1, so scale the weights up to keep
+    /// fractional differences between them
+    #[must_use]
+    pub fn cocktail_weights(
+        mut self,
+        weights: impl IntoIterator<Item = (IngredientSeti, usize)>,
+    ) -> BranchBoundBuilder {
+        self.weights.extend(weights);
+        self
+    }
+
+    /// Price ingredients, for [`BranchBound::cost`] and [`BranchBound::pareto`], without
+    /// limiting what may be spent
+    #[must_use]
+    pub fn prices(
+        mut self,
+        prices: impl IntoIterator<Item = (Ingredienti, f64)>,
+    ) -> BranchBoundBuilder {
+        self.prices.extend(prices);
+        self
+    }
+
+    /// Only accept ingredient sets costing at most `budget` in all, at `prices`.
+    /// This applies as well as the limit on the number of ingredients, so raise that
+    /// to search by cost alone. Ingredients without a price are free, and any bought
+    /// only to meet category minimums aren't costed
+    #[must_use]
+    pub fn spend_budget(
+        mut self,
+        budget: f64,
+        prices: impl IntoIterator<Item = (Ingredienti, f64)>,
+    ) -> BranchBoundBuilder {
+        self.budget = Some(budget);
+        self.prices.extend(prices);
+        self
+    }
+
+    /// Treat `cocktails` as variants of one drink (e.g. a Daiquiri and a Hemingway
+    /// Daiquiri), which only count once towards the score however many are made.
+    /// A cocktail belongs to at most one group: adding it to another moves it
+    #[must_use]
+    pub fn variant_group(
+        mut self,
+        cocktails: impl IntoIterator<Item = IngredientSeti>,
+    ) -> BranchBoundBuilder {
+        let group = self.variants.values().max().map_or(0, |g| g + 1);
+        for cocktail in cocktails {
+            self.variants.insert(cocktail, group);
+        }
+        self
+    }
+
+    /// Start the search from a known set of makeable cocktails, e.g. from a previous
+    /// run, so that branches which can't beat it are pruned straight away
+    #[must_use]
+    pub fn initial_incumbent(mut self, cocktails: FxHashSet<IngredientSeti>) -> BranchBoundBuilder {
+        self.incumbent = cocktails;
+        self
+    }
+
+    /// Start the search from `ingredients`, e.g. the bottles already on the shelf: the
+    /// cocktails they make are the best set to beat, so the result is only different if
+    /// a better set exists. [`try_build`](Self::try_build) checks that they meet the
+    /// constraints
+    #[must_use]
+    pub fn with_initial_solution(mut self, ingredients: &[Ingredienti]) -> BranchBoundBuilder {
+        self.initial_solution = Some(ingredients.iter().copied().collect());
+        self
+    }
+
+    /// Only accept solutions including all of `ingredients`. They count against the
+    /// ingredient budget
+    #[must_use]
+    pub fn required_ingredients(mut self, ingredients: IngredientSeti) -> BranchBoundBuilder {
+        self.required = ingredients;
+        self
+    }
+
+    /// Never include every one of `ingredients` (e.g. both mezcal and scotch), although
+    /// any of them alone is fine. Ingredients bought only to meet category minimums
+    /// aren't checked
+    ///
+    /// Such combinations seed the set of forbidden ingredient sets the search already
+    /// keeps for the cocktails it has branched away from, so they prune in the same way.
+    #[must_use]
+    pub fn add_forbidden(mut self, ingredients: IngredientSeti) -> BranchBoundBuilder {
+        self.forbidden_combinations.push(ingredients);
+        self
+    }
+
+    /// Only accept solutions making every one of `cocktails`, by requiring all their
+    /// ingredients. They count against the ingredient budget, so [`try_build`]
+    /// rejects cocktails that can't all be made within it
+    ///
+    /// [`try_build`]: Self::try_build
+    #[must_use]
+    pub fn must_make(
+        mut self,
+        cocktails: impl IntoIterator<Item = IngredientSeti>,
+    ) -> BranchBoundBuilder {
+        self.must_make.extend(cocktails.into_iter().flatten());
+        self
+    }
+
+    /// Include `ingredients` in every solution, as already owned: unlike
+    /// [required ingredients](Self::required_ingredients), they're free and don't count
+    /// against the ingredient budget, so only the remaining purchases are optimised
+    #[must_use]
+    pub fn owned_ingredients(
+        mut self,
+        ingredients: impl IntoIterator<Item = Ingredienti>,
+    ) -> BranchBoundBuilder {
+        self.owned.extend(ingredients);
+        self
+    }
+
+    /// Never include `ingredients`: cocktails needing any of them are dropped before
+    /// searching, and they can't be used to meet category minimums
+    #[must_use]
+    pub fn excluded_ingredients(
+        mut self,
+        ingredients: impl IntoIterator<Item = Ingredienti>,
+    ) -> BranchBoundBuilder {
+        self.excluded.extend(ingredients);
+        self
+    }
+
+    /// Search approximately, pruning any branch that can't beat the best set found so
+    /// far by more than a fraction `epsilon` of its score. A completed search then
+    /// guarantees a result within a factor of [`BranchBound::approximation_factor`]
+    /// of the optimum, usually far faster than proving optimality
+    #[must_use]
+    pub fn approximation(mut self, epsilon: f64) -> BranchBoundBuilder {
+        self.epsilon = epsilon;
+        self
+    }
+
+    /// Stop branching `max_depth` levels below the initial call. This bounds the
+    /// depth of the search at the cost of optimality: nodes left unexplored are
+    /// counted in [`BranchBound::depth_truncations`]
+    #[must_use]
+    pub fn max_depth(mut self, max_depth: usize) -> BranchBoundBuilder {
+        self.max_depth = Some(max_depth);
+        self
+    }
+
+    /// Stop searching once `limit` has passed, keeping the best set found so far.
+    /// The clock is checked every [`TIME_CHECK_INTERVAL`] nodes, so the search may
+    /// overrun slightly. Whether it did stop early is recorded in [`BranchBound::status`]
+    #[must_use]
+    pub fn with_time_limit(mut self, limit: Duration) -> BranchBoundBuilder {
+        self.time_limit = Some(limit);
+        self
+    }
+
+    /// Save the search to `path` before every `every` nodes, so that it can be
+    /// [resumed](BranchBound::resume_from) if interrupted. Sequential, depth-first
+    /// searches only. See [`checkpoint`]
+    #[must_use]
+    pub fn checkpoint(mut self, path: impl Into<PathBuf>, every: u32) -> BranchBoundBuilder {
+        self.checkpointing = Some(Checkpointing {
+            path: path.into(),
+            every: every.max(1),
+        });
+        self
+    }
+
+    /// Choose the candidate to branch on with `strategy`, rather than by
+    /// [`MinAmortizedCost`]. Clones of this builder share the strategy
+    #[must_use]
+    pub fn branching(mut self, strategy: impl BranchingStrategy + 'static) -> BranchBoundBuilder {
+        self.branching = Arc::new(strategy);
+        self
+    }
+
+    /// Explore nodes in `order` rather than depth-first. A [beam](SearchOrder::Beam)
+    /// search is approximate. The [parallel](Self::parallel) search is always
+    /// depth-first
+    #[must_use]
+    pub fn search_order(mut self, order: SearchOrder) -> BranchBoundBuilder {
+        self.order = order;
+        self
+    }
+
+    /// Prune nodes reached again by another branch, remembering the ones searched in a
+    /// [table](transposition) of at most about `max_bytes`, which makes room as
+    /// `eviction` says once it's full
+    #[must_use]
+    pub fn transposition_table(
+        mut self,
+        max_bytes: usize,
+        eviction: Eviction,
+    ) -> BranchBoundBuilder {
+        self.transpositions = Some((max_bytes, eviction));
+        self
+    }
+
+    /// Remember how much each subtree searched could add, in a [cache](subtree) of at
+    /// most about `max_bytes`, and prune nodes leaving the same problem as one already
+    /// searched that can't add enough. Doesn't change the answer
+    #[must_use]
+    pub fn subtree_cache(mut self, max_bytes: usize) -> BranchBoundBuilder {
+        self.subtree_cache = Some(max_bytes);
+        self
+    }
+
+    /// Keep the `k` best distinct ingredient sets in [`BranchBound::alternatives`],
+    /// rather than just the best. Only branches that could beat the k-th best set are
+    /// explored, so the larger `k`, the slower the search
+    #[must_use]
+    pub fn with_top_k(mut self, k: usize) -> BranchBoundBuilder {
+        self.top_k = k;
+        self
+    }
+
+    /// Collect up to `max` distinct ingredient sets scoring as much as the best in
+    /// [`BranchBound::ties`], whatever their size. Branches that could only tie have to
+    /// be explored until there are `max`, even if they'd need more ingredients, so the
+    /// search is slower
+    #[must_use]
+    pub fn with_ties(mut self, max: usize) -> BranchBoundBuilder {
+        self.max_ties = max;
+        self
+    }
+
+    /// Reorder the bounds as the search goes, consulting first those that have spent
+    /// the least time per node they pruned, rather than always in the order they're
+    /// registered in. Each [cost tier](BoundCost) still goes before the next. The
+    /// first bound to prune a node saves evaluating the rest, so this pays off when a
+    /// bound prunes much of what a dearer one in its tier would.
+    /// Every bound is still consulted before a node is expanded, so the answer is the
+    /// same either way
+    #[must_use]
+    pub fn with_adaptive_bounds(mut self) -> BranchBoundBuilder {
+        self.adaptive_bounds = true;
+        self
+    }
+
+    /// Also consult the [expensive](BoundCost::Expensive) bounds, at nodes none of the
+    /// cheaper ones prune. They prune more nodes, but on the bundled dataset not enough
+    /// to pay for themselves. Makes no difference if the [bounds](Self::bounds) are
+    /// chosen
+    #[must_use]
+    pub fn with_expensive_bounds(mut self) -> BranchBoundBuilder {
+        self.expensive_bounds = true;
+        self
+    }
+
+    /// Consult only the bounds pruning for `reasons`, whatever they cost, e.g. to see
+    /// what each is worth. [`try_build`](Self::try_build) rejects reasons that aren't
+    /// bounds, such as [`Prune::Infeasible`]. The answer is the same whichever bounds
+    /// are consulted, even none, but the search may take much longer
+    #[must_use]
+    pub fn bounds(mut self, reasons: impl IntoIterator<Item = Prune>) -> BranchBoundBuilder {
+        self.bounds = Some(reasons.into_iter().collect());
+        self
+    }
+
+    /// Before [searching](BranchBound::search), probe each ingredient: if the bounds
+    /// show that no set including it could beat the best found so far, drop the
+    /// cocktails needing it, and if none without it could, require it. Repeats until
+    /// nothing more is found, each find tightening the bounds for the rest. Not done
+    /// when [keeping](Self::with_top_k) sets worse than the best
+    #[must_use]
+    pub fn with_probing(mut self) -> BranchBoundBuilder {
+        self.probing = true;
+        self
+    }
+
+    /// Before [searching](BranchBound::search), make `passes` short passes of at most
+    /// `calls` calls each, breaking ties between candidates to branch on at random,
+    /// and start the full search from the best set any of them finds. See
+    /// [`restarts`]. The passes are seeded from [the seed](Self::with_seed), if given,
+    /// and their calls come out of the call budget. Doesn't change the answer
+    #[must_use]
+    pub fn with_restarts(mut self, passes: usize, calls: i32) -> BranchBoundBuilder {
+        self.restarts = Some((passes, calls));
+        self
+    }
+
+    /// Report the search's progress to `observer`, which may also stop it. Clones of
+    /// this builder share the observer
+    #[must_use]
+    pub fn observer(
+        mut self,
+        observer: impl SearchObserver + Send + 'static,
+    ) -> BranchBoundBuilder {
+        self.observer = Some(Observer::new(observer));
+        self
+    }
+
+    /// Only accept ingredient sets satisfying `minimum`
+    #[must_use]
+    pub fn category_minimum(mut self, minimum: CategoryMinimum) -> BranchBoundBuilder {
+        self.category_minimums.push(minimum);
+        self
+    }
+
+    /// Only accept ingredient sets within `maximum`
+    #[must_use]
+    pub fn category_maximum(mut self, maximum: CategoryMaximum) -> BranchBoundBuilder {
+        self.category_maximums.push(maximum);
+        self
+    }
+
+    /// Penalise ingredient sets going over `limit`'s limit, rather than ruling them out
+    #[must_use]
+    pub fn soft_limit(mut self, limit: SoftLimit) -> BranchBoundBuilder {
+        self.soft_limits.push(limit);
+        self
+    }
+
+    /// Check the configuration for mistakes before building
+    ///
+    /// Rejects an empty ingredient budget, a call budget that would end the search
+    /// before it starts, category minimums that can't all be met within the budget, and
+    /// checkpoints asked of a search that isn't depth-first.
+    pub fn try_build(mut self) -> Result<BranchBound, ConfigError> {
+        if self.max_size == 0 {
+            return Err(ConfigError::ZeroSize);
+        }
+        self.merge_required();
+        if let Some(&ingredient) = self
+            .required
+            .iter()
+            .find(|ingredient| self.excluded.contains(ingredient))
+        {
+            return Err(ConfigError::ExcludedRequired(ingredient));
+        }
+        if let Some(combination) = self
+            .forbidden_combinations
+            .iter()
+            .find(|combination| combination.is_subset(&self.required))
+        {
+            return Err(ConfigError::ForbiddenRequired(combination.clone()));
+        }
+        if let Some(initial) = &self.initial_solution {
+            if let Some(&ingredient) = initial
+                .iter()
+                .find(|ingredient| self.excluded.contains(ingredient))
+            {
+                return Err(ConfigError::ExcludedInitial(ingredient));
+            }
+            let initial = initial | &self.required;
+            if let Some(combination) = self
+                .forbidden_combinations
+                .iter()
+                .find(|combination| combination.is_subset(&initial))
+            {
+                return Err(ConfigError::ForbiddenInitial(combination.clone()));
+            }
+        }
+        self.apply_exclusions();
+        if self.max_calls <= 0 {
+            return Err(ConfigError::NoCalls(self.max_calls));
+        }
+        if !(self.epsilon >= 0.0 && self.epsilon.is_finite()) {
+            return Err(ConfigError::InvalidApproximation(self.epsilon));
+        }
+        if let Some(&reason) = self
+            .bounds
+            .iter()
+            .flatten()
+            .find(|reason| reason.cost().is_none())
+        {
+            return Err(ConfigError::NotABound(reason));
+        }
+        if self.checkpointing.is_some() && self.order != SearchOrder::DepthFirst {
+            return Err(ConfigError::UncheckpointedOrder(self.order));
+        }
+        constraints::check_minimums(&self.category_minimums, self.max_size)?;
+        constraints::check_maximums(&self.category_minimums, &self.category_maximums)?;
+        let bb = self.build();
+        if let Some(maximum) = bb
+            .category_maximums
+            .iter()
+            .find(|maximum| maximum.excess(&bb.required) > 0)
+        {
+            return Err(ConfigError::RequiredOverMaximum {
+                category: maximum.category.clone(),
+                required: maximum.count(&bb.required),
+                maximum: maximum.maximum,
+            });
+        }
+        if !bb.fits(&bb.required) {
+            return Err(ConfigError::TooManyRequired {
+                required: bb.padded_len(&bb.required),
+                max_size: bb.max_size,
+            });
+        }
+        let incumbent_ingredients = &bb.highest_ingredients | &bb.required;
+        if !bb.fits(&incumbent_ingredients) {
+            return Err(ConfigError::IncumbentTooLarge {
+                ingredients: bb.padded_len(&incumbent_ingredients),
+                max_size: bb.max_size,
+            });
+        }
+        if let Some(initial) = &bb.initial_solution {
+            let initial = initial | &bb.required;
+            if bb.padded_len(&initial) > bb.max_size {
+                return Err(ConfigError::IncumbentTooLarge {
+                    ingredients: bb.padded_len(&initial),
+                    max_size: bb.max_size,
+                });
+            }
+            if let Some(budget) = bb.budget {
+                let cost = bb.cost(&initial);
+                if cost > budget + COST_TOLERANCE {
+                    return Err(ConfigError::InitialOverSpend { cost, budget });
+                }
+            }
+        }
+        Ok(bb)
+    }
+
+    /// Build without validating the configuration. Prefer [`try_build`](Self::try_build)
+    #[must_use]
+    pub fn build(mut self) -> BranchBound {
+        self.apply_exclusions();
+        self.merge_required();
+        let highest_ingredients = self.incumbent.iter().flatten().copied().collect();
+        let mut bb = BranchBound {
+            calls: self.max_calls,
+            max_size: self.max_size,
+            highest_score: 0,
+            highest: self.incumbent,
+            highest_ingredients,
+            initial_solution: self.initial_solution,
+            transpositions: self
+                .transpositions
+                .map(|(max_bytes, eviction)| TranspositionTable::new(max_bytes, eviction)),
+            subtrees: self.subtree_cache.map(SubtreeCache::new),
+            open_subtrees: vec![],
+            discrepancy_limit: None,
+            discrepancy_cuts: 0,
+            random: self.seed.map(StdRng::seed_from_u64),
+            counter: 0,
+            min_cover: FxHashMap::default(),
+            min_amortized_cost: FxHashMap::default(),
+            initial: true,
+            category_minimums: self.category_minimums,
+            category_maximums: self.category_maximums,
+            soft_limits: self.soft_limits,
+            max_depth: self.max_depth,
+            depth: 0,
+            depth_truncations: 0,
+            open_bound: 0,
+            current_candidates: 0,
+            current_forbidden: 0,
+            epsilon: self.epsilon,
+            required: self.required,
+            owned: self.owned,
+            excluded: self.excluded,
+            forbidden_combinations: self.forbidden_combinations,
+            variants: self.variants,
+            house: self.house,
+            house_weight: 1,
+            weights: self.weights,
+            budget: self.budget,
+            prices: self.prices,
+            profile: self.profile.then(Profile::new),
+            threads: self.threads,
+            time_limit: self.time_limit,
+            deadline: None,
+            status: SearchStatus::Complete,
+            observer: self.observer,
+            top_k: self.top_k,
+            alternatives: vec![],
+            max_ties: self.max_ties,
+            ties: vec![],
+            tally: None,
+            adaptive_bounds: self.adaptive_bounds,
+            bound_order: tiered_order(),
+            consulted: consulted(self.bounds.as_deref(), self.expensive_bounds),
+            probing: self.probing,
+            restarts: self.restarts.map(|(passes, calls)| Restarts {
+                passes,
+                calls,
+                seed: self.seed.unwrap_or(0),
+            }),
+            tie_slack: 0.0,
+            checkpointing: self.checkpointing,
+            checkpoint_error: None,
+            frontier: vec![],
+            cocktails: Arc::default(),
+            unmet: Unmet::default(),
+            portfolio: None,
+            stop_at: None,
+            stats: SearchStats::default(),
+            branching: self.branching,
+            order: self.order,
+        };
+        bb.highest_score = bb.score(&bb.highest);
+        bb
+    }
+
+    /// Require the owned ingredients and those of the cocktails that must be made
+    fn merge_required(&mut self) {
+        self.required.extend(self.owned.iter().copied());
+        self.required.extend(self.must_make.iter().copied());
+    }
+
+    /// Keep excluded ingredients out of the category minimums and the initial incumbent
+    fn apply_exclusions(&mut self) {
+        if self.excluded.is_empty() {
+            return;
+        }
+        for minimum in &mut self.category_minimums {
+            minimum
+                .ingredients
+                .retain(|ingredient| !self.excluded.contains(ingredient));
+        }
+        self.incumbent
+            .retain(|cocktail| cocktail.is_disjoint(&self.excluded));
+    }
+}
+
+impl fmt::Debug for BranchBound {
+    /// Summarises the search state rather than dumping the caches, which hold
+    /// an entry for every cocktail
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("BranchBound")
+            .field("max_size", &self.max_size)
+            .field("category_minimums", &self.category_minimums)
+            .field("category_maximums", &self.category_maximums)
+            .field("soft_limits", &self.soft_limits)
+            .field("max_depth", &self.max_depth)
+            .field("highest", &self.highest)
+            .field("highest_ingredients", &self.highest_ingredients)
+            .field("snapshot", &self.snapshot())
+            .finish_non_exhaustive()
+    }
+}
+
+// keep BranchBound usable from worker threads and async runtimes' blocking pools
+const _: fn() = || {
+    fn assert_send<T: Send>() {}
+    assert_send::<BranchBound>();
+};
+
+/// Which of the [`BOUND_FUNCTIONS`] to consult: those for `reasons` if given, and
+/// otherwise all but the [expensive](BoundCost::Expensive) ones, unless they're wanted
+fn consulted(reasons: Option<&[Prune]>, expensive: bool) -> [bool; BOUND_FUNCTIONS.len()] {
+    std::array::from_fn(|index| {
+        let (reason, _, cost) = BOUND_FUNCTIONS[index];
+        match reasons {
+            Some(reasons) => reasons.contains(&reason),
+            None => cost < BoundCost::Expensive || expensive,
+        }
+    })
+}
+
+/// How many ingredients sorted `a` and `b` have in common
+fn shared(a: &[Ingredienti], b: &[Ingredienti]) -> usize {
+    let (mut i, mut j, mut count) = (0, 0, 0);
+    while i < a.len() && j < b.len() {
+        match a[i].cmp(&b[j]) {
+            Ordering::Less => i += 1,
+            Ordering::Greater => j += 1,
+            Ordering::Equal => {
+                count += 1;
+                i += 1;
+                j += 1;
+            }
+        }
+    }
+    count
+}
+
+/// `ingredients`, along with those the cocktails in `made` need
+fn with_made(ingredients: &IngredientSeti, made: &FxHashSet<IngredientSeti>) -> IngredientSeti {
+    let mut ingredients = ingredients.clone();
+    ingredients.extend(made.iter().flatten().copied());
+    ingredients
+}
+
+/// The [`BOUND_FUNCTIONS`] cheapest tier first, and in the order they're registered in
+/// within a tier
+fn tiered_order() -> BoundOrder {
+    let mut order: BoundOrder = std::array::from_fn(|index| index);
+    order.sort_by_key(|&index| BOUND_FUNCTIONS[index].2);
+    order
+}
+
+/// Order the [`BOUND_FUNCTIONS`] within each tier by the time each has taken per node
+/// it pruned, given how many times each was evaluated, for how long in all, and how
+/// many nodes it pruned. Those evaluated too few times to tell go first, so that they
+/// soon are
+fn adapted_order(usage: [(u32, Duration, u32); BOUND_FUNCTIONS.len()]) -> BoundOrder {
+    let cost = |(evaluated, time, pruned): (u32, Duration, u32)| {
+        if evaluated < ADAPTIVE_WARMUP {
+            0.0
+        } else if pruned == 0 {
+            f64::INFINITY
+        } else {
+            time.as_secs_f64() / f64::from(pruned)
+        }
+    };
+    let mut order = tiered_order();
+    // stable, so bounds that cost the same stay in the order they're registered in
+    order.sort_by(|&a, &b| {
+        BOUND_FUNCTIONS[a]
+            .2
+            .cmp(&BOUND_FUNCTIONS[b].2)
+            .then(cmp_f64(cost(usage[a]), cost(usage[b])))
+    });
+    order
 }
 
 /// This will obviously explode on NaN values
@@ -36,27 +1311,478 @@ fn cmp_f64(a: f64, b: f64) -> Ordering {
     } else if a > b {
         return Ordering::Greater;
     }
-    Ordering::Equal
-}
+    Ordering::Equal
+}
+
+/// How many of the cocktails adding `increases` have to be dropped, largest first, to
+/// take away `excess`: all of them, if that's still not enough. Increases are a
+/// cocktail's worth of ingredients at most, so they're counted rather than sorted
+fn fewest_dropped(increases: impl IntoIterator<Item = usize>, excess: i32) -> usize {
+    let mut counts = vec![];
+    for increase in increases {
+        if increase >= counts.len() {
+            counts.resize(increase + 1, 0);
+        }
+        counts[increase] += 1;
+    }
+    let mut excess = excess.max(0) as usize;
+    let mut dropped = 0;
+    for (increase, &count) in counts.iter().enumerate().rev() {
+        if excess == 0 {
+            break;
+        }
+        let needed = match increase {
+            0 => count,
+            _ => count.min(excess.div_ceil(increase)),
+        };
+        dropped += needed;
+        excess = excess.saturating_sub(needed * increase);
+    }
+    dropped
+}
+
+impl BranchBound {
+    #[must_use]
+    pub fn new(max_calls: i32, max_size: usize) -> BranchBound {
+        BranchBoundBuilder::new(max_calls, max_size).build()
+    }
+
+    /// Summarise the state of the search, e.g. to inspect a long-running search
+    #[must_use]
+    pub fn snapshot(&self) -> SearchSnapshot {
+        let set_bytes = |set: &IngredientSeti| {
+            std::mem::size_of::<IngredientSeti>() + set.len() * std::mem::size_of::<Ingredienti>()
+        };
+        // both caches are keyed by each cocktail, and the sets dominate their size
+        let cache_bytes = self
+            .min_cover
+            .keys()
+            .map(|cocktail| {
+                2 * set_bytes(cocktail) + std::mem::size_of::<i32>() + std::mem::size_of::<f64>()
+            })
+            .sum::<usize>();
+        let average_cocktail = if self.min_cover.is_empty() {
+            0
+        } else {
+            cache_bytes / (2 * self.min_cover.len())
+        };
+        // the table holds each cocktail the search has met twice over, and each level of
+        // the search holds candidate and forbidden bitsets over it, for this node and
+        // for the exclusion branch stacked beside it
+        let table_bytes = 2 * self.cocktails.len() * average_cocktail;
+        let node_bytes = 4 * self.cocktails.len().div_ceil(64) * std::mem::size_of::<u64>();
+        SearchSnapshot {
+            depth: self.depth,
+            nodes: self.counter,
+            calls_remaining: self.calls,
+            incumbent_score: self.highest_score,
+            incumbent_ingredients: self.highest_ingredients.len(),
+            candidates: self.current_candidates,
+            forbidden: self.current_forbidden,
+            cached_cocktails: self.min_cover.len(),
+            estimated_bytes: cache_bytes + table_bytes + (self.depth + 1) * node_bytes,
+        }
+    }
+
+    /// The guaranteed ratio of the score found to the optimal score, if the search
+    /// runs to completion: 1 for an exact search, or `1 / (1 + epsilon)` when
+    /// [approximating](BranchBoundBuilder::approximation)
+    #[must_use]
+    pub fn approximation_factor(&self) -> f64 {
+        1.0 / (1.0 + self.epsilon)
+    }
+
+    /// The incumbent, with its ingredients and score
+    #[must_use]
+    pub fn best(&self) -> Alternative {
+        Alternative {
+            score: self.highest_score,
+            cocktails: self.highest.clone(),
+            ingredients: self.highest_ingredients.clone(),
+        }
+    }
+
+    /// The highest score the search couldn't rule out: the best of the incumbent,
+    /// every node it left unexplored, and, when
+    /// [approximating](BranchBoundBuilder::approximation), what the nodes it pruned
+    /// early could have reached
+    #[must_use]
+    pub fn upper_bound(&self) -> usize {
+        let approximate = (self.highest_score as f64 * (1.0 + self.epsilon)).floor() as usize;
+        self.highest_score.max(self.open_bound).max(approximate)
+    }
+
+    /// How far the incumbent may be from optimal, if the search couldn't prove it
+    /// optimal
+    #[must_use]
+    pub fn gap(&self) -> Option<Gap> {
+        let upper = self.upper_bound();
+        (upper > self.highest_score).then_some(Gap {
+            lower: self.highest_score,
+            upper,
+        })
+    }
+
+    /// The value of `cocktails`: each ungrouped cocktail counts its
+    /// [weight](BranchBoundBuilder::cocktail_weights), 1 by default, as does each
+    /// [variant group](BranchBoundBuilder::variant_group) with at least one member
+    /// present, at the weight of its heaviest member.
+    /// [House cocktails](BranchBoundBuilder::house_cocktails) count `house_weight`.
+    /// Any [penalty](Self::penalty) for the ingredients they need is subtracted
+    #[must_use]
+    pub fn score(&self, cocktails: &FxHashSet<IngredientSeti>) -> usize {
+        let value = self.weighted(cocktails, |cocktail| self.value(cocktail));
+        if self.soft_limits.is_empty() {
+            return value;
+        }
+        value.saturating_sub(self.penalty(&self.ingredients(cocktails)))
+    }
+
+    /// What `ingredients` lose for going over [soft limits](BranchBoundBuilder::soft_limit)
+    #[must_use]
+    pub fn penalty(&self, ingredients: &IngredientSeti) -> usize {
+        self.soft_limits
+            .iter()
+            .map(|limit| limit.penalty(ingredients))
+            .sum()
+    }
+
+    /// The number of distinct drinks among `cocktails`, counting each variant group once
+    #[must_use]
+    pub fn distinct(&self, cocktails: &FxHashSet<IngredientSeti>) -> usize {
+        self.weighted(cocktails, |_| 1)
+    }
+
+    fn weighted(
+        &self,
+        cocktails: &FxHashSet<IngredientSeti>,
+        value: impl Fn(&IngredientSeti) -> usize,
+    ) -> usize {
+        if self.variants.is_empty() {
+            return cocktails.iter().map(value).sum();
+        }
+        // a group is worth its most valuable member
+        let mut groups = FxHashMap::default();
+        let mut ungrouped = 0;
+        for cocktail in cocktails {
+            match self.variants.get(cocktail) {
+                Some(group) => {
+                    let best = groups.entry(group).or_insert(0);
+                    *best = value(cocktail).max(*best);
+                }
+                None => ungrouped += value(cocktail),
+            }
+        }
+        ungrouped + groups.values().sum::<usize>()
+    }
+
+    fn value(&self, cocktail: &IngredientSeti) -> usize {
+        if self.house.contains(cocktail) {
+            self.house_weight
+        } else {
+            self.weight(cocktail)
+        }
+    }
+
+    fn weight(&self, cocktail: &IngredientSeti) -> usize {
+        self.weights.get(cocktail).copied().unwrap_or(1)
+    }
+
+    /// The number of distinct house cocktails among `cocktails`
+    #[must_use]
+    pub fn house_made(&self, cocktails: &FxHashSet<IngredientSeti>) -> usize {
+        cocktails
+            .iter()
+            .filter(|cocktail| self.house.contains(*cocktail))
+            .count()
+    }
+
+    /// The number of ingredients needed to buy `ingredients`, plus whatever
+    /// extra ingredients are needed to meet the category minimums.
+    /// Never decreases as ingredients are added
+    fn padded_len(&self, ingredients: &IngredientSeti) -> usize {
+        self.bought(ingredients)
+            + self
+                .category_minimums
+                .iter()
+                .map(|minimum| minimum.deficit(ingredients))
+                .sum::<usize>()
+    }
+
+    /// The number of `ingredients` that aren't already owned
+    fn bought(&self, ingredients: &IngredientSeti) -> usize {
+        ingredients.len() - ingredients.intersection(&self.owned).count()
+    }
+
+    /// Is the ingredient budget the only limit on what can be bought, so that whether a
+    /// set [fits](Self::fits) depends on nothing but how many it buys?
+    fn unlimited(&self) -> bool {
+        self.category_minimums.is_empty()
+            && self.category_maximums.is_empty()
+            && self.budget.is_none()
+    }
+
+    /// Can `ingredients` be extended into an acceptable solution within budget?
+    fn fits(&self, ingredients: &IngredientSeti) -> bool {
+        self.padded_len(ingredients) <= self.max_size
+            && self.within_maximums(ingredients)
+            && self
+                .budget
+                .is_none_or(|budget| self.cost(ingredients.iter()) <= budget + COST_TOLERANCE)
+    }
+
+    /// Do `ingredients`, and any bought to meet the category minimums, stay within every
+    /// [category maximum](BranchBoundBuilder::category_maximum)?
+    fn within_maximums(&self, ingredients: &IngredientSeti) -> bool {
+        self.category_maximums.iter().all(|maximum| {
+            // padding for a minimum within the category can only come from the category
+            let padding = self
+                .category_minimums
+                .iter()
+                .filter(|minimum| minimum.ingredients.is_subset(&maximum.ingredients))
+                .map(|minimum| minimum.deficit(ingredients))
+                .sum::<usize>();
+            maximum.count(ingredients) + padding <= maximum.maximum
+        })
+    }
+
+    /// Is `ingredients` acceptable as it stands: within budget, and without any
+    /// [forbidden combination](BranchBoundBuilder::add_forbidden)?
+    pub(crate) fn admits(&self, ingredients: &IngredientSeti) -> bool {
+        self.fits(ingredients)
+            && !self
+                .forbidden_combinations
+                .iter()
+                .any(|combination| combination.is_subset(ingredients))
+    }
+
+    /// The total price of `ingredients`, other than those already owned
+    #[must_use]
+    pub fn cost<'a>(&self, ingredients: impl IntoIterator<Item = &'a Ingredienti>) -> f64 {
+        ingredients
+            .into_iter()
+            .filter(|ingredient| !self.owned.contains(ingredient))
+            .filter_map(|ingredient| self.prices.get(ingredient))
+            .sum()
+    }
+
+    /// Ingredients that must be bought in addition to `highest_ingredients`
+    /// to meet the category minimums
+    #[must_use]
+    pub fn padding(&self) -> IngredientSeti {
+        self.category_minimums
+            .iter()
+            .flat_map(|minimum| minimum.padding(&self.highest_ingredients))
+            .collect()
+    }
+
+    /// Search for the best set of each number of ingredients in `sizes`, in turn,
+    /// returning each size's best set
+    ///
+    /// The per-cocktail caches are only computed once, and each search gets the full
+    /// call budget. With `warm_start`, each search starts from the previous size's best
+    /// set, which still fits a larger budget, so it prunes from the outset.
+    /// [`status`](Self::status) is left as the first incomplete search's, if any.
+    pub fn sweep(
+        &mut self,
+        candidates: &FxHashSet<IngredientSeti>,
+        sizes: RangeInclusive<usize>,
+        warm_start: bool,
+    ) -> Vec<(usize, Alternative)> {
+        let mut candidates = candidates.clone();
+        let mut partial = FxHashSet::default();
+        let mut forbidden = None;
+        if self.initial {
+            // only drop the cocktails too large for every size
+            self.max_size = *sizes.end();
+            self.prepare(&mut candidates, &mut partial, &mut forbidden);
+        }
+        let calls = self.calls;
+        let mut status = SearchStatus::Complete;
+        let mut results = vec![];
+        for size in sizes {
+            self.restart(size, calls);
+            if !warm_start || !self.fits(&self.highest_ingredients) {
+                self.highest.clear();
+                self.highest_ingredients.clone_from(&self.required);
+            }
+            self.highest_score = self.score(&self.highest);
+            self.seed_greedy(&(&candidates | &partial));
+            let best = self.search_prepared(&candidates, &partial, &forbidden);
+            if status.completed() {
+                status = self.status;
+            }
+            results.push((
+                size,
+                Alternative {
+                    score: self.highest_score,
+                    cocktails: best,
+                    ingredients: self.highest_ingredients.clone(),
+                },
+            ));
+        }
+        self.status = status;
+        results
+    }
+
+    /// Find the fewest ingredients making cocktails worth at least `target`, as
+    /// [`score`](Self::score) counts them, returning a set that does, or `None` if no
+    /// set within the constraints can
+    ///
+    /// This binary-searches the ingredient budget. Each step looks for any set reaching
+    /// the target within the budget being tried, pruning as though the incumbent fell
+    /// just short of it, and stopping at the first set found. The per-cocktail caches
+    /// are only computed once, and each step gets the full call budget: a step that runs
+    /// out of calls counts as finding nothing, so the answer may then not be the fewest.
+    /// [`status`](Self::status) is left as the first incomplete step's, if any, and
+    /// `max_size` as the answer's size.
+    pub fn min_ingredients_for(
+        &mut self,
+        candidates: &FxHashSet<IngredientSeti>,
+        target: usize,
+    ) -> Option<Alternative> {
+        let mut candidates = candidates.clone();
+        let mut partial = FxHashSet::default();
+        let mut forbidden = None;
+        if self.initial {
+            // any number of ingredients may be needed, so don't drop cocktails for size
+            self.max_size = self.padded_len(&self.ingredients(&candidates));
+            self.prepare(&mut candidates, &mut partial, &mut forbidden);
+        }
+        let calls = self.calls;
+        let mut status = SearchStatus::Complete;
+        let mut low = self.padded_len(&self.required);
+        let high = self.padded_len(&self.ingredients(&(&candidates | &partial)));
+        let mut reach = |bb: &mut BranchBound, size| {
+            let found = bb.reach(size, target, &candidates, &partial, &forbidden, calls);
+            if status.completed() {
+                status = bb.status;
+            }
+            found
+        };
+        let mut found = reach(self, high.max(low))?;
+        let mut high = self.padded_len(&found.ingredients);
+        while low < high {
+            let size = low + (high - low) / 2;
+            match reach(self, size) {
+                Some(smaller) => {
+                    high = self.padded_len(&smaller.ingredients);
+                    found = smaller;
+                }
+                None => low = size + 1,
+            }
+        }
+        self.status = status;
+        self.max_size = high;
+        self.highest.clone_from(&found.cocktails);
+        self.highest_score = found.score;
+        self.highest_ingredients.clone_from(&found.ingredients);
+        Some(found)
+    }
+
+    /// Look for any set of at most `size` ingredients making cocktails worth at least
+    /// `target`, for [`min_ingredients_for`](Self::min_ingredients_for)
+    fn reach(
+        &mut self,
+        size: usize,
+        target: usize,
+        candidates: &FxHashSet<IngredientSeti>,
+        partial: &FxHashSet<IngredientSeti>,
+        forbidden: &Option<FxHashSet<IngredientSeti>>,
+        calls: i32,
+    ) -> Option<Alternative> {
+        self.restart(size, calls);
+        self.highest.clone_from(partial);
+        self.highest_ingredients.clone_from(&self.required);
+        self.highest_score = self.score(partial);
+        if self.highest_score < target && self.fits(&self.required) {
+            // the greedy set often gets there without searching at all
+            let greedy = self.greedy(&(candidates | partial));
+            if greedy.score >= target && self.fits(&greedy.ingredients) {
+                return Some(greedy);
+            }
+            // nothing short of the target is worth keeping, and nothing can tie it
+            // with fewer ingredients than the required ones
+            self.highest_score = target - 1;
+            self.stop_at = Some(target);
+            self.search_prepared(candidates, partial, forbidden);
+            self.stop_at = None;
+        }
+        let score = self.score(&self.highest);
+        (score >= target && self.fits(&self.highest_ingredients)).then(|| Alternative {
+            score,
+            cocktails: self.highest.clone(),
+            ingredients: self.highest_ingredients.clone(),
+        })
+    }
 
-impl BranchBound {
-    #[must_use]
-    pub fn new(max_calls: i32, max_size: usize) -> BranchBound {
-        BranchBound {
-            calls: max_calls,
-            max_size,
-            highest_score: 0usize,
-            highest: FxHashSet::default(),
-            highest_ingredients: BTreeSet::new(),
-            random: rand::thread_rng(),
-            counter: 0,
-            min_cover: FxHashMap::default(),
-            min_amortized_cost: FxHashMap::default(),
-            initial: true,
+    /// Get ready to search again from the initial node, with `size` ingredients and
+    /// `calls` calls, keeping the per-cocktail caches. The incumbent is left to the caller
+    pub(crate) fn restart(&mut self, size: usize, calls: i32) {
+        self.max_size = size;
+        self.calls = calls;
+        self.status = SearchStatus::Complete;
+        self.open_bound = 0;
+        self.alternatives.clear();
+        self.ties.clear();
+        self.frontier.clear();
+        if let Some(table) = &mut self.transpositions {
+            table.clear();
+        }
+    }
+
+    /// Search from the initial node, once it has been [prepared](Self::prepare), in
+    /// parallel if configured to, and otherwise in the configured order
+    fn search_prepared(
+        &mut self,
+        candidates: &FxHashSet<IngredientSeti>,
+        partial: &FxHashSet<IngredientSeti>,
+        forbidden: &Option<FxHashSet<IngredientSeti>>,
+    ) -> FxHashSet<IngredientSeti> {
+        let forbidden = forbidden.clone();
+        match (self.threads, self.order) {
+            (Some(threads), _) => {
+                self.search_parallel(threads, candidates, partial, forbidden.unwrap_or_default())
+            }
+            (None, SearchOrder::DepthFirst) => {
+                self.search(&mut candidates.clone(), &mut partial.clone(), &mut {
+                    forbidden
+                })
+            }
+            (None, order) => {
+                self.search_frontier(order, candidates, partial, forbidden.unwrap_or_default())
+            }
+        }
+    }
+
+    /// Like [`search`](Self::search), but first checks that a search which has already
+    /// started is only continued with cocktails it was started with
+    pub fn try_search(
+        &mut self,
+        candidates: &mut FxHashSet<IngredientSeti>,
+        partial: &mut FxHashSet<IngredientSeti>,
+        forbidden: &mut Option<FxHashSet<IngredientSeti>>,
+    ) -> Result<FxHashSet<IngredientSeti>, Error> {
+        if !self.initial {
+            if let Some(unknown) = candidates
+                .iter()
+                .find(|cocktail| !self.min_cover.contains_key(*cocktail))
+            {
+                return Err(Error::Inconsistent(format!(
+                    "cocktail {unknown:?} wasn't a candidate when the search started"
+                )));
+            }
         }
+        Ok(self.search(candidates, partial, forbidden))
     }
 
-    #[inline(always)]
+    /// Search for the best set of cocktails among `candidates`, in addition to those in
+    /// `partial`, and without making all of any in `forbidden`
+    ///
+    /// The search is depth-first, over an explicit stack of the nodes still to explore
+    /// rather than by recursion, so however deep it goes it can't overflow the call
+    /// stack. If it stops early, out of calls or time or because it was aborted, the
+    /// nodes it didn't get to are kept, and [`resume`](Self::resume) carries on with them.
     pub fn search(
         &mut self,
         candidates: &mut FxHashSet<IngredientSeti>,
@@ -66,163 +1792,934 @@ impl BranchBound {
         // first run-through, so populate min_cover, amortized cost and cocktail cardinality
         // this SHOULD be a great use of Option, but it's actually such a pain to work with
         if self.initial {
-            *forbidden = Some(FxHashSet::default());
-            let mut cardinality = FxHashMap::default();
-            candidates
-                .iter()
-                .flatten()
-                .for_each(|ingredient| *cardinality.entry(ingredient).or_insert(0) += 1);
-
-            // we can calculate the minimum amortized cost for each cocktail:
-            // if we were to have enough
-            // ingredients to make all the cocktails, how much should
-            // we pay, in ingredient-cost, for each cocktail. For
-            // example, if a cocktail has a unique ingredient, and two
-            // other ingredients shared by one other cocktail, then the
-            // amortized cost would be 1/1 + 1/2 + 1/2 = 2
-            //
-            // The minimum amortized cost is a lower bound on how much
-            // we will ever pay in ingredient cost for a cocktail.
-            for cocktail in candidates.iter() {
-                self.min_amortized_cost.insert(
-                    cocktail.clone(),
-                    cocktail
-                        .iter()
-                        .map(|ingredient| 1f64 / f64::from(*cardinality.get(ingredient).unwrap()))
-                        .sum::<f64>(),
-                );
-                self.min_cover.insert(
-                    cocktail.clone(),
-                    *cocktail
-                        .iter()
-                        .map(|ingredient| cardinality.get(ingredient).unwrap())
-                        .min()
-                        .unwrap(),
-                );
+            self.prepare(candidates, partial, forbidden);
+            if self.probing && self.top_k == 1 {
+                self.probe(candidates, partial);
+            }
+            if let Some(restarts) = self.restarts {
+                if self.run_restarts(restarts, candidates, partial, forbidden) {
+                    return self.highest.clone();
+                }
+            }
+            if self.threads.is_some() || self.order != SearchOrder::DepthFirst {
+                return self.search_prepared(candidates, partial, forbidden);
+            }
+        }
+        let base = self.frontier.len();
+        let root = self.root_node(candidates, partial, forbidden.iter().flatten());
+        self.frontier.push(root);
+        self.depth_first(base)
+    }
+
+    /// The node to start searching `candidates` from, in addition to `partial` and
+    /// without making all of any in `forbidden`, numbering any cocktails the search
+    /// hasn't met
+    pub(crate) fn root_node<'a>(
+        &mut self,
+        candidates: &FxHashSet<IngredientSeti>,
+        partial: &FxHashSet<IngredientSeti>,
+        forbidden: impl IntoIterator<Item = &'a IngredientSeti>,
+    ) -> Node {
+        let partial_ingredients = self.ingredients(partial);
+        let table = Arc::make_mut(&mut self.cocktails);
+        Node {
+            candidates: table.intern_all(candidates),
+            partial: partial.clone(),
+            partial_ingredients,
+            forbidden: table.intern_all(forbidden),
+            bought: IngredientSeti::new(),
+            depth: self.depth,
+            discrepancies: 0,
+            branch: Branch::Root,
+        }
+    }
+
+    /// Search the nodes on the frontier above the first `base` depth-first, until
+    /// they're exhausted or the search has to stop
+    fn depth_first(&mut self, base: usize) -> FxHashSet<IngredientSeti> {
+        let depth = self.depth;
+        while self.frontier.len() > base {
+            self.close_subtrees();
+            if self.reached_target() {
+                self.frontier.truncate(base);
+                self.open_subtrees.clear();
+                break;
+            }
+            let node = self
+                .frontier
+                .pop()
+                .expect("the frontier is longer than base");
+            // a pass of a limited discrepancy search can't be resumed as a depth-first one
+            if self.discrepancy_limit.is_none()
+                && self
+                    .checkpointing
+                    .as_ref()
+                    .is_some_and(|checkpointing| self.counter.is_multiple_of(checkpointing.every))
+            {
+                self.save_checkpoint(node.clone());
+            }
+            let Some(children) = self.expand_node(&node) else {
+                // anything left unexplored could still have led to anything its bounds allow
+                let open = std::iter::once(&node)
+                    .chain(&self.frontier[base..])
+                    .map(|node| self.bound(node))
+                    .max()
+                    .unwrap_or(0);
+                self.open_bound = self.open_bound.max(open);
+                self.frontier.push(node);
+                break;
+            };
+            // the include branch is on top, so it's searched first
+            for child in children.into_iter().rev() {
+                if self
+                    .discrepancy_limit
+                    .is_some_and(|limit| child.discrepancies > limit)
+                {
+                    self.discrepancy_cuts += 1;
+                    let bound = self.bound(&child);
+                    self.open_bound = self.open_bound.max(bound);
+                    continue;
+                }
+                self.frontier.push(child);
+            }
+        }
+        if self.status == SearchStatus::Complete && self.frontier.len() <= base {
+            self.close_subtrees();
+        }
+        // a subtree the search stopped in the middle of hasn't been searched
+        self.open_subtrees.clear();
+        self.depth = depth;
+        // the final set will return to the caller
+        self.highest.clone()
+    }
+
+    /// Record the subtrees the depth-first search has finished, now that the frontier is
+    /// back down to where they started
+    fn close_subtrees(&mut self) {
+        while let Some(&(start, fingerprint, score)) = self.open_subtrees.last() {
+            if self.frontier.len() > start {
+                break;
+            }
+            self.open_subtrees.pop();
+            // everything in the subtree scored no more than the incumbent, or was pruned
+            // by a bound no more than it
+            let gain = self.highest_score.saturating_sub(score);
+            if let Some(cache) = &mut self.subtrees {
+                cache.insert(fingerprint, gain);
             }
-            self.initial = false;
         }
-        // begin
+    }
+
+    /// The fingerprint of what's left at a node, if there's a [cache](subtree) and it
+    /// can be consulted
+    fn subtree_fingerprint(
+        &self,
+        candidates: &[&IngredientSeti],
+        partial_ingredients: &IngredientSeti,
+        forbidden: &CocktailSet,
+    ) -> Option<u128> {
+        let cache = self.subtrees.as_ref()?;
+        let additive = self.variants.is_empty()
+            && self.soft_limits.is_empty()
+            && self.category_minimums.is_empty()
+            && self.category_maximums.is_empty()
+            && self.budget.is_none();
+        let exact = self.top_k <= 1
+            && self.max_ties == 0
+            && self.tally.is_none()
+            && self.epsilon == 0.0
+            && self.max_depth.is_none();
+        let sequential = self.threads.is_none() && self.order == SearchOrder::DepthFirst;
+        (additive && exact && sequential).then(|| {
+            let remaining = self
+                .max_size
+                .saturating_sub(self.padded_len(partial_ingredients));
+            let reachable = candidates
+                .iter()
+                .copied()
+                .flatten()
+                .collect::<FxHashSet<_>>();
+            cache.fingerprint(
+                candidates
+                    .iter()
+                    .map(|&cocktail| (self.value(cocktail), cocktail - partial_ingredients)),
+                forbidden
+                    .iter()
+                    .map(|index| &self.cocktails[index] - partial_ingredients)
+                    .filter(|unmet| {
+                        unmet
+                            .iter()
+                            .all(|ingredient| reachable.contains(ingredient))
+                    }),
+                remaining,
+            )
+        })
+    }
+
+    /// Expand `node`, adopting it as the incumbent if it's better, and return its include
+    /// and exclude branches, or nothing if it's pruned. `None` if the search has to stop
+    fn expand_node(&mut self, node: &Node) -> Option<Vec<Node>> {
+        let Node {
+            partial,
+            forbidden,
+            bought,
+            depth,
+            branch,
+            ..
+        } = node;
+        let mut lap = self.profile.is_some().then(Instant::now);
         if self.calls <= 0 {
-            println!("{:?}", "Early return!");
-            return self.highest.clone();
+            self.status = SearchStatus::OutOfCalls;
+            return None;
+        }
+        if self.status == SearchStatus::TimedOut || self.past_deadline(self.counter) {
+            self.status = SearchStatus::TimedOut;
+            return None;
+        }
+        if self.status == SearchStatus::Aborted
+            || self
+                .portfolio
+                .as_ref()
+                .is_some_and(|portfolio| portfolio.finished())
+        {
+            self.status = SearchStatus::Aborted;
+            return None;
         }
         self.calls -= 1;
         self.counter += 1;
-        let score = partial.len();
+        self.depth = *depth;
+        self.stats.expand(*depth);
+        let calls = self.calls;
+        if self.observe(|observer| observer.on_node_expanded(*depth, calls))
+            == Some(ControlFlow::Break(()))
+        {
+            self.status = SearchStatus::Aborted;
+            return None;
+        }
+        // a handle of its own, so the candidates can borrow from the table while the
+        // rest of the search changes
+        let table = Arc::clone(&self.cocktails);
+        let candidates = &table.resolve(&node.candidates);
+        self.current_candidates = candidates.len();
+        self.current_forbidden = forbidden.len();
+        let score = self.score(partial);
+
+        // the ingredients the cocktails made so far need, kept up to date by branching
+        let partial_ingredients = &node.partial_ingredients;
 
-        if score > self.highest_score {
+        let highest_len = self.padded_len(&self.highest_ingredients);
+        if self.improves(score, partial_ingredients, self.highest_score, highest_len) {
             self.highest.clone_from(partial);
             self.highest_score = score;
+            self.highest_ingredients.clone_from(partial_ingredients);
+            self.stats
+                .improved(score, partial_ingredients.len(), self.counter);
+            self.observe(|observer| observer.on_new_incumbent(score, partial_ingredients));
+            if let Some(portfolio) = &self.portfolio {
+                portfolio.offer(self, score, partial, partial_ingredients);
+            }
+        }
+        // another member of the portfolio may have done better
+        if let Some(better) = self
+            .portfolio
+            .as_ref()
+            .and_then(|portfolio| portfolio.better_than(self.highest_score))
+        {
+            self.highest = better.cocktails;
+            self.highest_score = better.score;
+            self.highest_ingredients = better.ingredients;
+            self.stats
+                .improved(better.score, self.highest_ingredients.len(), self.counter);
+        }
+        if self.top_k > 1 {
+            let mut alternatives = std::mem::take(&mut self.alternatives);
+            self.offer(&mut alternatives, score, partial, partial_ingredients);
+            self.alternatives = alternatives;
+        }
+        if self.max_ties > 0 {
+            let mut ties = std::mem::take(&mut self.ties);
+            self.tie(
+                &mut ties,
+                self.highest_score,
+                score,
+                partial,
+                partial_ingredients,
+            );
+            self.ties = ties;
+        }
+        if self.tally.is_some()
+            && score > 0
+            && score == self.highest_score
+            && self.fits(partial_ingredients)
+        {
+            let size = self.padded_len(partial_ingredients);
+            if let Some(tally) = &mut self.tally {
+                tally.add(partial_ingredients, size);
+            }
+        }
+        self.record(&mut lap, (*depth, *branch), Phase::Prepare);
+        // what an ingredient branch bought counts against the budget from here on,
+        // whether or not anything uses it yet
+        let partial_ingredients = partial_ingredients | bought;
+        let fingerprint = self.transpositions.as_ref().map(|table| {
+            let depth = self.max_depth.map(|_| *depth);
+            table.fingerprint(&partial_ingredients, &node.candidates, forbidden, depth)
+        });
+        if let Some((table, fingerprint)) = self.transpositions.as_ref().zip(fingerprint) {
+            if table.contains(fingerprint) {
+                self.stats.prune(Prune::Transposition);
+                self.observe(|observer| observer.on_prune(Prune::Transposition));
+                return Some(vec![]);
+            }
+        }
+        let subtree = self.subtree_fingerprint(candidates, &partial_ingredients, forbidden);
+        if let Some(gain) = subtree.and_then(|fingerprint| self.subtrees.as_ref()?.get(fingerprint))
+        {
+            if score.saturating_add(gain) < self.highest_score {
+                self.stats.prune(Prune::Subtree);
+                self.observe(|observer| observer.on_prune(Prune::Subtree));
+                return Some(vec![]);
+            }
+        }
+        let bar = self.bar(
+            self.highest_score,
+            &self.highest_ingredients,
+            &self.alternatives,
+            &self.ties,
+        );
+        let mut times = BoundTimes::default();
+        let explore = self.keep_exploring(
+            candidates,
+            partial,
+            &partial_ingredients,
+            bar,
+            &self.bound_order,
+            &mut times,
+        );
+        for ((reason, _, _), time) in BOUND_FUNCTIONS.iter().zip(times) {
+            if let Some(time) = time {
+                self.stats.evaluate(*reason, time);
+            }
+        }
+        if self.adaptive_bounds && self.counter.is_multiple_of(TIME_CHECK_INTERVAL) {
+            self.bound_order = adapted_order(std::array::from_fn(|index| {
+                let reason = BOUND_FUNCTIONS[index].0;
+                let usage = self.stats.bounds.get(&reason).copied().unwrap_or_default();
+                let pruned = self.stats.prunes.get(&reason).copied().unwrap_or(0);
+                (usage.evaluated, usage.time, pruned)
+            }));
+        }
+        self.record(&mut lap, (*depth, *branch), Phase::Bounds);
+        if let Err(reason) = explore {
+            self.stats.prune(reason);
+            self.observe(|observer| observer.on_prune(reason));
+            return Some(vec![]);
+        }
+        if self.max_depth.is_some_and(|max| *depth >= max) {
+            self.depth_truncations += 1;
+            self.open_bound =
+                self.open_bound
+                    .max(self.node_bound(candidates, partial, &partial_ingredients));
+            return Some(vec![]);
+        }
+        if let Some(fingerprint) = fingerprint {
+            let bound = self.node_bound(candidates, partial, &partial_ingredients);
+            if let Some(table) = &mut self.transpositions {
+                table.insert(fingerprint, bound);
+            }
+        }
+        let Some(pivot) = self.pivot(candidates, &partial_ingredients) else {
+            return Some(vec![]);
+        };
+        if let Some(fingerprint) = subtree {
+            // the node has left the frontier, and its subtree is finished once the
+            // frontier is back down to where it was
+            self.open_subtrees
+                .push((self.frontier.len(), fingerprint, score));
+        }
+        let mut unmet = std::mem::take(&mut self.unmet);
+        let children = match pivot {
+            Pivot::Cocktail(best) => {
+                self.cocktail_branches(node, &partial_ingredients, best, &mut unmet)
+            }
+            Pivot::Ingredient(ingredient) => {
+                self.ingredient_branches(node, &partial_ingredients, ingredient, &mut unmet)
+            }
+        };
+        self.unmet = unmet;
+        self.record(&mut lap, (*depth, *branch), Phase::Branch);
+        Some(children)
+    }
+
+    /// What to branch on at a node with `candidates`, whose cocktails and bought
+    /// ingredients come to `partial_ingredients`: the ingredient the
+    /// [branching strategy](BranchingStrategy) names if it branches on ingredients, and
+    /// otherwise the candidate it prefers
+    fn pivot(
+        &mut self,
+        candidates: &[&IngredientSeti],
+        partial_ingredients: &IngredientSeti,
+    ) -> Option<Pivot> {
+        if let Some(ingredient) = self
+            .branching
+            .ingredient(self, candidates, partial_ingredients)
+        {
+            return Some(Pivot::Ingredient(ingredient));
         }
+        let mut random = self.random.take();
+        let best = self.choose(candidates, partial_ingredients, random.as_mut());
+        self.random = random;
+        best.map(Pivot::Cocktail)
+    }
+
+    /// The include and exclude branches of `node` on the candidate `best`
+    fn cocktail_branches(
+        &self,
+        node: &Node,
+        partial_ingredients: &IngredientSeti,
+        best: IngredientSeti,
+        unmet: &mut Unmet,
+    ) -> Vec<Node> {
+        let (covered, permitted) = self.include_branch(
+            &node.candidates,
+            partial_ingredients,
+            &best,
+            Some(&node.forbidden),
+            unmet,
+        );
+        let best = self
+            .cocktails
+            .index_of(&best)
+            .expect("candidates are in the table");
+        let remaining = self.exclude_branch(&node.candidates, partial_ingredients, best);
+        let mut new_forbidden = node.forbidden.clone();
+        new_forbidden.insert(best);
+        vec![
+            Node {
+                candidates: permitted,
+                partial: &node.partial | &covered,
+                partial_ingredients: with_made(&node.partial_ingredients, &covered),
+                forbidden: node.forbidden.clone(),
+                bought: node.bought.clone(),
+                depth: node.depth + 1,
+                discrepancies: node.discrepancies,
+                branch: Branch::Include,
+            },
+            Node {
+                candidates: remaining,
+                partial: node.partial.clone(),
+                partial_ingredients: node.partial_ingredients.clone(),
+                forbidden: new_forbidden,
+                bought: node.bought.clone(),
+                depth: node.depth + 1,
+                discrepancies: node.discrepancies + 1,
+                branch: Branch::Exclude,
+            },
+        ]
+    }
+
+    /// The branches of `node` buying `ingredient`, and ruling it out
+    fn ingredient_branches(
+        &self,
+        node: &Node,
+        partial_ingredients: &IngredientSeti,
+        ingredient: Ingredienti,
+        unmet: &mut Unmet,
+    ) -> Vec<Node> {
+        let (covered, permitted) = self.include_branch(
+            &node.candidates,
+            partial_ingredients,
+            &IngredientSeti::from([ingredient]),
+            Some(&node.forbidden),
+            unmet,
+        );
+        let mut bought = node.bought.clone();
+        bought.insert(ingredient);
+        // nothing can be completed with it ruled out, not even what's forbidden
+        let without = |cocktails: &CocktailSet| {
+            let mut kept = cocktails.clone();
+            kept.retain(|index| !self.cocktails[index].contains(&ingredient));
+            kept
+        };
+        vec![
+            Node {
+                candidates: permitted,
+                partial: &node.partial | &covered,
+                partial_ingredients: with_made(&node.partial_ingredients, &covered),
+                forbidden: node.forbidden.clone(),
+                bought,
+                depth: node.depth + 1,
+                discrepancies: node.discrepancies,
+                branch: Branch::Include,
+            },
+            Node {
+                candidates: without(&node.candidates),
+                partial: node.partial.clone(),
+                partial_ingredients: node.partial_ingredients.clone(),
+                forbidden: without(&node.forbidden),
+                bought: node.bought.clone(),
+                depth: node.depth + 1,
+                discrepancies: node.discrepancies + 1,
+                branch: Branch::Exclude,
+            },
+        ]
+    }
+
+    /// Populate the per-cocktail caches, and move anything the required ingredients
+    /// already make into `partial`
+    fn prepare(
+        &mut self,
+        candidates: &mut FxHashSet<IngredientSeti>,
+        partial: &mut FxHashSet<IngredientSeti>,
+        forbidden: &mut Option<FxHashSet<IngredientSeti>>,
+    ) {
+        *forbidden = Some(self.forbidden_combinations.iter().cloned().collect());
+        self.deadline = self.time_limit.map(|limit| Instant::now() + limit);
+        self.stats.start();
+        // deeper down, candidates are only kept if they can be added without completing
+        // a forbidden combination, so the initial ones must be too
+        for (_, reason) in self.reduce(candidates) {
+            self.stats.eliminate(reason);
+        }
+        // anything makeable from the required ingredients alone is made by every solution
+        if !self.required.is_empty() {
+            candidates.retain(|cocktail| {
+                let covered = cocktail.is_subset(&self.required);
+                if covered {
+                    partial.insert(cocktail.clone());
+                }
+                !covered
+            });
+            self.highest_ingredients
+                .extend(self.required.iter().copied());
+        }
+        self.cache_difficulties(candidates);
+        if !self.house.is_empty() {
+            self.house_weight = candidates
+                .iter()
+                .chain(partial.iter())
+                .map(|cocktail| self.weight(cocktail))
+                .sum::<usize>()
+                + 1;
+            self.highest_score = self.score(&self.highest);
+        }
+        // a good incumbent from the start lets the bounds prune straight away
+        if let Some(initial) = self.initial_solution.clone() {
+            self.seed_initial(&(&*candidates | &*partial), &initial);
+        }
+        self.seed_greedy(&(&*candidates | &*partial));
+        self.initial = false;
+    }
+
+    /// Fill the per-cocktail caches for `candidates`
+    pub(crate) fn cache_difficulties(&mut self, candidates: &FxHashSet<IngredientSeti>) {
+        // required ingredients are in every solution, so only the rest make a cocktail
+        // harder to justify. In particular, a cocktail whose only unique ingredient is
+        // required doesn't need an ingredient budget of its own
+        let unmet = candidates
+            .iter()
+            .map(|cocktail| (cocktail, cocktail - &self.required))
+            .collect::<Vec<(&IngredientSeti, IngredientSeti)>>();
+        let difficulties = analysis::difficulty(unmet.iter().map(|(_, rest)| rest));
+        for (cocktail, rest) in &unmet {
+            let difficulty = &difficulties[rest];
+            self.min_amortized_cost
+                .insert((*cocktail).clone(), difficulty.amortized_cost);
+            self.min_cover
+                .insert((*cocktail).clone(), difficulty.min_cover);
+        }
+    }
+
+    /// Call the observer, if there is one
+    fn observe<T>(&self, f: impl FnOnce(&mut dyn SearchObserver) -> T) -> Option<T> {
+        self.observer.as_ref().map(|observer| observer.with(f))
+    }
+
+    /// Has the deadline passed? Only checked every [`TIME_CHECK_INTERVAL`] `nodes`
+    fn past_deadline(&self, nodes: u32) -> bool {
+        nodes.is_multiple_of(TIME_CHECK_INTERVAL)
+            && self
+                .deadline
+                .is_some_and(|deadline| Instant::now() >= deadline)
+    }
 
-        // what cocktails could be added without blowing our ingredient budget?
-        // this will be empty on the first iteration
-        let partial_ingredients = partial
+    /// The ingredients needed to make `cocktails`, along with the required ones
+    fn ingredients(&self, cocktails: &FxHashSet<IngredientSeti>) -> IngredientSeti {
+        let mut ingredients = cocktails
             .iter()
             .flatten()
             .copied()
             .collect::<IngredientSeti>();
-        let keep_exploring = self.keep_exploring(candidates, partial, &partial_ingredients);
+        ingredients.extend(self.required.iter().copied());
+        ingredients
+    }
 
-        if keep_exploring {
-            // new best heuristic: pick the candidate cocktail
-            // which is the "least unique" in its ingredient list
-            let best = candidates
-                .iter()
-                .min_by(|a, b| {
-                    cmp_f64(
-                        *self.min_amortized_cost.get(a).unwrap(),
-                        *self.min_amortized_cost.get(b).unwrap(),
-                    )
-                })
-                .unwrap()
-                .clone();
-            let new_partial_ingredients = &partial_ingredients | &best;
-            let covered_candidates = candidates
-                .iter()
-                .filter(|&cocktail| {
-                    cocktail.is_subset(&new_partial_ingredients)
-                        || cocktail == &new_partial_ingredients
-                })
-                .cloned()
-                .collect();
-            let mut permitted_candidates = FxHashSet::default();
-            (&*candidates - &covered_candidates)
-                .iter()
-                .for_each(|cocktail| {
-                    let extended_ingredients = cocktail | &new_partial_ingredients;
-                    if extended_ingredients.len() <= self.max_size {
-                        // when we branch, we need to not only remove a cocktail
-                        // from the candidate set, but ensure that it's impossible
-                        // for the final ingredient list to be a superset of the cocktail.
-                        // otherwise, we could undercount the score of the branch.
-                        // this is O(N^2), alas.
-                        let forbidden_cover =
-                            forbidden
-                                .as_mut()
-                                .unwrap()
-                                .iter()
-                                .any(|forbidden_cocktail| {
-                                    forbidden_cocktail.is_subset(&extended_ingredients)
-                                        || forbidden_cocktail == &extended_ingredients
-                                });
-                        if !forbidden_cover {
-                            permitted_candidates.insert(cocktail.clone());
-                        }
-                    }
-                });
+    /// Would `ingredients`, making cocktails worth `score`, beat an incumbent worth
+    /// `highest_score` that needs `highest_len` ingredients?
+    fn improves(
+        &self,
+        score: usize,
+        ingredients: &IngredientSeti,
+        highest_score: usize,
+        highest_len: usize,
+    ) -> bool {
+        // secondary objective: among sets making the same number of cocktails,
+        // prefer the one needing the fewest ingredients
+        self.fits(ingredients)
+            && (score > highest_score
+                || (score == highest_score && self.padded_len(ingredients) < highest_len))
+    }
+
+    /// The score and padded length a branch must be able to beat to be worth exploring:
+    /// the incumbent's or, when keeping the top k, the k-th best set's. While collecting
+    /// ties or counting optima, matching the score is enough
+    fn bar(
+        &self,
+        highest_score: usize,
+        highest_ingredients: &IngredientSeti,
+        alternatives: &[Alternative],
+        ties: &[Alternative],
+    ) -> (usize, usize) {
+        let (score, len) = if self.top_k <= 1 {
+            (highest_score, self.padded_len(highest_ingredients))
+        } else {
+            match alternatives.get(self.top_k - 1) {
+                Some(kth) => (kth.score, self.padded_len(&kth.ingredients)),
+                // until there are k sets, anything feasible will do
+                None => (0, usize::MAX),
+            }
+        };
+        if ties.len() < self.max_ties || self.tally.is_some() {
+            (score, usize::MAX)
+        } else {
+            (score, len)
+        }
+    }
+
+    /// Add `cocktails` to `alternatives` if it's among the `top_k` best distinct
+    /// ingredient sets
+    fn offer(
+        &self,
+        alternatives: &mut Vec<Alternative>,
+        score: usize,
+        cocktails: &FxHashSet<IngredientSeti>,
+        ingredients: &IngredientSeti,
+    ) {
+        // a set making nothing is no alternative, however few others there are
+        if score == 0 || !self.fits(ingredients) {
+            return;
+        }
+        let key = |score: usize, ingredients: &IngredientSeti| {
+            (std::cmp::Reverse(score), self.padded_len(ingredients))
+        };
+        if let Some(existing) = alternatives
+            .iter()
+            .position(|alternative| &alternative.ingredients == ingredients)
+        {
+            if key(score, ingredients) >= key(alternatives[existing].score, ingredients) {
+                return;
+            }
+            alternatives.remove(existing);
+        } else if alternatives.len() >= self.top_k
+            && alternatives
+                .last()
+                .is_some_and(|kth| key(score, ingredients) >= key(kth.score, &kth.ingredients))
+        {
+            return;
+        }
+        let position = alternatives.partition_point(|alternative| {
+            key(alternative.score, &alternative.ingredients) <= key(score, ingredients)
+        });
+        alternatives.insert(
+            position,
+            Alternative {
+                score,
+                cocktails: cocktails.clone(),
+                ingredients: ingredients.clone(),
+            },
+        );
+        alternatives.truncate(self.top_k);
+    }
+
+    /// Add `cocktails` to `ties` if they score as much as an incumbent scoring
+    /// `highest_score`, after dropping any ties it has since beaten
+    fn tie(
+        &self,
+        ties: &mut Vec<Alternative>,
+        highest_score: usize,
+        score: usize,
+        cocktails: &FxHashSet<IngredientSeti>,
+        ingredients: &IngredientSeti,
+    ) {
+        ties.retain(|tie| tie.score == highest_score);
+        if score == 0
+            || score != highest_score
+            || ties.len() >= self.max_ties
+            || !self.fits(ingredients)
+            || ties.iter().any(|tie| &tie.ingredients == ingredients)
+        {
+            return;
+        }
+        ties.push(Alternative {
+            score,
+            cocktails: cocktails.clone(),
+            ingredients: ingredients.clone(),
+        });
+    }
+
+    /// The candidate to branch on, as the [branching strategy](BranchingStrategy)
+    /// prefers, breaking ties with `random` if given
+    fn choose(
+        &self,
+        candidates: &[&IngredientSeti],
+        partial_ingredients: &IngredientSeti,
+        random: Option<&mut StdRng>,
+    ) -> Option<IngredientSeti> {
+        let costs = candidates
+            .iter()
+            .map(|&cocktail| {
+                let cost = self
+                    .branching
+                    .cost(self, cocktail, candidates, partial_ingredients);
+                (cocktail, cost)
+            })
+            .collect::<Vec<(&IngredientSeti, f64)>>();
+        // by default, the candidate which is the "least unique" in its ingredient list
+        let &(best, cost) = costs.iter().min_by(|a, b| cmp_f64(a.1, b.1))?;
+        let Some(random) = random else {
+            return Some(best.clone());
+        };
+        // during a restart pass, nearly as cheap counts as tied
+        let slack = if cost.is_finite() {
+            self.tie_slack * cost.abs()
+        } else {
+            0.0
+        };
+        // sorted, so the draw doesn't depend on hash order
+        let mut ties = costs
+            .iter()
+            .filter(|(_, other)| *other <= cost + slack)
+            .map(|&(cocktail, _)| cocktail)
+            .collect::<Vec<_>>();
+        ties.sort_unstable();
+        ties.choose(random).map(|&cocktail| cocktail.clone())
+    }
 
-            self.search(
-                &mut permitted_candidates,
-                &mut (&*partial | &covered_candidates),
-                forbidden,
+    /// The branch adding `best`: the candidates it makes as a side effect, and those
+    /// that can still be added within budget without making a forbidden cocktail
+    fn include_branch(
+        &self,
+        candidates: &CocktailSet,
+        partial_ingredients: &IngredientSeti,
+        best: &IngredientSeti,
+        forbidden: Option<&CocktailSet>,
+        unmet: &mut Unmet,
+    ) -> (FxHashSet<IngredientSeti>, CocktailSet) {
+        let new_partial_ingredients = partial_ingredients | best;
+        // without category or spending limits, whether a candidate fits only depends on
+        // how many ingredients it adds, so the union needn't be built to find out
+        let bought = self
+            .unlimited()
+            .then(|| self.bought(&new_partial_ingredients));
+        // what each forbidden cocktail still needs is the same for every candidate
+        unmet.clear();
+        for forbidden_cocktail in forbidden.into_iter().flat_map(CocktailSet::iter) {
+            unmet.push(
+                self.cocktails[forbidden_cocktail]
+                    .difference(&new_partial_ingredients)
+                    .copied(),
             );
+        }
+        let mut covered_candidates = FxHashSet::default();
+        let mut permitted_candidates = candidates.clone();
+        permitted_candidates.retain(|index| {
+            let cocktail = &self.cocktails[index];
+            if cocktail.is_subset(&new_partial_ingredients) {
+                covered_candidates.insert(cocktail.clone());
+                return false;
+            }
+            let fits = match bought {
+                Some(bought) => {
+                    let added = cocktail
+                        .difference(&new_partial_ingredients)
+                        .filter(|ingredient| !self.owned.contains(ingredient))
+                        .count();
+                    bought + added <= self.max_size
+                }
+                None => self.fits(&(cocktail | &new_partial_ingredients)),
+            };
+            // when we branch, we need to not only remove a cocktail
+            // from the candidate set, but ensure that it's impossible
+            // for the final ingredient list to be a superset of the cocktail.
+            // otherwise, we could undercount the score of the branch.
+            // this is O(N^2), alas.
+            fits && !unmet.completed_by(cocktail)
+        });
+        (covered_candidates, permitted_candidates)
+    }
 
-            let mut remaining = candidates.clone();
-            remaining.remove(&best);
-            remaining.retain(|cocktail| {
-                let test = cocktail | &partial_ingredients;
-                !best.is_subset(&test) || best != test
+    /// The candidates of the branch forbidding the candidate numbered `best`
+    fn exclude_branch(
+        &self,
+        candidates: &CocktailSet,
+        partial_ingredients: &IngredientSeti,
+        best: usize,
+    ) -> CocktailSet {
+        let mut remaining = candidates.clone();
+        remaining.remove(best);
+        // drop any cocktail that would complete `best` by itself, comparing sizes rather
+        // than building the union
+        let best = &self.cocktails[best];
+        if partial_ingredients.is_subset(best) {
+            remaining.retain(|index| {
+                let cocktail = &self.cocktails[index];
+                !cocktail.is_subset(best)
+                    || cocktail.union(partial_ingredients).count() != best.len()
             });
-            let mut new_forbidden = forbidden.as_ref().unwrap().clone();
-            new_forbidden.insert(best);
+        }
+        remaining
+    }
 
-            self.search(&mut remaining, partial, &mut Some(new_forbidden));
+    /// A cocktail's minimum amortized cost. Cocktails that weren't candidates when the
+    /// search started are considered last
+    pub(crate) fn amortized_cost(&self, cocktail: &IngredientSeti) -> f64 {
+        self.min_amortized_cost
+            .get(cocktail)
+            .copied()
+            .unwrap_or(f64::INFINITY)
+    }
+
+    /// Charge the time since `lap` to `phase` of the current node, and restart it
+    fn record(&mut self, lap: &mut Option<Instant>, node: (usize, Branch), phase: Phase) {
+        if let (Some(profile), Some(start)) = (self.profile.as_mut(), lap.as_mut()) {
+            let now = Instant::now();
+            profile.record(node.0, node.1, phase, now - *start);
+            *start = now;
         }
-        // search() called from inner loop instances will return to the callee at this point
-        // once those are exhausted, the final set will return to the caller
-        self.highest.clone()
     }
 
     fn keep_exploring(
         &self,
-        candidates: &mut FxHashSet<IngredientSeti>,
-        partial: &mut FxHashSet<IngredientSeti>,
+        candidates: &[&IngredientSeti],
+        partial: &FxHashSet<IngredientSeti>,
         partial_ingredients: &IngredientSeti,
-    ) -> bool {
-        let threshold = (self.highest_score - partial.len()) as i32;
-        let bound_functions = [
-            Self::total_bound,
-            Self::singleton_bound,
-            Self::concentration_bound,
-        ];
-        for func in bound_functions {
-            let bound = func(self, candidates, partial, partial_ingredients);
-            if bound <= threshold {
-                return false;
+        (highest_score, highest_len): (usize, usize),
+        order: &BoundOrder,
+        times: &mut BoundTimes,
+    ) -> Result<(), Prune> {
+        // in approximate mode, only explore branches that could beat
+        // the incumbent by more than the approximation factor. Weighted scores can
+        // outgrow the bounds' i32, so compare in i64
+        let target = if self.epsilon > 0.0 {
+            (highest_score as f64 * (1.0 + self.epsilon)).floor() as i64
+        } else {
+            highest_score as i64
+        };
+        // the bounds count only what more cocktails add. Ingredients are never removed
+        // along a branch, so neither is any penalty already in the partial score
+        let threshold = target - self.score(partial) as i64;
+        let top_values = self.top_values(candidates);
+        // a branch that can at best tie the incumbent is only worth exploring
+        // if it could tie using fewer ingredients. Ingredients are never removed
+        // along a branch, so that's impossible if we've already used as many
+        let tie_possible = self.padded_len(partial_ingredients) < highest_len;
+        // nor can an infeasible branch ever become feasible
+        if !self.fits(partial_ingredients) {
+            return Err(Prune::Infeasible);
+        }
+        let node = BoundContext::new(candidates, partial, partial_ingredients);
+        for &index in order {
+            let (reason, func, _) = BOUND_FUNCTIONS[index];
+            if !self.consulted[index] {
+                continue;
+            }
+            let started = Instant::now();
+            let mut bound = i64::from(func(self, &node));
+            times[index] = Some(started.elapsed());
+            if let Some(top_values) = &top_values {
+                bound = top_values[(bound.max(0) as usize).min(top_values.len() - 1)] as i64;
+            }
+            if bound < threshold || (bound == threshold && (self.epsilon > 0.0 || !tie_possible)) {
+                return Err(reason);
             };
         }
-        true
+        Ok(())
     }
 
-    fn total_bound(
+    /// The most `partial`, whose cocktails and bought ingredients come to
+    /// `partial_ingredients`, and the cocktails it could go on to make are worth, by the
+    /// tightest bound. Nothing, if it's already infeasible
+    fn node_bound(
         &self,
-        candidates: &FxHashSet<IngredientSeti>,
-        _partial: &FxHashSet<IngredientSeti>,
-        _partial_ingredients: &IngredientSeti,
-    ) -> i32 {
-        candidates.len() as i32
+        candidates: &[&IngredientSeti],
+        partial: &FxHashSet<IngredientSeti>,
+        partial_ingredients: &IngredientSeti,
+    ) -> usize {
+        if !self.fits(partial_ingredients) {
+            return 0;
+        }
+        self.bound_given(candidates, partial, partial_ingredients)
+    }
+
+    /// The most `partial`, needing `partial_ingredients`, and the cocktails among
+    /// `candidates` it could go on to make are worth, by the tightest bound
+    pub(crate) fn bound_given(
+        &self,
+        candidates: &[&IngredientSeti],
+        partial: &FxHashSet<IngredientSeti>,
+        partial_ingredients: &IngredientSeti,
+    ) -> usize {
+        let node = BoundContext::new(candidates, partial, partial_ingredients);
+        // making every candidate is always possible, as far as no bound can tell
+        let bound = BOUND_FUNCTIONS
+            .iter()
+            .zip(self.consulted)
+            .filter(|(_, consulted)| *consulted)
+            .map(|((_, func, _), _)| func(self, &node))
+            .min()
+            .map_or(candidates.len(), |bound| bound.max(0) as usize);
+        let more = match self.top_values(candidates) {
+            Some(top_values) => top_values[bound.min(top_values.len() - 1)],
+            None => bound,
+        };
+        self.score(partial) + more
+    }
+
+    /// The bounds count cocktails. With house cocktails or weights, k more cocktails
+    /// are worth at most the k most valuable candidates: the kth of these prefix sums
+    fn top_values(&self, candidates: &[&IngredientSeti]) -> Option<Vec<usize>> {
+        (!self.house.is_empty() || !self.weights.is_empty()).then(|| {
+            let mut values = candidates
+                .iter()
+                .map(|cocktail| self.value(cocktail))
+                .collect::<Vec<usize>>();
+            values.sort_unstable_by(|a, b| b.cmp(a));
+            std::iter::once(0)
+                .chain(values.into_iter().scan(0, |total, value| {
+                    *total += value;
+                    Some(*total)
+                }))
+                .collect::<Vec<usize>>()
+        })
+    }
+
+    /// Every candidate could be made, but variants of a drink already made add nothing
+    fn total_bound(&self, node: &BoundContext) -> i32 {
+        if self.variants.is_empty() {
+            return node.candidates.len() as i32;
+        }
+        let mut groups = node
+            .partial
+            .iter()
+            .filter_map(|cocktail| self.variants.get(cocktail))
+            .collect::<FxHashSet<&usize>>();
+        node.candidates
+            .iter()
+            .filter(|cocktail| match self.variants.get(*cocktail) {
+                Some(group) => groups.insert(group),
+                None => true,
+            })
+            .count() as i32
     }
 
     /// There are many cocktails that have an unique ingredient.
@@ -231,17 +2728,40 @@ impl BranchBound {
     /// one ingredient from our ingredient budget, and the total
     /// possible increase due to these unique cocktails is bounded
     /// by the ingredient budget
-    fn singleton_bound(
-        &self,
-        candidates: &FxHashSet<IngredientSeti>,
-        _partial: &FxHashSet<IngredientSeti>,
-        partial_ingredients: &IngredientSeti,
-    ) -> i32 {
+    fn singleton_bound(&self, node: &BoundContext) -> i32 {
+        let candidates = node.candidates;
         let n_unique_cocktails = candidates
             .iter()
-            .filter(|cocktail| self.min_cover.get(cocktail).unwrap() == &1)
+            .filter(|cocktail| self.min_cover.get(cocktail) == Some(&1))
             .count();
-        let ingredient_budget = self.max_size - partial_ingredients.len();
+        let mut ingredient_budget = self
+            .max_size
+            .saturating_sub(self.bought(node.partial_ingredients));
+        if let Some(budget) = self.budget {
+            // each unique cocktail needs an ingredient of its own, costing at least
+            // its cheapest new ingredient
+            let mut cheapest = candidates
+                .iter()
+                .zip(node.increases())
+                .filter(|(cocktail, _)| self.min_cover.get(cocktail) == Some(&1))
+                .map(|(_, increase)| {
+                    increase
+                        .iter()
+                        .map(|ingredient| self.cost([ingredient]))
+                        .fold(f64::INFINITY, f64::min)
+                })
+                .collect::<Vec<f64>>();
+            cheapest.sort_unstable_by(|a, b| cmp_f64(*a, *b));
+            let mut remaining = budget - self.cost(node.partial_ingredients) + COST_TOLERANCE;
+            let affordable = cheapest
+                .iter()
+                .take_while(|&&price| {
+                    remaining -= price;
+                    remaining >= 0.0
+                })
+                .count();
+            ingredient_budget = ingredient_budget.min(affordable);
+        }
         candidates.len() as i32 - n_unique_cocktails as i32
             + (n_unique_cocktails.min(ingredient_budget) as i32)
     }
@@ -252,32 +2772,257 @@ impl BranchBound {
     /// note that we are just updating the bound. it could actually
     /// be that we want to add one of these cocktails that add
     /// a lot of ingredients
-    fn concentration_bound(
-        &self,
-        candidates: &FxHashSet<IngredientSeti>,
-        _partial: &FxHashSet<IngredientSeti>,
-        partial_ingredients: &IngredientSeti,
-    ) -> i32 {
-        let candidate_ingredients = candidates
+    fn concentration_bound(&self, node: &BoundContext) -> i32 {
+        let candidates = node.candidates;
+        // everything bought if every candidate were made
+        let mut all_ingredients = node.partial_ingredients.clone();
+        all_ingredients.extend(candidates.iter().copied().flatten());
+        let excess_ingredients = self.bought(&all_ingredients) as i32 - self.max_size as i32;
+        let ingredient_increases = node.increases().iter().map(IngredientSeti::len);
+        let mut upper_increment =
+            candidates.len() - fewest_dropped(ingredient_increases, excess_ingredients);
+        // likewise for each category: dropping a cocktail takes at most its new
+        // ingredients out of it
+        for maximum in &self.category_maximums {
+            let excess = maximum.count(&all_ingredients) as i32 - maximum.maximum as i32;
+            let increases = node
+                .increases()
+                .iter()
+                .map(|increase| maximum.count(increase));
+            upper_increment =
+                upper_increment.min(candidates.len() - fewest_dropped(increases, excess));
+        }
+        if let Some(budget) = self.budget {
+            // likewise for spending: dropping a cocktail saves at most the price of
+            // its new ingredients
+            let mut excess_cost = self.cost(&all_ingredients) - budget - COST_TOLERANCE;
+            let mut cost_increases = node
+                .increases()
+                .iter()
+                .map(|increase| self.cost(increase))
+                .collect::<Vec<f64>>();
+            cost_increases.sort_unstable_by(|a, b| cmp_f64(*b, *a));
+            let mut affordable = candidates.len();
+            for cost_increase in cost_increases {
+                if excess_cost <= 0.0 {
+                    break;
+                }
+                affordable -= 1;
+                excess_cost -= cost_increase;
+            }
+            upper_increment = upper_increment.min(affordable);
+        }
+        upper_increment as i32
+    }
+
+    /// The singleton bound, counting each candidate's private ingredients: those it
+    /// still needs that no other candidate uses. However the budget's remaining slots
+    /// are spent, each candidate made takes as many of them as it has private
+    /// ingredients, all to itself. Slots are interchangeable, so the most candidates
+    /// that can be matched with slots in full are those needing the fewest. Candidates
+    /// with no private ingredients could all be made.
+    ///
+    /// Privacy is judged among the node's candidates, not all the cocktails, so it's
+    /// stronger than the singleton bound deeper down, and wherever a cocktail has two or
+    /// more ingredients of its own
+    fn matching_bound(&self, node: &BoundContext) -> i32 {
+        let mut users = FxHashMap::<Ingredienti, u32>::default();
+        for increase in node.increases() {
+            for &ingredient in increase.difference(&self.owned) {
+                *users.entry(ingredient).or_insert(0) += 1;
+            }
+        }
+        let mut shared = 0;
+        let mut private = vec![];
+        for increase in node.increases() {
+            match increase
+                .difference(&self.owned)
+                .filter(|ingredient| users[ingredient] == 1)
+                .count()
+            {
+                0 => shared += 1,
+                slots => private.push(slots),
+            }
+        }
+        private.sort_unstable();
+        let mut budget = self
+            .max_size
+            .saturating_sub(self.bought(node.partial_ingredients));
+        let matched = private
+            .into_iter()
+            .take_while(|&slots| {
+                let fits = slots <= budget;
+                budget = budget.saturating_sub(slots);
+                fits
+            })
+            .count();
+        (shared + matched) as i32
+    }
+
+    /// Two candidates conflict if the budget's remaining slots can't buy both of their
+    /// new ingredients, so at most one of any set of mutually conflicting candidates
+    /// (a clique) can be made. Covering the candidates with cliques, greedily, largest
+    /// increases first, there's at most one cocktail to a clique.
+    ///
+    /// Takes time quadratic in the candidates, but near the leaves, where little of the
+    /// budget is left, most pairs conflict
+    fn conflict_bound(&self, node: &BoundContext) -> i32 {
+        let slots = self
+            .max_size
+            .saturating_sub(self.bought(node.partial_ingredients));
+        let mut increases = node
+            .increases()
             .iter()
-            .flatten()
-            .copied()
-            .collect::<IngredientSeti>();
-        let mut excess_ingredients =
-            (&candidate_ingredients | partial_ingredients).len() as i32 - self.max_size as i32;
-        let mut ingredient_increases = candidates
+            .map(|increase| increase.difference(&self.owned).copied().collect())
+            .collect::<Vec<Vec<Ingredienti>>>();
+        increases.sort_unstable_by_key(|increase| std::cmp::Reverse(increase.len()));
+        // if even the two largest fit together, nothing conflicts
+        if increases.len() < 2 || increases[0].len() + increases[1].len() <= slots {
+            return increases.len() as i32;
+        }
+        let conflict = |a: &[Ingredienti], b: &[Ingredienti]| {
+            a.len() + b.len() > slots && a.len() + b.len() - shared(a, b) > slots
+        };
+        // those fitting alongside even the largest conflict with nothing, and come last
+        let largest = increases[0].len();
+        let lone = increases
             .iter()
-            .map(|cocktail| (cocktail - partial_ingredients).len() as i32)
-            .collect::<Vec<i32>>();
-        ingredient_increases.sort_unstable_by(|a, b| b.cmp(a));
-        let mut upper_increment = candidates.len();
-        for ingredient_increase in ingredient_increases {
-            if excess_ingredients <= 0 {
-                break;
+            .rev()
+            .take_while(|increase| largest + increase.len() <= slots)
+            .count();
+        let mut cliques: Vec<Vec<&[Ingredienti]>> = vec![];
+        for increase in &increases[..increases.len() - lone] {
+            match cliques
+                .iter_mut()
+                .find(|clique| clique.iter().all(|member| conflict(member, increase)))
+            {
+                Some(clique) => clique.push(increase),
+                None => cliques.push(vec![increase]),
             }
-            upper_increment -= 1;
-            excess_ingredients -= ingredient_increase;
         }
-        upper_increment as i32
+        (cliques.len() + lone) as i32
+    }
+
+    /// Share each candidate out evenly between the ingredients it still needs to buy.
+    /// However a cocktail gets made, all of its shares are bought, so a set of
+    /// ingredients can't make more cocktails than its shares add up to, and the most
+    /// the budget allows is the sum of the largest shares. Candidates needing nothing
+    /// more are free.
+    ///
+    /// This is the Lagrangian relaxation of the ingredient budget, with fixed
+    /// multipliers, so it's weakest when a few ingredients are shared by many cocktails
+    fn fractional_bound(&self, node: &BoundContext) -> i32 {
+        let mut free = 0;
+        let mut shares = FxHashMap::<Ingredienti, f64>::default();
+        for increase in node.increases() {
+            let needed = self.bought(increase);
+            if needed == 0 {
+                free += 1;
+                continue;
+            }
+            let share = 1.0 / needed as f64;
+            for &ingredient in increase.difference(&self.owned) {
+                *shares.entry(ingredient).or_insert(0.0) += share;
+            }
+        }
+        let mut shares = shares.into_values().collect::<Vec<f64>>();
+        shares.sort_unstable_by(|a, b| cmp_f64(*b, *a));
+        let budget = self
+            .max_size
+            .saturating_sub(self.bought(node.partial_ingredients));
+        let most = shares.iter().take(budget).sum::<f64>();
+        // shares are fractions, so allow for rounding before flooring
+        free + (most + 1e-9).floor() as i32
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::problem::Problem;
+
+    fn cocktails(sets: &[&[i32]]) -> FxHashSet<IngredientSeti> {
+        sets.iter()
+            .map(|set| set.iter().copied().collect())
+            .collect()
+    }
+
+    fn search(bb: &mut BranchBound, candidates: &FxHashSet<IngredientSeti>) {
+        bb.search(
+            &mut candidates.clone(),
+            &mut FxHashSet::default(),
+            &mut None,
+        );
+        assert!(bb.status.completed());
+    }
+
+    #[test]
+    fn variants_count_once() {
+        // a Daiquiri and a Hemingway Daiquiri, and a Gimlet
+        let candidates = cocktails(&[&[0, 1, 2], &[0, 1, 2, 3], &[1, 2, 4]]);
+        let daiquiris = cocktails(&[&[0, 1, 2], &[0, 1, 2, 3]]);
+        let mut bb = BranchBoundBuilder::new(10_000, 4)
+            .variant_group(daiquiris.clone())
+            .build();
+        assert_eq!(bb.score(&daiquiris), 1);
+        assert_eq!(bb.distinct(&candidates), 2);
+        // both daiquiris would tie with a daiquiri and a gimlet, were they counted twice
+        search(&mut bb, &candidates);
+        assert_eq!(bb.highest_score, 2);
+        assert_eq!(bb.highest_ingredients, BTreeSet::from([0, 1, 2, 4]));
+    }
+
+    #[test]
+    fn house_cocktails_come_first() {
+        // two single-ingredient cocktails outnumber the house cocktail, but lose to it
+        let candidates = cocktails(&[&[0], &[1], &[5, 6], &[7, 8]]);
+        let house = cocktails(&[&[5, 6]]);
+        let mut bb = BranchBoundBuilder::new(10_000, 2)
+            .house_cocktails(house.clone())
+            .build();
+        search(&mut bb, &candidates);
+        assert_eq!(bb.highest_ingredients, BTreeSet::from([5, 6]));
+        // with room to spare, the other cocktails break the tie
+        let mut bb = BranchBoundBuilder::new(10_000, 3)
+            .house_cocktails(house)
+            .build();
+        search(&mut bb, &candidates);
+        assert_eq!(bb.highest.len(), 2);
+        assert!(bb.highest_ingredients.is_superset(&BTreeSet::from([5, 6])));
+    }
+
+    #[test]
+    fn stops_at_the_time_limit() {
+        let problem = Problem::random(&mut StdRng::seed_from_u64(1755), 20, 40, 2..=4, 6);
+        let mut bb = problem
+            .builder(1_000_000)
+            .with_time_limit(Duration::ZERO)
+            .build();
+        problem.search(&mut bb).unwrap();
+        assert_eq!(bb.status, SearchStatus::TimedOut);
+        assert_eq!(bb.counter, 0);
+    }
+
+    /// Stops the search once it has expanded this many more nodes
+    struct StopAfter(i32);
+
+    impl SearchObserver for StopAfter {
+        fn on_node_expanded(&mut self, _depth: usize, _calls_remaining: i32) -> ControlFlow<()> {
+            self.0 -= 1;
+            if self.0 > 0 {
+                ControlFlow::Continue(())
+            } else {
+                ControlFlow::Break(())
+            }
+        }
+    }
+
+    #[test]
+    fn an_observer_stops_the_search() {
+        let problem = Problem::random(&mut StdRng::seed_from_u64(1756), 20, 40, 2..=4, 6);
+        let mut bb = problem.builder(1_000_000).observer(StopAfter(5)).build();
+        problem.search(&mut bb).unwrap();
+        assert_eq!(bb.status, SearchStatus::Aborted);
+        assert_eq!(bb.counter, 5);
     }
 }
@@ -1,87 +1,1414 @@
-use branchbound::{BranchBound, Ingredient, IngredientSet, IngredientSeti, Ingredienti};
+use branchbound::anneal::Annealing;
+use branchbound::branching::{
+    MaxCoverage, MostCommonIngredient, MostConstrainedIngredient, Random,
+};
+use branchbound::brute::BruteForce;
+use branchbound::cocktail::Cocktail;
+use branchbound::constraints::{CategoryMaximum, CategoryMinimum};
+use branchbound::frontier::SearchOrder;
+use branchbound::menu::Menu;
+use branchbound::metadata::IngredientInfo;
+use branchbound::observer::{Prune, SearchObserver};
+use branchbound::portfolio::Portfolio;
+use branchbound::problem::Problem;
+use branchbound::quantity::QuantityParser;
+use branchbound::solution::Solution;
+use branchbound::solver::{Greedy, Solver};
+use branchbound::{analysis, bench, metadata, weights};
+use branchbound::{
+    BranchBound, BranchBoundBuilder, Error, Ingredient, IngredientSeti, Ingredienti, SearchStatus,
+};
+use clap::{Args, Parser, Subcommand, ValueEnum};
 use csv::ReaderBuilder;
 use rustc_hash::{FxHashMap, FxHashSet};
-use std::collections::BTreeSet;
 use std::fs::File;
-use std::io::BufReader;
+use std::io::{BufReader, BufWriter};
+use std::ops::RangeInclusive;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
 
-fn main() {
-    let mut map: FxHashMap<IngredientSet, String> = FxHashMap::default();
+/// Find the set of ingredients that lets you make the most cocktails
+#[derive(Parser)]
+struct Cli {
+    /// CSV file with one cocktail per row: its name, followed by its ingredients.
+    /// May be repeated to merge several files
+    #[arg(long, global = true, default_value = "cocktails.csv")]
+    input: Vec<String>,
+    /// Strip leading quantities and units (e.g. "2 oz Gin") from ingredient cells
+    #[arg(long, global = true)]
+    strip_quantities: bool,
+    /// Comma-separated units to recognise when stripping quantities, replacing the defaults
+    #[arg(
+        long,
+        global = true,
+        value_delimiter = ',',
+        requires = "strip_quantities"
+    )]
+    units: Option<Vec<String>>,
+    #[command(subcommand)]
+    command: Option<Command>,
+    #[command(flatten)]
+    solve: SolveArgs,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Search for the best ingredient set (the default)
+    Solve(Box<SolveArgs>),
+    /// Report solver-independent statistics about the dataset
+    Analyze(AnalyzeArgs),
+    /// Compare how long the exact search takes with different bounds, branching
+    /// strategies and seeds
+    Bench(BenchArgs),
+}
+
+/// The branching strategies selectable with --branching
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+enum Branching {
+    /// The cocktail whose ingredients are shared most widely
+    MinAmortizedCost,
+    /// The cocktail whose ingredients would make the most candidates
+    MaxCoverage,
+    /// The cocktail using the rarest ingredient
+    MostConstrained,
+    /// The ingredient the most candidates need, rather than a cocktail
+    MostCommonIngredient,
+    /// A cocktail chosen at random, seeded by --rng-seed
+    Random,
+}
+
+impl Branching {
+    /// Branch this way, seeding any randomness with `seed`
+    fn apply(self, builder: BranchBoundBuilder, seed: u64) -> BranchBoundBuilder {
+        match self {
+            Branching::MinAmortizedCost => builder,
+            Branching::MaxCoverage => builder.branching(MaxCoverage),
+            Branching::MostConstrained => builder.branching(MostConstrainedIngredient),
+            Branching::MostCommonIngredient => builder.branching(MostCommonIngredient),
+            Branching::Random => builder.branching(Random::new(seed)),
+        }
+    }
+}
+
+/// The solvers selectable with --solver
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+enum Algorithm {
+    /// Branch and bound
+    Exact,
+    /// Repeatedly add the ingredient completing the most cocktails
+    Greedy,
+    /// Simulated annealing, starting from the greedy solution
+    Anneal,
+    /// Race exact searches breaking ties differently against the other solvers, sharing
+    /// the best solution found between them
+    Portfolio,
+    /// Try every combination of ingredients: optimal, but only for up to 20 candidate
+    /// ingredients
+    BruteForce,
+    /// Solve as an integer program: optimal, proven independently of the search
+    #[cfg(feature = "ilp")]
+    Ilp,
+}
+
+#[derive(Args)]
+struct SolveArgs {
+    /// Number of ingredients to buy: 12 by default, or as many as the budget allows
+    /// with --budget
+    #[arg(short = 'n', long)]
+    ingredients: Option<usize>,
+    /// Maximum number of search rounds
+    #[arg(long, default_value_t = 8_000_000)]
+    max_calls: i32,
+    /// Stop branching below this depth, trading optimality for a bounded search
+    #[arg(long)]
+    max_depth: Option<usize>,
+    /// Search approximately, only exploring branches that could beat the best set
+    /// found so far by more than this fraction (e.g. 0.1 for 10 %)
+    #[arg(long)]
+    approximation: Option<f64>,
+    /// Write a cocktails × ingredients coverage matrix of the result to this file
+    /// (JSON if it ends in .json, CSV otherwise)
+    #[arg(long)]
+    heatmap: Option<PathBuf>,
+    /// Ingredient metadata (CSV rows of name, ABV %, category), used to report on the
+    /// balance of the resulting menu
+    #[arg(long)]
+    metadata: Option<PathBuf>,
+    /// Require at least this many ingredients from a metadata category, e.g. `gin=1`.
+    /// May be repeated
+    #[arg(long, value_parser = parse_category_count, requires = "metadata")]
+    min_category: Vec<(String, usize)>,
+    /// Allow at most this many ingredients from a metadata category, e.g. `spirit=4`.
+    /// May be repeated
+    #[arg(long, value_parser = parse_category_count, requires = "metadata")]
+    max_category: Vec<(String, usize)>,
+    /// Prefer at most this many ingredients from a metadata category, e.g. `vermouth=2`,
+    /// allowing more only if they make enough extra cocktails. May be repeated
+    #[arg(long, value_parser = parse_category_count, requires = "metadata")]
+    prefer_max_category: Vec<(String, usize)>,
+    /// How many cocktails each ingredient over a --prefer-max-category limit costs
+    #[arg(long, default_value_t = 1, requires = "prefer_max_category")]
+    over_penalty: usize,
+    /// Save the result as JSON, for use with --seed-solution in later runs
+    #[arg(long)]
+    save_solution: Option<PathBuf>,
+    /// Start from a solution saved by an earlier run. Any cocktails its ingredients
+    /// still make are the initial best set, so worse branches are pruned straight away
+    #[arg(long)]
+    seed_solution: Option<PathBuf>,
+    /// Also require every ingredient of the seed solution in the result
+    #[arg(long, requires = "seed_solution")]
+    require_seed: bool,
+    /// Comma-separated ingredients to start from, e.g. the bottles already on the shelf.
+    /// The result only differs from them if a better set exists
+    #[arg(long, value_delimiter = ',', conflicts_with = "seed_solution")]
+    initial: Vec<String>,
+    /// CSV file with one row per drink listing the names of its variants, which then
+    /// only count once towards the score
+    #[arg(long)]
+    variants: Option<PathBuf>,
+    /// Comma-separated house cocktails: as many of these as possible are made first,
+    /// and only then as many cocktails overall
+    #[arg(long, value_delimiter = ',')]
+    house: Vec<String>,
+    /// Comma-separated ingredients already owned: they're in every result, for free,
+    /// and don't count towards -n
+    #[arg(long, value_delimiter = ',')]
+    owned: Vec<String>,
+    /// Comma-separated ingredients never to buy: cocktails needing any of them are dropped
+    #[arg(long, value_delimiter = ',')]
+    exclude: Vec<String>,
+    /// Comma-separated cocktails every result must make
+    #[arg(long, value_delimiter = ',')]
+    must_make: Vec<String>,
+    /// Comma-separated ingredients never to buy all of, e.g. "Mezcal,Scotch". May be
+    /// repeated
+    #[arg(long)]
+    never_together: Vec<String>,
+    /// Solve for every number of ingredients in a range such as 5-20, reporting how
+    /// many cocktails each makes, instead of for -n alone
+    #[arg(long, value_parser = parse_sizes, conflicts_with = "ingredients")]
+    sweep: Option<RangeInclusive<usize>>,
+    /// Find the fewest ingredients making at least this many cocktails (or this much
+    /// total weight, with --weights), instead of the most cocktails -n makes
+    #[arg(long, conflicts_with_all = ["ingredients", "sweep", "house", "resume"])]
+    target: Option<usize>,
+    /// Report the sets of each number of ingredients in a range such as 1-15 that no
+    /// other beats on ingredients, cocktails and cost (at the --prices given) at once
+    #[arg(
+        long,
+        value_parser = parse_sizes,
+        conflicts_with_all = ["ingredients", "sweep", "target", "resume"]
+    )]
+    pareto: Option<RangeInclusive<usize>>,
+    /// Count how many distinct ingredient sets make as many cocktails as the best,
+    /// rather than reporting one
+    #[arg(long, conflicts_with_all = ["sweep", "pareto", "target", "resume"])]
+    count_optima: bool,
+    /// Write the problem as an integer program to this file instead of solving it, in
+    /// MPS format if it ends in .mps and CPLEX LP format otherwise
+    #[arg(long, conflicts_with_all = ["sweep", "pareto", "target", "count_optima"])]
+    export: Option<PathBuf>,
+    /// Write the time spent at each depth and phase of the search to this file, as
+    /// folded stacks for inferno or flamegraph.pl
+    #[arg(long)]
+    profile: Option<PathBuf>,
+    /// Write a drinks menu of the result, with any recipes from the input, to this file
+    /// (HTML if it ends in .html, Markdown otherwise)
+    #[arg(long)]
+    menu: Option<PathBuf>,
+    /// Search both branches of each node in parallel on this many threads (0 for one
+    /// per CPU). Ties between equally good solutions may then break differently.
+    /// With --solver portfolio, the number of exact searches to race instead
+    #[arg(long)]
+    threads: Option<usize>,
+    /// Break ties between equally good cocktails to branch on at random, with this
+    /// seed, to explore the search differently but reproducibly
+    #[arg(long)]
+    rng_seed: Option<u64>,
+    /// Stop after this many seconds, keeping the best result found so far
+    #[arg(long, value_parser = parse_seconds)]
+    time_limit: Option<Duration>,
+    /// Save the search to this file every --checkpoint-every rounds, so that it can be
+    /// carried on with --resume if interrupted. Depth-first searches only
+    #[arg(long, conflicts_with_all = ["best_first", "beam", "limited_discrepancy"])]
+    checkpoint: Option<PathBuf>,
+    /// Search rounds between checkpoints
+    #[arg(long, default_value_t = 1_000_000, requires = "checkpoint")]
+    checkpoint_every: u32,
+    /// Carry on the search saved in this checkpoint file rather than starting afresh.
+    /// Give the same dataset and options as the interrupted search
+    #[arg(long, conflicts_with = "sweep")]
+    resume: Option<PathBuf>,
+    /// The algorithm to use. Only the exact search is guaranteed to find the optimum
+    #[arg(long, value_enum, default_value_t = Algorithm::Exact)]
+    solver: Algorithm,
+    /// How the exact search chooses the cocktail to branch on. The choice doesn't
+    /// change the answer, only how long it takes to find
+    #[arg(long, value_enum, default_value_t = Branching::MinAmortizedCost)]
+    branching: Branching,
+    /// Always expand the most promising node next, rather than searching depth-first.
+    /// Still exact, but may need much more memory
+    #[arg(long)]
+    best_first: bool,
+    /// Consult the bounds that have pruned most for the time they take first, rather
+    /// than always in the same order. Doesn't change the answer
+    #[arg(long)]
+    adaptive_bounds: bool,
+    /// Also consult the bounds that solve a problem of their own at each node. They
+    /// prune more, but may take longer than they save. Doesn't change the answer
+    #[arg(long)]
+    expensive_bounds: bool,
+    /// Consult only these bounds, comma-separated, e.g. total,singleton,fractional.
+    /// Doesn't change the answer, only how long it takes to find
+    #[arg(long, value_delimiter = ',', value_parser = parse_bound, conflicts_with = "expensive_bounds")]
+    bounds: Option<Vec<Prune>>,
+    /// Before searching, rule out the ingredients the bounds show can't be in a better
+    /// set than the first found, and require those every better set needs. Doesn't
+    /// change the answer
+    #[arg(long)]
+    probe: bool,
+    /// Before searching, make this many short passes breaking ties at random, and
+    /// start from the best set any of them finds. Doesn't change the answer
+    #[arg(long)]
+    restarts: Option<usize>,
+    /// Search rounds each of the --restarts passes may take
+    #[arg(long, default_value_t = 10_000)]
+    restart_calls: i32,
+    /// Search a level at a time, keeping only this many of the most promising nodes
+    /// of each: much faster on large datasets, but approximate
+    #[arg(long, conflicts_with = "best_first")]
+    beam: Option<usize>,
+    /// Search depth-first, first only along the paths always including the cocktail
+    /// chosen to branch on, then those excluding it at most once, twice, and so on.
+    /// Still exact, and tends to find good sets sooner
+    #[arg(long, conflicts_with_all = ["best_first", "beam"])]
+    limited_discrepancy: bool,
+    /// Steps to take with --solver anneal
+    #[arg(long, default_value_t = 100_000)]
+    iterations: u64,
+    /// Report each better ingredient set on stderr as the search finds it
+    #[arg(long)]
+    progress: bool,
+    /// Also report the next best distinct ingredient sets, up to this many in all
+    #[arg(long, default_value_t = 1)]
+    top_k: usize,
+    /// Also report other ingredient sets making as many cocktails as the best, up to
+    /// this many
+    #[arg(long)]
+    ties: Option<usize>,
+    /// Report how the search went: nodes expanded, prunes by bound, and when each
+    /// better set was found
+    #[arg(long)]
+    stats: bool,
+    /// CSV file of cocktail names and weights: maximise the total weight of the
+    /// cocktails made rather than their number. Unlisted cocktails weigh 1
+    #[arg(long)]
+    weights: Option<PathBuf>,
+    /// CSV file of ingredient names and prices, for use with --budget. Unlisted
+    /// ingredients are free
+    #[arg(long)]
+    prices: Option<PathBuf>,
+    /// Spend at most this much on ingredients, at the prices given by --prices
+    #[arg(long, requires = "prices")]
+    budget: Option<f64>,
+}
+
+/// Prints each new incumbent as the search finds it
+struct Progress {
+    started: Instant,
+}
+
+impl SearchObserver for Progress {
+    fn on_new_incumbent(&mut self, score: usize, ingredients: &IngredientSeti) {
+        eprintln!(
+            "{:>8.2}s: score {score} with {} ingredients",
+            self.started.elapsed().as_secs_f64(),
+            ingredients.len()
+        );
+    }
+}
+
+fn parse_sizes(value: &str) -> Result<RangeInclusive<usize>, String> {
+    let (from, to) = value
+        .split_once('-')
+        .ok_or_else(|| format!("expected a range such as 5-20, not {value:?}"))?;
+    let from = from.trim().parse::<usize>().map_err(|e| e.to_string())?;
+    let to = to.trim().parse::<usize>().map_err(|e| e.to_string())?;
+    if from == 0 || from > to {
+        return Err(format!(
+            "{value:?} isn't a range of at least one ingredient"
+        ));
+    }
+    Ok(from..=to)
+}
+
+fn parse_seconds(value: &str) -> Result<Duration, String> {
+    let seconds = value
+        .parse::<f64>()
+        .map_err(|e| format!("{value:?} isn't a number of seconds: {e}"))?;
+    Duration::try_from_secs_f64(seconds).map_err(|e| format!("invalid time limit {value:?}: {e}"))
+}
+
+fn parse_bound(value: &str) -> Result<Prune, String> {
+    Prune::from_name(value.trim())
+        .filter(|reason| reason.cost().is_some())
+        .ok_or_else(|| {
+            let names = Prune::ALL
+                .into_iter()
+                .filter(|reason| reason.cost().is_some())
+                .map(Prune::name)
+                .collect::<Vec<&str>>();
+            format!(
+                "no bound called {value:?}; expected one of {}",
+                names.join(", ")
+            )
+        })
+}
+
+fn parse_category_count(value: &str) -> Result<(String, usize), String> {
+    let (category, count) = value
+        .split_once('=')
+        .ok_or_else(|| format!("expected CATEGORY=COUNT, got {value:?}"))?;
+    let count = count
+        .trim()
+        .parse()
+        .map_err(|e| format!("invalid count in {value:?}: {e}"))?;
+    Ok((category.trim().to_lowercase(), count))
+}
+
+#[derive(Args)]
+struct AnalyzeArgs {
+    /// Number of entries to show in each ranking
+    #[arg(long, default_value_t = 15)]
+    top: usize,
+    /// Ignore pairs that co-occur fewer times than this when ranking by PMI
+    #[arg(long, default_value_t = 2)]
+    min_count: u32,
+    /// Also list the cocktails most similar to this one
+    #[arg(long)]
+    similar_to: Option<String>,
+    /// Compare cocktails by overlap rather than Jaccard similarity
+    #[arg(long, requires = "similar_to")]
+    overlap: bool,
+    /// Derive cocktail weights from a log of drinks made (CSV rows of name, YYYY-MM-DD)
+    #[arg(long)]
+    log: Option<PathBuf>,
+    /// Number of days after which a logged drink counts half as much
+    #[arg(long, default_value_t = 90.0, requires = "log")]
+    half_life: f64,
+}
+
+#[derive(Args)]
+struct BenchArgs {
+    /// Number of ingredients to buy
+    #[arg(short = 'n', long, default_value_t = 12)]
+    ingredients: usize,
+    /// Maximum number of search rounds for each search
+    #[arg(long, default_value_t = 8_000_000)]
+    max_calls: i32,
+    /// A comma-separated set of bounds to consult, e.g. total,fractional. May be
+    /// repeated to compare several; the default set otherwise
+    #[arg(long, value_parser = parse_bounds)]
+    bounds: Vec<Vec<Prune>>,
+    /// A branching strategy to compare. May be repeated; min-amortized-cost otherwise
+    #[arg(long, value_enum)]
+    branching: Vec<Branching>,
+    /// Also break ties between equally good cocktails at random, with each of this many
+    /// seeds
+    #[arg(long, default_value_t = 0)]
+    seeds: u64,
+    /// Run each configuration this many times, keeping the fastest
+    #[arg(long, default_value_t = 3)]
+    runs: usize,
+}
+
+fn parse_bounds(value: &str) -> Result<Vec<Prune>, String> {
+    value.split(',').map(parse_bound).collect()
+}
+
+fn load(paths: &[String], parser: Option<&QuantityParser>) -> Problem {
+    let mut problem = Problem::default();
+    for path in paths {
+        or_exit(problem.read_path(path, parser));
+    }
+    problem
+}
+
+fn open(path: &Path) -> Result<BufReader<File>, Error> {
+    File::open(path)
+        .map(BufReader::new)
+        .map_err(|source| Error::Io {
+            path: path.to_owned(),
+            source,
+        })
+}
+
+fn create(path: &Path) -> Result<BufWriter<File>, Error> {
+    File::create(path)
+        .map(BufWriter::new)
+        .map_err(|source| Error::Io {
+            path: path.to_owned(),
+            source,
+        })
+}
 
-    let f = File::open("cocktails.csv").unwrap();
-    let reader = BufReader::new(f);
-    let csvr = ReaderBuilder::new()
+/// Read rows of a name and a non-negative number, such as a weight or a price
+fn read_amounts(path: &Path) -> Result<Vec<(String, f64)>, Error> {
+    let mut amounts = vec![];
+    for (i, record) in ReaderBuilder::new()
+        .has_headers(false)
+        .trim(csv::Trim::All)
+        .from_path(path)?
+        .into_records()
+        .enumerate()
+    {
+        let record = record?;
+        let amount = record.get(1).and_then(|amount| amount.parse::<f64>().ok());
+        match (record.get(0), amount) {
+            (Some(name), Some(amount)) if amount >= 0.0 => amounts.push((name.to_owned(), amount)),
+            _ => {
+                return Err(Error::Malformed {
+                    row: i + 1,
+                    reason: "expected a name and a non-negative number".to_owned(),
+                })
+            }
+        }
+    }
+    Ok(amounts)
+}
+
+/// Read variant groups: one row of cocktail names per drink
+fn read_variants(path: &Path) -> Result<Vec<Vec<String>>, csv::Error> {
+    ReaderBuilder::new()
         .flexible(true)
         .has_headers(false)
-        .from_reader(reader);
-    let records = csvr.into_records();
-    // populate map
-    records.for_each(|record| {
-        let r = record.unwrap();
-        let key = r
-            .iter()
-            .skip(1)
-            .map(std::borrow::ToOwned::to_owned)
-            .collect::<IngredientSet>();
-        let value = r.iter().next().unwrap().to_owned();
-        // println!("key {:?}", &key);
-        // println!("value {:?}", &value);
-        map.insert(key, value);
-    });
-    let mut res: FxHashSet<IngredientSeti> = FxHashSet::default();
-    // build mapping from cocktail <--> set<i32> and ingredient <--> i32
-    let mut ingredient_lookup = FxHashMap::default();
-    let mut cocktail_lookup = FxHashMap::default();
-    let mut ingredient_lookup_reverse = FxHashMap::default();
-    let mut cocktail_lookup_reverse = FxHashMap::default();
-
-    let mut numeric_set = FxHashSet::default();
-    // populate lookups
-    let mut counter = 0;
-    map.iter().enumerate().for_each(|(i, (ingset, name))| {
-        cocktail_lookup.entry(name).or_insert(i);
-        for ingredient in ingset {
-            if ingredient_lookup.get(ingredient).is_none() {
-                ingredient_lookup.insert(ingredient, counter);
-                ingredient_lookup_reverse.insert(counter, ingredient);
-                counter += 1;
+        .from_path(path)?
+        .into_records()
+        .map(|record| Ok(record?.iter().map(|name| name.trim().to_owned()).collect()))
+        .collect()
+}
+
+/// Unwrap a result, or report the error and exit
+fn or_exit<T, E: std::fmt::Display>(result: Result<T, E>) -> T {
+    result.unwrap_or_else(|e| {
+        eprintln!("{e}");
+        std::process::exit(1);
+    })
+}
+
+fn solve(mut problem: Problem, args: &SolveArgs) {
+    // a checkpoint holds an exact search, which only the exact solver can carry on
+    if args.resume.is_some() && args.solver != Algorithm::Exact {
+        let solver = args.solver.to_possible_value().unwrap();
+        eprintln!("--resume can't be used with --solver {}", solver.get_name());
+        std::process::exit(1);
+    }
+    problem.max_size = match (args.ingredients, args.budget) {
+        (Some(ingredients), _) => ingredients,
+        (None, Some(_)) => problem.mapper().len(),
+        (None, None) => 12,
+    };
+    if let Some(sizes) = args.sweep.as_ref().or(args.pareto.as_ref()) {
+        problem.max_size = *sizes.end();
+    }
+    // the number of ingredients is what's being found
+    if args.target.is_some() {
+        problem.max_size = problem.mapper().len().max(1);
+    }
+    if let Some(path) = &args.prices {
+        let mut unknown = vec![];
+        for (name, price) in or_exit(read_amounts(path)) {
+            if problem.set_price(&name, price).is_err() {
+                unknown.push(name);
             }
         }
-        let ingredientset = ingset
+        if !unknown.is_empty() {
+            eprintln!("Ignoring prices of unknown ingredients: {unknown:?}");
+        }
+    }
+    if let Some(budget) = args.budget {
+        problem.spend_budget(budget);
+    }
+    let cocktails = problem.cocktail_sets();
+    if let Some(path) = &args.variants {
+        let groups = or_exit(read_variants(path));
+        let mut unknown = vec![];
+        for group in groups {
+            let (known, missing): (Vec<String>, Vec<String>) = group
+                .into_iter()
+                .partition(|name| problem.find(name).is_some());
+            unknown.extend(missing);
+            or_exit(problem.variant_group(known.iter().map(String::as_str)));
+        }
+        if !unknown.is_empty() {
+            eprintln!("Ignoring unknown variant cocktails: {unknown:?}");
+        }
+    }
+    if let Some(path) = &args.weights {
+        let mut unknown = vec![];
+        for (name, weight) in or_exit(read_amounts(path)) {
+            if problem.set_weight(&name, weight).is_err() {
+                unknown.push(name);
+            }
+        }
+        if !unknown.is_empty() {
+            eprintln!("Ignoring weights of unknown cocktails: {unknown:?}");
+        }
+    }
+    if !args.owned.is_empty() {
+        let (owned, unknown): (Vec<&str>, Vec<&str>) = args
+            .owned
             .iter()
-            .map(|ingredient| *ingredient_lookup.get(ingredient).unwrap())
-            .collect::<BTreeSet<i32>>();
-        // populate mapping for optimisation
-        numeric_set.insert(ingredientset.clone());
-        cocktail_lookup_reverse.insert(ingredientset, name);
+            .map(String::as_str)
+            .partition(|name| problem.mapper().id(name).is_some());
+        if !unknown.is_empty() {
+            eprintln!("Ignoring owned ingredients no cocktail uses: {unknown:?}");
+        }
+        problem.with_owned_ingredients(&owned);
+    }
+    if let Some(name) = args.owned.iter().find(|name| args.exclude.contains(name)) {
+        eprintln!("{name:?} can't be both owned and excluded");
+        std::process::exit(1);
+    }
+    let unknown = args
+        .exclude
+        .iter()
+        .filter(|name| problem.exclude_ingredient(name).is_err())
+        .collect::<Vec<&String>>();
+    if !unknown.is_empty() {
+        eprintln!("Ignoring excluded ingredients no cocktail uses: {unknown:?}");
+    }
+    for combination in &args.never_together {
+        let names = combination.split(',').map(str::trim).collect::<Vec<&str>>();
+        if let Err(name) = problem.forbid_combination(&names) {
+            eprintln!("Ignoring {combination:?}: no cocktail uses {name:?}");
+        }
+    }
+    if !args.must_make.is_empty() {
+        let (known, unknown): (Vec<&str>, Vec<&str>) = args
+            .must_make
+            .iter()
+            .map(String::as_str)
+            .partition(|name| problem.cocktails().iter().any(|c| c.name == *name));
+        if !unknown.is_empty() {
+            eprintln!("Ignoring unknown cocktails to make: {unknown:?}");
+        }
+        or_exit(problem.must_make(known));
+    }
+    if !args.house.is_empty() {
+        let (house, unknown): (Vec<&String>, Vec<&String>) = args
+            .house
+            .iter()
+            .partition(|name| problem.find(name).is_some());
+        if !unknown.is_empty() {
+            eprintln!("Ignoring unknown house cocktails: {unknown:?}");
+        }
+        or_exit(problem.house_cocktails(house.iter().map(|name| name.as_str())));
+    }
+    // metadata is keyed by name, but the solver and analyses work on ingredient ids
+    let info = args.metadata.as_ref().map(|path| {
+        let info =
+            or_exit(open(path).and_then(|f| metadata::read_metadata(f).map_err(Error::from)));
+        problem
+            .mapper()
+            .iter()
+            .filter_map(|(id, name)| Some((id, info.get(name)?.clone())))
+            .collect::<FxHashMap<Ingredienti, IngredientInfo>>()
     });
-    let mut bb = BranchBound::new(8_000_000, 12);
 
-    let best = bb.search(&mut numeric_set, &mut res, &mut None);
-    // map back from sets of i32 to cocktail names
-    let mut best_names = best
+    for (category, limit) in &args.prefer_max_category {
+        let names = info
+            .iter()
+            .flatten()
+            .filter(|(_, meta)| meta.category.as_ref() == Some(category))
+            .filter_map(|(id, _)| problem.mapper().name(*id).map(ToString::to_string))
+            .collect::<Vec<String>>();
+        problem.soft_limit(
+            category,
+            names.iter().map(String::as_str),
+            *limit,
+            args.over_penalty,
+        );
+    }
+
+    let mut builder = problem.builder(args.max_calls);
+    if let Some(max_depth) = args.max_depth {
+        builder = builder.max_depth(max_depth);
+    }
+    if let Some(epsilon) = args.approximation {
+        builder = builder.approximation(epsilon);
+    }
+    let minimums = args
+        .min_category
         .iter()
-        .map(|cocktail| cocktail_lookup_reverse.get(cocktail).unwrap())
-        .collect::<Vec<&&String>>();
-    best_names.sort_unstable();
+        .map(|(category, minimum)| CategoryMinimum {
+            category: category.clone(),
+            ingredients: info
+                .iter()
+                .flatten()
+                .filter(|(_, meta)| meta.category.as_ref() == Some(category))
+                .map(|(id, _)| *id)
+                .collect(),
+            minimum: *minimum,
+        })
+        .collect::<Vec<CategoryMinimum>>();
+    for minimum in minimums {
+        builder = builder.category_minimum(minimum);
+    }
+    for (category, maximum) in &args.max_category {
+        builder = builder.category_maximum(CategoryMaximum {
+            category: category.clone(),
+            ingredients: info
+                .iter()
+                .flatten()
+                .filter(|(_, meta)| meta.category.as_ref() == Some(category))
+                .map(|(id, _)| *id)
+                .collect(),
+            maximum: *maximum,
+        });
+    }
+    if let Some(path) = &args.seed_solution {
+        let seed: Solution =
+            or_exit(open(path).and_then(|f| serde_json::from_reader(f).map_err(Error::from)));
+        // the dataset may have changed since the seed was saved, so go by name
+        let revalidation = problem.revalidate(&seed);
+        for violation in &revalidation.violations {
+            eprintln!("The seed solution no longer holds: {violation}");
+        }
+        if !revalidation.gained.is_empty() {
+            eprintln!(
+                "The seed ingredients now also make: {:?}",
+                revalidation.gained
+            );
+        }
+        let seed_ingredients = seed
+            .ingredients
+            .iter()
+            .filter_map(|name| problem.mapper().id(name))
+            .collect::<IngredientSeti>();
+        let incumbent = cocktails
+            .iter()
+            .filter(|cocktail| cocktail.is_subset(&seed_ingredients))
+            .cloned()
+            .collect();
+        builder = builder.initial_incumbent(incumbent);
+        if args.require_seed {
+            builder = builder.required_ingredients(seed_ingredients);
+        }
+    }
+    if !args.initial.is_empty() {
+        let names = args
+            .initial
+            .iter()
+            .map(String::as_str)
+            .collect::<Vec<&str>>();
+        builder = problem
+            .with_initial_solution(builder, &names)
+            .unwrap_or_else(|violations| {
+                for violation in violations {
+                    eprintln!("The initial ingredients don't work: {violation}");
+                }
+                std::process::exit(1);
+            });
+        let initial = names
+            .iter()
+            .filter_map(|name| problem.mapper().id(name))
+            .collect::<IngredientSeti>();
+        let made = problem
+            .cocktails()
+            .iter()
+            .filter(|cocktail| cocktail.makeable_from(&initial))
+            .count();
+        println!("The initial ingredients make {made} cocktails");
+    }
+    if args.top_k > 1 {
+        builder = builder.with_top_k(args.top_k);
+    }
+    if let Some(ties) = args.ties {
+        builder = builder.with_ties(ties);
+    }
+    if args.progress {
+        builder = builder.observer(Progress {
+            started: Instant::now(),
+        });
+    }
+    if let Some(limit) = args.time_limit {
+        builder = builder.with_time_limit(limit);
+    }
+    if let Some(seed) = args.rng_seed {
+        builder = builder.with_seed(seed);
+    }
+    if args.best_first {
+        builder = builder.search_order(SearchOrder::BestFirst);
+    }
+    if let Some(width) = args.beam {
+        builder = builder.search_order(SearchOrder::Beam(width));
+    }
+    if args.limited_discrepancy {
+        builder = builder.search_order(SearchOrder::LimitedDiscrepancy);
+    }
+    if args.adaptive_bounds {
+        builder = builder.with_adaptive_bounds();
+    }
+    if args.expensive_bounds {
+        builder = builder.with_expensive_bounds();
+    }
+    if let Some(bounds) = &args.bounds {
+        builder = builder.bounds(bounds.iter().copied());
+    }
+    if args.probe {
+        builder = builder.with_probing();
+    }
+    if let Some(passes) = args.restarts {
+        builder = builder.with_restarts(passes, args.restart_calls);
+    }
+    builder = args.branching.apply(builder, args.rng_seed.unwrap_or(0));
+    // the portfolio's searches each run on a thread of their own, and would all write to
+    // the same checkpoint
+    let seed = args.rng_seed.unwrap_or(0);
+    let mut portfolio = Portfolio::new(
+        builder.clone(),
+        seed..seed + args.threads.unwrap_or(2).max(1) as u64,
+    )
+    .with_annealing(Annealing::new(args.iterations).with_seed(seed));
+    if let Some(threads) = args.threads {
+        builder = builder.parallel(threads);
+    }
+    if let Some(path) = &args.checkpoint {
+        builder = builder.checkpoint(path, args.checkpoint_every);
+    }
+    if let Some(path) = &args.export {
+        let exported = match path.extension().and_then(|extension| extension.to_str()) {
+            Some("mps") => problem.export_mps(path),
+            _ => problem.export_lp(path),
+        };
+        or_exit(exported);
+        return;
+    }
 
-    let fset = best
+    let mut bb = match &args.resume {
+        Some(path) => {
+            let mut bb = or_exit(BranchBound::resume_from(path));
+            // the time limit applies to each run, not the search as a whole
+            bb.time_limit = args.time_limit;
+            bb
+        }
+        None => or_exit(builder.profile(args.profile.is_some()).try_build()),
+    };
+
+    if let Some(sizes) = &args.sweep {
+        let results = bb.sweep(&problem.cocktail_sets(), sizes.clone(), true);
+        println!("Search rounds {:?}", bb.counter);
+        println!("Ingredients  Cocktails  Ingredient set");
+        for (size, solution) in problem.name_sweep(results) {
+            println!(
+                "{size:>11}  {:>9}  {:?}",
+                solution.cocktails.len(),
+                solution.ingredients
+            );
+        }
+        return;
+    }
+
+    if let Some(sizes) = &args.pareto {
+        let points = bb.pareto(&problem.cocktail_sets(), sizes.clone());
+        println!("Search rounds {:?}", bb.counter);
+        println!("Ingredients  Cocktails      Cost  Ingredient set");
+        for (point, solution) in problem.name_pareto(points) {
+            println!(
+                "{:>11}  {:>9}  {:>8.2}  {:?}",
+                point.ingredients,
+                solution.cocktails.len(),
+                point.cost,
+                solution.ingredients
+            );
+        }
+        return;
+    }
+
+    if args.count_optima {
+        let count = bb.count_optima(&problem.cocktail_sets());
+        println!("Search rounds {:?}", bb.counter);
+        if !bb.status.completed() {
+            println!("The search stopped early, so there may be more");
+        }
+        println!(
+            "{} distinct ingredient sets score {}",
+            count.total(),
+            count.score
+        );
+        println!("Ingredients       Sets");
+        for (size, sets) in &count.by_size {
+            println!("{size:>11}  {sets:>9}");
+        }
+        return;
+    }
+
+    if let Some(target) = args.target {
+        let Some(solution) = problem.min_ingredients_for(&mut bb, target) else {
+            println!("Search rounds {:?}", bb.counter);
+            println!("No ingredient set makes {target} cocktails");
+            return;
+        };
+        println!("Search rounds {:?}", bb.counter);
+        if !bb.status.completed() {
+            println!("The search stopped early, so fewer ingredients may do");
+        }
+        println!(
+            "Fewest ingredients: {}, making {} cocktails",
+            solution.ingredients.len(),
+            solution.cocktails.len()
+        );
+        println!("Cocktails: {:?}", solution.cocktails);
+        println!("Ingredients: {:?}", solution.ingredients);
+        return;
+    }
+
+    let mut greedy = Greedy;
+    let mut annealing = Annealing::new(args.iterations);
+    if let Some(seed) = args.rng_seed {
+        annealing = annealing.with_seed(seed);
+    }
+    let solver: &mut dyn Solver = match args.solver {
+        Algorithm::Exact => &mut bb,
+        Algorithm::Greedy => &mut greedy,
+        Algorithm::Anneal => &mut annealing,
+        Algorithm::Portfolio => &mut portfolio,
+        Algorithm::BruteForce => &mut BruteForce,
+        #[cfg(feature = "ilp")]
+        Algorithm::Ilp => &mut branchbound::ilp::Ilp,
+    };
+    let mut solution = or_exit(match args.resume {
+        Some(_) => problem.resume(&mut bb),
+        None => solver.solve(&problem),
+    });
+    if let Some(e) = &bb.checkpoint_error {
+        eprintln!("Couldn't save a checkpoint: {e}");
+    }
+    // read back from the solution, as only the exact search leaves its result in `bb`
+    let best = &solution
+        .cocktails
+        .iter()
+        .filter_map(|name| problem.find(name))
+        .map(|cocktail| cocktail.ingredients.clone())
+        .collect::<FxHashSet<IngredientSeti>>();
+    let best_ingredients = solution
+        .ingredients
+        .iter()
+        .filter_map(|name| problem.mapper().id(name))
+        .collect::<IngredientSeti>();
+    let mut best_cocktails = best
         .iter()
-        .flatten()
-        .copied()
-        .collect::<FxHashSet<Ingredienti>>();
-    // map back from i32 to ingredient names
-    let mut fset_names = fset
+        .map(|cocktail| problem.cocktail(cocktail).unwrap())
+        .collect::<Vec<&Cocktail>>();
+    best_cocktails.sort_unstable_by(|a, b| a.name.cmp(&b.name));
+    let best_names = best_cocktails
         .iter()
-        .map(|entry| ingredient_lookup_reverse.get(entry).unwrap())
-        .collect::<Vec<&&Ingredient>>();
-    fset_names.sort_unstable();
+        .map(|cocktail| &cocktail.name)
+        .collect::<Vec<&String>>();
+    let fset_names = solution.ingredients.iter().collect::<Vec<&Ingredient>>();
+
+    if let (Some(path), Some(profile)) = (&args.profile, &bb.profile) {
+        or_exit(create(path).and_then(|f| {
+            profile.write_folded(f).map_err(|source| Error::Io {
+                path: path.clone(),
+                source,
+            })
+        }));
+    }
+
+    if let Some(path) = &args.menu {
+        let menu = Menu {
+            title: "Cocktail menu".to_owned(),
+            ingredients: fset_names.clone(),
+            cocktails: best_cocktails.clone(),
+            mapper: problem.mapper(),
+        };
+        let text = if path.extension().is_some_and(|ext| ext == "html") {
+            menu.to_html()
+        } else {
+            menu.to_markdown()
+        };
+        or_exit(std::fs::write(path, text).map_err(|source| Error::Io {
+            path: path.clone(),
+            source,
+        }));
+    }
+
+    if let Some(path) = &args.heatmap {
+        let ids = fset_names
+            .iter()
+            .map(|name| problem.mapper().id(name).unwrap())
+            .collect::<Vec<Ingredienti>>();
+        let matrix = analysis::coverage_matrix(
+            best_cocktails.iter().map(|cocktail| &cocktail.ingredients),
+            &ids,
+        );
+        or_exit(write_heatmap(path, &best_names, &fset_names, &matrix));
+    }
 
-    println!("Search rounds {:?}", bb.counter);
-    println!("Ingredient set ({}): {:?}", &fset_names.len(), &fset_names);
+    if args.solver == Algorithm::Exact {
+        println!("Search rounds {:?}", bb.counter);
+    }
+    if let Some(stats) = solution.stats.as_ref().filter(|_| args.stats) {
+        println!(
+            "Nodes expanded: {}, pruned: {}, deepest: {}",
+            stats.nodes,
+            stats.pruned(),
+            stats.peak_depth
+        );
+        for reason in Prune::ALL {
+            let pruned = stats.prunes.get(&reason).copied().unwrap_or(0);
+            match stats.bounds.get(&reason) {
+                Some(usage) => println!(
+                    "  {pruned:>9}  by the {} bound, evaluated {} times in {:.3}s",
+                    reason.name(),
+                    usage.evaluated,
+                    usage.time.as_secs_f64()
+                ),
+                None if pruned > 0 => println!("  {pruned:>9}  by the {} bound", reason.name()),
+                None => {}
+            }
+        }
+        if !stats.eliminated.is_empty() {
+            println!("Cocktails dropped before searching, as they:");
+        }
+        for (reason, eliminated) in &stats.eliminated {
+            println!("  {eliminated:>9}  {}", reason.describe());
+        }
+        if stats.probed_required > 0 {
+            println!("Ingredients required by probing: {}", stats.probed_required);
+        }
+        for names in problem.duplicates() {
+            println!("Searched as one, sharing their ingredients: {names:?}");
+        }
+        for improvement in &stats.improvements {
+            println!(
+                "  {:>8.3}s  node {:>9}: score {} with {} ingredients",
+                improvement.elapsed.as_secs_f64(),
+                improvement.node,
+                improvement.score,
+                improvement.ingredients
+            );
+        }
+    }
+    if bb.approximation_factor() < 1.0 {
+        println!(
+            "Approximate search: guaranteed to make at least {:.0} % as many cocktails as the optimum",
+            bb.approximation_factor() * 100.0
+        );
+    }
+    if bb.status == SearchStatus::TimedOut {
+        println!("Time limit reached, so this may not be optimal");
+    }
+    if bb.depth_truncations > 0 {
+        println!(
+            "Depth limit left {} branches unexplored, so this may not be optimal",
+            bb.depth_truncations
+        );
+    }
+    if let Some(gap) = solution.gap {
+        // weights and house cocktails make the score something other than a count
+        if args.weights.is_some() || !bb.house.is_empty() {
+            println!("Optimality gap: score {gap}");
+        } else {
+            println!("Optimality gap: {gap} cocktails possible");
+        }
+    }
+    let (owned, bought): (Vec<&Ingredient>, Vec<&Ingredient>) =
+        fset_names.iter().partition(|name| {
+            problem
+                .mapper()
+                .id(name)
+                .is_some_and(|id| problem.owned().contains(&id))
+        });
+    if owned.is_empty() {
+        println!("Ingredient set ({}): {:?}", &fset_names.len(), &fset_names);
+    } else {
+        println!("Ingredients to buy ({}): {:?}", bought.len(), bought);
+        println!("Already owned ({}): {:?}", owned.len(), owned);
+    }
     println!(
         "Possible cocktails ({}) with this set: {:?}",
         &best_names.len(),
         &best_names
     );
+    // only worth reporting where each recipe came from if there's a choice
+    let merged = problem
+        .cocktails()
+        .iter()
+        .flat_map(|cocktail| &cocktail.sources)
+        .collect::<FxHashSet<&String>>()
+        .len()
+        > 1;
+    if merged {
+        println!("Sources:");
+        for (cocktail, from) in &solution.sources {
+            println!("  {cocktail}: {}", from.join(", "));
+        }
+    } else {
+        solution.sources.clear();
+    }
+    if args.variants.is_some() {
+        println!(
+            "Distinct drinks, counting variants once: {}",
+            bb.distinct(best)
+        );
+    }
+    if let Some(budget) = args.budget {
+        println!(
+            "Ingredient cost: {:.2} of {budget:.2}",
+            problem.cost(&best_ingredients)
+        );
+    }
+    if args.weights.is_some() {
+        println!(
+            "Total weight of cocktails made: {:.2}",
+            best_cocktails
+                .iter()
+                .map(|cocktail| cocktail.weight)
+                .sum::<f64>()
+        );
+    }
+    if !bb.house.is_empty() {
+        println!(
+            "House cocktails made: {} of {}",
+            bb.house_made(best),
+            bb.house.len()
+        );
+    }
+
+    for (rank, alternative) in solution.alternatives().enumerate().skip(1) {
+        println!(
+            "Alternative {} ({} cocktails): {:?} makes {:?}",
+            rank + 1,
+            alternative.cocktails.len(),
+            alternative.ingredients,
+            alternative.cocktails
+        );
+    }
+    for tie in &solution.ties {
+        println!(
+            "Tied ({} ingredients): {:?} makes {:?}",
+            tie.ingredients.len(),
+            tie.ingredients,
+            tie.cocktails
+        );
+    }
+
+    if let Some(path) = &args.save_solution {
+        let f = or_exit(create(path));
+        or_exit(serde_json::to_writer_pretty(f, &solution));
+    }
+
+    let padding = bb
+        .category_minimums
+        .iter()
+        .flat_map(|minimum| minimum.padding(&best_ingredients))
+        .collect::<IngredientSeti>();
+    if !padding.is_empty() {
+        let mut padding_names = padding
+            .iter()
+            .map(|entry| problem.mapper().name(*entry).unwrap())
+            .collect::<Vec<&Ingredient>>();
+        padding_names.sort_unstable();
+        println!(
+            "Also buy ({}) to meet category minimums: {:?}",
+            padding_names.len(),
+            padding_names
+        );
+    }
+
+    for limit in &bb.soft_limits {
+        let present = limit.ingredients.intersection(&best_ingredients).count();
+        if present > limit.limit {
+            println!(
+                "Over the preferred {} {}: {present}, costing {} cocktails",
+                limit.limit,
+                limit.category,
+                (present - limit.limit) * args.over_penalty
+            );
+        }
+    }
+
+    if let Some(info) = &info {
+        let stats = analysis::menu_stats(
+            best_cocktails.iter().map(|cocktail| &cocktail.ingredients),
+            info,
+        );
+        println!(
+            "Menu balance: {} spirit-forward, {} long drinks, {} other",
+            stats.spirit_forward, stats.long_drinks, stats.other
+        );
+        if let Some(abv) = stats.average_abv {
+            println!("Average estimated ABV: {abv:.1}%");
+        }
+        for (spirit, count) in &stats.base_spirits {
+            println!("  {count:>3}  {spirit}");
+        }
+    }
+}
+
+/// Write a coverage matrix as CSV (a header row of ingredients, then one 0/1 row per cocktail)
+/// or, if the path ends in `.json`, as an object holding the ingredient and cocktail names
+/// alongside the matrix rows
+fn write_heatmap(
+    path: &Path,
+    cocktails: &[&String],
+    ingredients: &[&Ingredient],
+    matrix: &[Vec<bool>],
+) -> Result<(), Error> {
+    let f = create(path)?;
+    if path.extension().is_some_and(|ext| ext == "json") {
+        let value = serde_json::json!({
+            "ingredients": ingredients,
+            "cocktails": cocktails,
+            "matrix": matrix,
+        });
+        serde_json::to_writer_pretty(f, &value)?;
+    } else {
+        let mut csvw = csv::Writer::from_writer(f);
+        csvw.write_record(
+            std::iter::once("cocktail").chain(ingredients.iter().map(|i| i.as_str())),
+        )?;
+        for (cocktail, row) in cocktails.iter().zip(matrix) {
+            csvw.write_record(
+                std::iter::once(cocktail.as_str()).chain(row.iter().map(|&used| {
+                    if used {
+                        "1"
+                    } else {
+                        "0"
+                    }
+                })),
+            )?;
+        }
+        csvw.flush().map_err(|source| Error::Io {
+            path: path.to_owned(),
+            source,
+        })?;
+    }
+    Ok(())
+}
+
+fn analyze(problem: &Problem, args: &AnalyzeArgs) {
+    let name = |ingredient: &Ingredienti| problem.mapper().name(*ingredient).unwrap();
+    let cocktails = problem.cocktail_sets();
+    println!(
+        "{} cocktails using {} ingredients",
+        cocktails.len(),
+        problem.mapper().len()
+    );
+
+    let cooccurrence = analysis::cooccurrence(&cocktails);
+    let mut pairs = cooccurrence.pairs();
+    println!("\nMost common ingredient pairs:");
+    for ((a, b), count, pmi) in pairs.iter().take(args.top) {
+        println!("  {count:>3}  {} + {} (PMI {pmi:.2})", name(a), name(b));
+    }
+    pairs.retain(|(_, count, _)| *count >= args.min_count);
+    pairs.sort_by(|x, y| y.2.total_cmp(&x.2).then(y.1.cmp(&x.1)));
+    println!(
+        "\nStrongest ingredient associations (PMI, pairs seen at least {} times):",
+        args.min_count
+    );
+    for ((a, b), count, pmi) in pairs.iter().take(args.top) {
+        println!(
+            "  {pmi:>5.2}  {} + {} ({count} cocktails)",
+            name(a),
+            name(b)
+        );
+    }
+
+    println!("\nMost central ingredients (betweenness, degree, strength):");
+    for entry in analysis::centrality(&cocktails).iter().take(args.top) {
+        println!(
+            "  {:.3}  {:>3}  {:>3}  {}",
+            entry.betweenness,
+            entry.degree,
+            entry.strength,
+            name(&entry.ingredient)
+        );
+    }
+
+    let difficulty = problem.difficulty();
+    println!("\nHardest cocktails to justify (amortized ingredient cost, min cover):");
+    for (cocktail, difficulty) in difficulty.iter().take(args.top) {
+        println!(
+            "  {:.2}  {:>3}  {cocktail}",
+            difficulty.amortized_cost, difficulty.min_cover
+        );
+    }
+
+    let cover = analysis::full_cover(&cocktails);
+    println!(
+        "\nMaking every cocktail takes {} ingredients. Cocktails forcing ingredients nothing else uses:",
+        cover.ingredients.len()
+    );
+    for (cocktail, unique) in cover.forced.iter().take(args.top) {
+        println!(
+            "  {:>3}  {}: {}",
+            unique.len(),
+            problem.cocktail(cocktail).unwrap().name,
+            unique
+                .iter()
+                .map(|ingredient| name(ingredient).as_str())
+                .collect::<Vec<&str>>()
+                .join(", ")
+        );
+    }
+
+    if let Some(wanted) = &args.similar_to {
+        let Some(target) = problem.find(wanted) else {
+            eprintln!("No cocktail called {wanted:?}");
+            std::process::exit(1);
+        };
+        let similarity = if args.overlap {
+            analysis::overlap
+        } else {
+            analysis::jaccard
+        };
+        println!("\nCocktails most similar to {}:", target.name);
+        for (cocktail, score) in
+            analysis::most_similar(&target.ingredients, &cocktails, args.top, similarity)
+        {
+            println!("  {score:.2}  {}", problem.cocktail(cocktail).unwrap().name);
+        }
+    }
+
+    if let Some(path) = &args.log {
+        let log = or_exit(open(path).and_then(|f| weights::read_log(f).map_err(Error::from)));
+        let mut derived = weights::derive_weights(&log, args.half_life, None)
+            .into_iter()
+            .collect::<Vec<(String, f64)>>();
+        derived.sort_unstable_by(|a, b| b.1.total_cmp(&a.1).then(a.0.cmp(&b.0)));
+        println!(
+            "\nCocktail weights from {} logged drinks (half-life {} days):",
+            log.len(),
+            args.half_life
+        );
+        for (cocktail, weight) in derived.iter().take(args.top) {
+            let known = problem.find(cocktail).is_some();
+            let note = if known { "" } else { " (not in dataset)" };
+            println!("  {weight:>6.2}  {cocktail}{note}");
+        }
+    }
+}
+
+fn bench(mut problem: Problem, args: &BenchArgs) {
+    problem.max_size = args.ingredients;
+    let bound_sets = match args.bounds.as_slice() {
+        [] => vec![None],
+        sets => sets.iter().map(Some).collect(),
+    };
+    let branchings = match args.branching.as_slice() {
+        [] => &[Branching::MinAmortizedCost][..],
+        branchings => branchings,
+    };
+    let seeds = std::iter::once(None)
+        .chain((0..args.seeds).map(Some))
+        .collect::<Vec<Option<u64>>>();
+    let mut configurations = vec![];
+    for bounds in &bound_sets {
+        for &branching in branchings {
+            for &seed in &seeds {
+                let mut builder = problem.builder(args.max_calls);
+                let mut label = match bounds {
+                    Some(bounds) => {
+                        builder = builder.bounds(bounds.iter().copied());
+                        bounds
+                            .iter()
+                            .map(|reason| reason.name())
+                            .collect::<Vec<&str>>()
+                            .join(",")
+                    }
+                    None => "default bounds".to_string(),
+                };
+                builder = branching.apply(builder, seed.unwrap_or(0));
+                label.push_str(&format!(
+                    ", {}",
+                    branching.to_possible_value().unwrap().get_name()
+                ));
+                if let Some(seed) = seed {
+                    builder = builder.with_seed(seed);
+                    label.push_str(&format!(", seed {seed}"));
+                }
+                configurations.push((label, builder));
+            }
+        }
+    }
+    let trials = or_exit(
+        bench::compare(configurations, &problem.cocktail_sets(), args.runs).map_err(Error::from),
+    );
+    let width = trials
+        .iter()
+        .map(|trial| trial.label.chars().count())
+        .max()
+        .unwrap_or(0)
+        .max("configuration".len());
+    println!(
+        "{:width$}  {:>9}  {:>9}  {:>8}  {:>5}",
+        "configuration", "nodes", "pruned", "time", "score"
+    );
+    for trial in &trials {
+        let incomplete = if trial.status.completed() {
+            ""
+        } else {
+            "  (incomplete)"
+        };
+        println!(
+            "{:width$}  {:>9}  {:>9}  {:>7.3}s  {:>5}{incomplete}",
+            trial.label,
+            trial.nodes,
+            trial.pruned,
+            trial.time.as_secs_f64(),
+            trial.score
+        );
+    }
+}
+
+fn main() {
+    let cli = Cli::parse();
+    let parser = cli.strip_quantities.then(|| match &cli.units {
+        Some(units) => QuantityParser::new(units.iter().map(String::as_str)),
+        None => QuantityParser::default(),
+    });
+    let problem = load(&cli.input, parser.as_ref());
+    match cli.command {
+        Some(Command::Solve(args)) => solve(problem, &args),
+        Some(Command::Analyze(args)) => analyze(&problem, &args),
+        Some(Command::Bench(args)) => bench(problem, &args),
+        None => solve(problem, &cli.solve),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn writes_heatmaps_as_csv_or_json() {
+        let (negroni, gimlet) = ("Negroni".to_owned(), "Gimlet".to_owned());
+        let (gin, lime) = ("gin".to_owned(), "lime".to_owned());
+        let matrix = [vec![true, false], vec![true, true]];
+        let path = std::env::temp_dir().join(format!("heatmap-{}", std::process::id()));
+        write_heatmap(&path, &[&negroni, &gimlet], &[&gin, &lime], &matrix).unwrap();
+        assert_eq!(
+            std::fs::read_to_string(&path).unwrap(),
+            "cocktail,gin,lime\nNegroni,1,0\nGimlet,1,1\n"
+        );
+        std::fs::remove_file(&path).ok();
+        let path = path.with_extension("json");
+        write_heatmap(&path, &[&negroni, &gimlet], &[&gin, &lime], &matrix).unwrap();
+        let json = serde_json::from_reader::<_, serde_json::Value>(File::open(&path).unwrap());
+        assert_eq!(
+            json.unwrap(),
+            serde_json::json!({
+                "ingredients": ["gin", "lime"],
+                "cocktails": ["Negroni", "Gimlet"],
+                "matrix": [[true, false], [true, true]],
+            })
+        );
+        std::fs::remove_file(path).ok();
+    }
 }
@@ -0,0 +1,179 @@
+//! Mapping ingredient names to the small integer ids used by the solver
+//!
+//! Hashing and comparing `i32`s is far cheaper than `String`s, so cocktails are searched
+//! as sets of ingredient ids. [`IdMapper`] hands those ids out.
+use crate::{Ingredient, IngredientSeti, Ingredienti};
+use rustc_hash::FxHashMap;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// Assigns dense, stable ids to ingredient names
+///
+/// Ids are assigned in order of first appearance, starting at 0, and never change once
+/// assigned. Ingredients seen for the first time in a later batch of cocktails (e.g. when
+/// a dataset is extended) simply get the next free id, so existing encodings stay valid.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IdMapper {
+    ids: FxHashMap<Ingredient, Ingredienti>,
+    names: Vec<Ingredient>,
+}
+
+impl IdMapper {
+    #[must_use]
+    pub fn new() -> IdMapper {
+        IdMapper::default()
+    }
+
+    /// The id of `name`, assigning the next free id if it hasn't been seen before
+    pub fn intern(&mut self, name: &str) -> Ingredienti {
+        if let Some(&id) = self.ids.get(name) {
+            return id;
+        }
+        let id = Ingredienti::try_from(self.names.len()).expect("too many ingredients");
+        self.ids.insert(name.to_owned(), id);
+        self.names.push(name.to_owned());
+        id
+    }
+
+    /// The id of `name`, if it's been seen
+    #[must_use]
+    pub fn id(&self, name: &str) -> Option<Ingredienti> {
+        self.ids.get(name).copied()
+    }
+
+    /// The name of the ingredient with this id
+    #[must_use]
+    pub fn name(&self, id: Ingredienti) -> Option<&Ingredient> {
+        usize::try_from(id).ok().and_then(|i| self.names.get(i))
+    }
+
+    /// The number of ingredients seen. Every id is less than this
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.names.len()
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.names.is_empty()
+    }
+
+    /// Every (id, name) pair, in id order
+    pub fn iter(&self) -> impl Iterator<Item = (Ingredienti, &Ingredient)> {
+        (0..).zip(self.names.iter())
+    }
+
+    /// Encode a cocktail's ingredients, assigning ids to any unseen ones
+    pub fn encode<'a>(&mut self, ingredients: impl IntoIterator<Item = &'a str>) -> IngredientSeti {
+        ingredients
+            .into_iter()
+            .map(|ingredient| self.intern(ingredient))
+            .collect()
+    }
+
+    /// Encode ingredients without assigning new ids, returning the first unknown name
+    /// as the error
+    pub fn lookup<'a>(
+        &self,
+        ingredients: impl IntoIterator<Item = &'a str>,
+    ) -> Result<IngredientSeti, &'a str> {
+        ingredients
+            .into_iter()
+            .map(|ingredient| self.id(ingredient).ok_or(ingredient))
+            .collect()
+    }
+
+    /// The names of a set of ingredient ids, sorted. Unknown ids are skipped
+    #[must_use]
+    pub fn decode(&self, ingredients: &IngredientSeti) -> Vec<&Ingredient> {
+        let mut names = ingredients
+            .iter()
+            .filter_map(|&id| self.name(id))
+            .collect::<Vec<&Ingredient>>();
+        names.sort_unstable();
+        names
+    }
+
+    /// `ingredients`, formatted by name rather than id, e.g. `{Campari, Gin}`, for
+    /// messages and logging
+    #[must_use]
+    pub fn display<'a>(&'a self, ingredients: &'a IngredientSeti) -> NamedSet<'a> {
+        NamedSet {
+            mapper: self,
+            ingredients,
+        }
+    }
+}
+
+/// An ingredient set that formats as its ingredients' names, sorted. Unknown ids are
+/// shown as `#id`. See [`IdMapper::display`]
+#[derive(Clone, Copy)]
+pub struct NamedSet<'a> {
+    mapper: &'a IdMapper,
+    ingredients: &'a IngredientSeti,
+}
+
+impl NamedSet<'_> {
+    fn names(&self) -> Vec<String> {
+        let mut names = self
+            .ingredients
+            .iter()
+            .map(|&id| match self.mapper.name(id) {
+                Some(name) => name.clone(),
+                None => format!("#{id}"),
+            })
+            .collect::<Vec<String>>();
+        names.sort_unstable();
+        names
+    }
+}
+
+impl fmt::Display for NamedSet<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{{{}}}", self.names().join(", "))
+    }
+}
+
+impl fmt::Debug for NamedSet<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_set().entries(self.names()).finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keeps_ids_across_batches() {
+        let mut mapper = IdMapper::new();
+        let negroni = mapper.encode(["gin", "campari", "vermouth"]);
+        assert_eq!(negroni, IngredientSeti::from([0, 1, 2]));
+        assert_eq!(mapper.intern("campari"), 1);
+        // a later batch only numbers what it adds
+        let gimlet = mapper.encode(["lime", "gin"]);
+        assert_eq!(gimlet, IngredientSeti::from([3, 0]));
+        assert_eq!(mapper.encode(["gin", "campari", "vermouth"]), negroni);
+        assert_eq!(mapper.len(), 4);
+    }
+
+    #[test]
+    fn looks_up_without_numbering() {
+        let mut mapper = IdMapper::new();
+        mapper.encode(["gin", "lime"]);
+        assert_eq!(
+            mapper.lookup(["lime", "gin"]),
+            Ok(IngredientSeti::from([0, 1]))
+        );
+        assert_eq!(mapper.lookup(["gin", "rum", "sugar"]), Err("rum"));
+        assert_eq!(mapper.len(), 2);
+    }
+
+    #[test]
+    fn decodes_known_ids_by_name() {
+        let mut mapper = IdMapper::new();
+        let ids = mapper.encode(["lime", "gin"]);
+        assert_eq!(mapper.decode(&ids), ["gin", "lime"]);
+        assert_eq!(mapper.decode(&IngredientSeti::from([1, 7, -1])), ["gin"]);
+    }
+}
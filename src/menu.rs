@@ -0,0 +1,97 @@
+//! Rendering a solution as a drinks menu
+use crate::cocktail::Cocktail;
+use crate::mapper::IdMapper;
+use crate::Ingredient;
+use std::fmt::Write;
+
+/// A shopping list and the cocktails it makes, with their recipes where known
+#[derive(Debug, Clone)]
+pub struct Menu<'a> {
+    pub title: String,
+    pub ingredients: Vec<&'a Ingredient>,
+    pub cocktails: Vec<&'a Cocktail>,
+    /// Names the cocktails' ingredients
+    pub mapper: &'a IdMapper,
+}
+
+impl Menu<'_> {
+    /// Render as Markdown: a shopping list, then a section per cocktail
+    #[must_use]
+    pub fn to_markdown(&self) -> String {
+        let mut out = format!("# {}\n\n## Shopping list\n\n", self.title);
+        for ingredient in &self.ingredients {
+            writeln!(out, "- {ingredient}").unwrap();
+        }
+        for cocktail in &self.cocktails {
+            write!(out, "\n## {}\n\n", cocktail.name).unwrap();
+            for ingredient in self.mapper.decode(&cocktail.ingredients) {
+                writeln!(out, "- {ingredient}").unwrap();
+            }
+            if let Some(recipe) = &cocktail.recipe {
+                write!(out, "\n{recipe}\n").unwrap();
+            }
+        }
+        out
+    }
+
+    /// Render as a standalone HTML page, with the same structure as
+    /// [`to_markdown`](Self::to_markdown)
+    #[must_use]
+    pub fn to_html(&self) -> String {
+        let title = escape(&self.title);
+        let mut out = format!(
+            "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{title}</title>\n</head>\n<body>\n<h1>{title}</h1>\n<h2>Shopping list</h2>\n"
+        );
+        list(&mut out, self.ingredients.iter().copied());
+        for cocktail in &self.cocktails {
+            writeln!(out, "<h2>{}</h2>", escape(&cocktail.name)).unwrap();
+            list(&mut out, self.mapper.decode(&cocktail.ingredients));
+            if let Some(recipe) = &cocktail.recipe {
+                writeln!(out, "<p>{}</p>", escape(recipe)).unwrap();
+            }
+        }
+        out.push_str("</body>\n</html>\n");
+        out
+    }
+}
+
+fn list<'a>(out: &mut String, items: impl IntoIterator<Item = &'a Ingredient>) {
+    out.push_str("<ul>\n");
+    for item in items {
+        writeln!(out, "<li>{}</li>", escape(item)).unwrap();
+    }
+    out.push_str("</ul>\n");
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn escapes_html_but_not_markdown() {
+        let mut mapper = IdMapper::new();
+        let mut cocktail = Cocktail::new("Gin & <Tonic>", mapper.encode(["gin", "tonic"]));
+        cocktail.recipe = Some("Top with \"tonic\"".to_owned());
+        let ingredients = ["gin".to_owned(), "tonic".to_owned()];
+        let menu = Menu {
+            title: "Tom's \"bar\"".to_owned(),
+            ingredients: ingredients.iter().collect(),
+            cocktails: vec![&cocktail],
+            mapper: &mapper,
+        };
+        let html = menu.to_html();
+        assert!(html.contains("<title>Tom's &quot;bar&quot;</title>"));
+        assert!(html.contains("<h2>Gin &amp; &lt;Tonic&gt;</h2>\n<ul>\n<li>gin</li>\n"));
+        assert!(html.contains("<p>Top with &quot;tonic&quot;</p>"));
+        let markdown = menu.to_markdown();
+        assert!(markdown.starts_with("# Tom's \"bar\"\n"));
+        assert!(markdown.contains("\n## Gin & <Tonic>\n\n- gin\n- tonic\n\nTop with \"tonic\"\n"));
+    }
+}
@@ -0,0 +1,94 @@
+//! Optional per-ingredient metadata: strength and category
+//!
+//! The metadata file is a headerless CSV with one row per ingredient: its name, its ABV as
+//! a percentage (may be empty if unknown), and a free-form category such as `gin`,
+//! `liqueur` or `mixer` (may be omitted). Ingredients missing from the file simply have no
+//! metadata.
+use rustc_hash::FxHashMap;
+use std::fmt;
+use std::io::Read;
+
+/// Category of ingredients that lengthen a drink, such as soda or tonic water.
+/// Cocktails using one count as long drinks in [`MenuStats`](crate::analysis::MenuStats)
+pub const MIXER: &str = "mixer";
+/// Category of ingredients that are ignored when classifying a cocktail's style
+pub const GARNISH: &str = "garnish";
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct IngredientInfo {
+    /// Alcohol by volume, as a percentage
+    pub abv: Option<f64>,
+    pub category: Option<String>,
+}
+
+#[derive(Debug)]
+pub enum MetadataError {
+    Csv(csv::Error),
+    /// A row had an empty name, or an ABV that wasn't a number between 0 and 100
+    Malformed {
+        row: usize,
+        value: String,
+    },
+}
+
+impl fmt::Display for MetadataError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MetadataError::Csv(e) => write!(f, "couldn't read ingredient metadata: {e}"),
+            MetadataError::Malformed { row, value } => {
+                write!(
+                    f,
+                    "row {row} of ingredient metadata is malformed: {value:?}"
+                )
+            }
+        }
+    }
+}
+
+impl std::error::Error for MetadataError {}
+
+impl From<csv::Error> for MetadataError {
+    fn from(e: csv::Error) -> Self {
+        MetadataError::Csv(e)
+    }
+}
+
+/// Read an ingredient metadata file, keyed by ingredient name. Categories are lowercased
+pub fn read_metadata<R: Read>(
+    reader: R,
+) -> Result<FxHashMap<String, IngredientInfo>, MetadataError> {
+    let csvr = csv::ReaderBuilder::new()
+        .flexible(true)
+        .has_headers(false)
+        .trim(csv::Trim::All)
+        .from_reader(reader);
+    csvr.into_records()
+        .enumerate()
+        .map(|(i, record)| {
+            let r = record?;
+            let malformed = || MetadataError::Malformed {
+                row: i + 1,
+                value: r.iter().collect::<Vec<_>>().join(","),
+            };
+            let name = r
+                .get(0)
+                .filter(|name| !name.is_empty())
+                .ok_or_else(malformed)?;
+            let abv = match r.get(1).filter(|abv| !abv.is_empty()) {
+                Some(abv) => Some(
+                    abv.trim_end_matches('%')
+                        .parse::<f64>()
+                        .ok()
+                        .filter(|abv| (0.0..=100.0).contains(abv))
+                        .ok_or_else(malformed)?,
+                ),
+                None => None,
+            };
+            let category = r
+                .get(2)
+                .filter(|category| !category.is_empty())
+                .map(str::to_lowercase);
+            Ok((name.to_owned(), IngredientInfo { abv, category }))
+        })
+        .collect()
+}
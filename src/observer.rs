@@ -0,0 +1,126 @@
+//! Watching a search as it runs
+//!
+//! Register a [`SearchObserver`] with
+//! [`BranchBoundBuilder::observer`](crate::BranchBoundBuilder::observer) to log a
+//! search, drive a progress bar, or stop it early. Every hook has a default that does
+//! nothing, so implement only the ones you need. The hooks run on the searching
+//! thread (or threads, in a [parallel](crate::BranchBoundBuilder::parallel) search),
+//! so they should be quick.
+use crate::{BoundCost, IngredientSeti, BOUND_FUNCTIONS};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::ops::ControlFlow;
+use std::sync::{Arc, Mutex, PoisonError};
+
+/// Why a search node was pruned
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum Prune {
+    /// Its ingredients already break the budget or a category minimum
+    Infeasible,
+    /// Even making every candidate couldn't beat the incumbent
+    Total,
+    /// Too few candidates can be made with a single extra ingredient
+    Singleton,
+    /// Too few candidates share their ingredients with enough others
+    Concentration,
+    /// The ingredient budget can't buy enough of the candidates' ingredients, sharing
+    /// each candidate out between the ingredients it needs
+    Fractional,
+    /// Too few candidates have few enough ingredients of their own for the ingredient
+    /// budget to buy
+    Matching,
+    /// Too few candidates can be made together, as most pairs of them need more
+    /// ingredients than the budget has left
+    Conflict,
+    /// It has been searched already, by way of another branch. See
+    /// [`transposition`](crate::transposition)
+    Transposition,
+    /// What's left has been searched already, from a node scoring at least as well.
+    /// See [`subtree`](crate::subtree)
+    Subtree,
+}
+
+impl Prune {
+    /// Every reason, in order
+    pub const ALL: [Prune; 9] = [
+        Prune::Infeasible,
+        Prune::Total,
+        Prune::Singleton,
+        Prune::Concentration,
+        Prune::Fractional,
+        Prune::Matching,
+        Prune::Conflict,
+        Prune::Transposition,
+        Prune::Subtree,
+    ];
+
+    /// A short lowercase name, e.g. for log lines
+    #[must_use]
+    pub fn name(self) -> &'static str {
+        match self {
+            Prune::Infeasible => "infeasible",
+            Prune::Total => "total",
+            Prune::Singleton => "singleton",
+            Prune::Concentration => "concentration",
+            Prune::Fractional => "fractional",
+            Prune::Matching => "matching",
+            Prune::Conflict => "conflict",
+            Prune::Transposition => "transposition",
+            Prune::Subtree => "subtree",
+        }
+    }
+
+    /// The reason with this [name](Self::name), if there is one
+    #[must_use]
+    pub fn from_name(name: &str) -> Option<Prune> {
+        Prune::ALL.into_iter().find(|reason| reason.name() == name)
+    }
+
+    /// What the bound function pruning for this reason costs to evaluate, if it's one
+    /// of the bounds
+    #[must_use]
+    pub fn cost(self) -> Option<BoundCost> {
+        BOUND_FUNCTIONS
+            .iter()
+            .find(|(reason, _, _)| *reason == self)
+            .map(|(_, _, cost)| *cost)
+    }
+}
+
+/// Callbacks from a running search
+pub trait SearchObserver {
+    /// The search found a better ingredient set, worth `score`
+    fn on_new_incumbent(&mut self, _score: usize, _ingredients: &IngredientSeti) {}
+
+    /// The search is about to explore a node `depth` levels below the initial call,
+    /// with `calls_remaining` of its call budget left. Break to stop the search,
+    /// keeping the best set found so far
+    fn on_node_expanded(&mut self, _depth: usize, _calls_remaining: i32) -> ControlFlow<()> {
+        ControlFlow::Continue(())
+    }
+
+    /// A node was pruned, for the reason given
+    fn on_prune(&mut self, _reason: Prune) {}
+}
+
+/// A registered observer, shared between the clones of a builder and the threads of a
+/// parallel search
+#[derive(Clone)]
+pub(crate) struct Observer(Arc<Mutex<dyn SearchObserver + Send>>);
+
+impl Observer {
+    pub(crate) fn new(observer: impl SearchObserver + Send + 'static) -> Observer {
+        Observer(Arc::new(Mutex::new(observer)))
+    }
+
+    pub(crate) fn with<T>(&self, f: impl FnOnce(&mut dyn SearchObserver) -> T) -> T {
+        let mut observer = self.0.lock().unwrap_or_else(PoisonError::into_inner);
+        f(&mut *observer)
+    }
+}
+
+impl fmt::Debug for Observer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Observer")
+    }
+}
@@ -0,0 +1,361 @@
+//! Searching both branches of each node concurrently
+//!
+//! [`BranchBound::search_parallel`] explores the same tree as the sequential search, but
+//! hands the include and exclude branches of each node to [`rayon::join`]. The workers
+//! share the score a branch must beat through an atomic, so a good solution found by
+//! one prunes the others straight away; the incumbents themselves sit behind a mutex,
+//! which is only taken when they improve.
+use crate::checkpoint::Node;
+use crate::observer::Prune;
+use crate::stats::Improvement;
+use crate::table::CocktailSet;
+use crate::{
+    adapted_order, with_made, Alternative, BoundOrder, BoundTimes, BranchBound, IngredientSeti,
+    SearchStatus, Unmet, BOUND_FUNCTIONS, TIME_CHECK_INTERVAL,
+};
+use rustc_hash::FxHashSet;
+use std::ops::ControlFlow;
+use std::sync::atomic::{AtomicBool, AtomicI32, AtomicU32, AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Mutex, PoisonError};
+use std::time::Duration;
+
+/// The search state shared between workers
+struct Shared {
+    /// The [bar](BranchBound::bar) a branch must beat, [packed](pack) so that a higher
+    /// bar is a larger number
+    bar: AtomicU64,
+    incumbents: Mutex<Incumbents>,
+    calls: AtomicI32,
+    nodes: AtomicU32,
+    depth_truncations: AtomicU32,
+    /// See [`BranchBound::open_bound`]
+    open_bound: AtomicUsize,
+    timed_out: AtomicBool,
+    aborted: AtomicBool,
+    /// Nodes pruned, indexed by [`Prune`]
+    prunes: [AtomicU32; Prune::ALL.len()],
+    /// How many times each of the bound functions was evaluated, and for how long in
+    /// all, in the order they're consulted
+    bound_evaluations: [AtomicU32; BOUND_FUNCTIONS.len()],
+    bound_nanos: [AtomicU64; BOUND_FUNCTIONS.len()],
+    /// The order to consult the bound functions in, [packed](pack_order)
+    bound_order: AtomicU64,
+    peak_depth: AtomicUsize,
+}
+
+struct Incumbents {
+    highest: FxHashSet<IngredientSeti>,
+    highest_ingredients: IngredientSeti,
+    highest_score: usize,
+    alternatives: Vec<Alternative>,
+    ties: Vec<Alternative>,
+    improvements: Vec<Improvement>,
+}
+
+/// Higher scores first, then fewer ingredients
+fn pack(score: usize, padded_len: usize) -> u64 {
+    (score as u64) << 32 | u64::from(u32::MAX - padded_len as u32)
+}
+
+fn unpack(packed: u64) -> (usize, usize) {
+    ((packed >> 32) as usize, (u32::MAX - packed as u32) as usize)
+}
+
+/// A byte for each bound function's index, first first, so that workers always see
+/// a whole order
+fn pack_order(order: &BoundOrder) -> u64 {
+    const _: () = assert!(BOUND_FUNCTIONS.len() <= 8);
+    order
+        .iter()
+        .rev()
+        .fold(0, |packed, &index| packed << 8 | index as u64)
+}
+
+fn unpack_order(packed: u64) -> BoundOrder {
+    std::array::from_fn(|position| (packed >> (8 * position)) as u8 as usize)
+}
+
+impl BranchBound {
+    /// Search from the initial node, once it has been
+    /// [prepared](BranchBound::prepare), on `threads` workers
+    pub(crate) fn search_parallel(
+        &mut self,
+        threads: usize,
+        candidates: &FxHashSet<IngredientSeti>,
+        partial: &FxHashSet<IngredientSeti>,
+        forbidden: FxHashSet<IngredientSeti>,
+    ) -> FxHashSet<IngredientSeti> {
+        let (bar_score, bar_len) = self.bar(
+            self.highest_score,
+            &self.highest_ingredients,
+            &self.alternatives,
+            &self.ties,
+        );
+        let shared = Shared {
+            bar: AtomicU64::new(pack(bar_score, bar_len)),
+            incumbents: Mutex::new(Incumbents {
+                highest: self.highest.clone(),
+                highest_ingredients: self.highest_ingredients.clone(),
+                highest_score: self.highest_score,
+                alternatives: std::mem::take(&mut self.alternatives),
+                ties: std::mem::take(&mut self.ties),
+                improvements: vec![],
+            }),
+            calls: AtomicI32::new(self.calls),
+            nodes: AtomicU32::new(0),
+            depth_truncations: AtomicU32::new(0),
+            open_bound: AtomicUsize::new(0),
+            timed_out: AtomicBool::new(false),
+            aborted: AtomicBool::new(false),
+            prunes: Default::default(),
+            bound_evaluations: Default::default(),
+            bound_nanos: Default::default(),
+            bound_order: AtomicU64::new(pack_order(&self.bound_order)),
+            peak_depth: AtomicUsize::new(0),
+        };
+        let root = self.root_node(candidates, partial, &forbidden);
+        let this = &*self;
+        let run = || {
+            let Node {
+                candidates,
+                partial,
+                partial_ingredients,
+                forbidden,
+                ..
+            } = root;
+            this.expand(
+                &shared,
+                candidates,
+                partial,
+                partial_ingredients,
+                &forbidden,
+                0,
+            );
+        };
+        // if a dedicated pool can't be built, rayon's global one will do
+        match rayon::ThreadPoolBuilder::new().num_threads(threads).build() {
+            Ok(pool) => pool.install(run),
+            Err(_) => run(),
+        }
+        let incumbents = shared
+            .incumbents
+            .into_inner()
+            .unwrap_or_else(PoisonError::into_inner);
+        self.highest = incumbents.highest;
+        self.highest_ingredients = incumbents.highest_ingredients;
+        self.highest_score = incumbents.highest_score;
+        self.alternatives = incumbents.alternatives;
+        self.ties = incumbents.ties;
+        self.stats.improvements.extend(incumbents.improvements);
+        let calls = shared.calls.into_inner();
+        if shared.aborted.into_inner() {
+            self.status = SearchStatus::Aborted;
+        } else if shared.timed_out.into_inner() {
+            self.status = SearchStatus::TimedOut;
+        } else if calls < 0 {
+            self.status = SearchStatus::OutOfCalls;
+        }
+        self.calls = calls.max(0);
+        let nodes = shared.nodes.into_inner();
+        self.counter += nodes;
+        self.stats.nodes += nodes;
+        for (reason, pruned) in Prune::ALL.into_iter().zip(shared.prunes) {
+            match pruned.into_inner() {
+                0 => {}
+                pruned => *self.stats.prunes.entry(reason).or_insert(0) += pruned,
+            }
+        }
+        for (((reason, _, _), evaluated), nanos) in BOUND_FUNCTIONS
+            .iter()
+            .zip(shared.bound_evaluations)
+            .zip(shared.bound_nanos)
+        {
+            let usage = self.stats.bounds.entry(*reason).or_default();
+            usage.evaluated += evaluated.into_inner();
+            usage.time += Duration::from_nanos(nanos.into_inner());
+        }
+        self.bound_order = unpack_order(shared.bound_order.into_inner());
+        let peak_depth = self.depth + shared.peak_depth.into_inner();
+        self.stats.peak_depth = self.stats.peak_depth.max(peak_depth);
+        self.depth_truncations += shared.depth_truncations.into_inner();
+        self.open_bound = self.open_bound.max(shared.open_bound.into_inner());
+        self.highest.clone()
+    }
+
+    /// The parallel counterpart of one call to [`BranchBound::search`]
+    fn expand(
+        &self,
+        shared: &Shared,
+        candidates: CocktailSet,
+        partial: FxHashSet<IngredientSeti>,
+        partial_ingredients: IngredientSeti,
+        forbidden: &CocktailSet,
+        depth: usize,
+    ) {
+        let cocktails = self.cocktails.resolve(&candidates);
+        // a node left unexplored could still have led to anything its bounds allow
+        let abandon = || {
+            let bound = self.node_bound(&cocktails, &partial, &partial_ingredients);
+            shared.open_bound.fetch_max(bound, Ordering::Relaxed);
+        };
+        if shared.calls.fetch_sub(1, Ordering::Relaxed) <= 0 {
+            abandon();
+            return;
+        }
+        let nodes = shared.nodes.fetch_add(1, Ordering::Relaxed);
+        if shared.timed_out.load(Ordering::Relaxed) || self.past_deadline(nodes) {
+            shared.timed_out.store(true, Ordering::Relaxed);
+            abandon();
+            return;
+        }
+        if shared.aborted.load(Ordering::Relaxed) {
+            abandon();
+            return;
+        }
+        shared.peak_depth.fetch_max(depth, Ordering::Relaxed);
+        let calls = shared.calls.load(Ordering::Relaxed);
+        if self.observe(|observer| observer.on_node_expanded(depth, calls))
+            == Some(ControlFlow::Break(()))
+        {
+            shared.aborted.store(true, Ordering::Relaxed);
+            abandon();
+            return;
+        }
+        let score = self.score(&partial);
+
+        let (bar_score, bar_len) = unpack(shared.bar.load(Ordering::Acquire));
+        if self.improves(score, &partial_ingredients, bar_score, bar_len) {
+            let mut incumbents = shared
+                .incumbents
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner);
+            // another worker may have got there first while we waited
+            let highest_len = self.padded_len(&incumbents.highest_ingredients);
+            if self.improves(
+                score,
+                &partial_ingredients,
+                incumbents.highest_score,
+                highest_len,
+            ) {
+                incumbents.highest = partial.clone();
+                incumbents.highest_ingredients = partial_ingredients.clone();
+                incumbents.highest_score = score;
+                let improvement = self.stats.improvement(
+                    score,
+                    partial_ingredients.len(),
+                    self.counter + nodes + 1,
+                );
+                incumbents.improvements.push(improvement);
+                self.observe(|observer| observer.on_new_incumbent(score, &partial_ingredients));
+            }
+            if self.top_k > 1 {
+                self.offer(
+                    &mut incumbents.alternatives,
+                    score,
+                    &partial,
+                    &partial_ingredients,
+                );
+            }
+            if self.max_ties > 0 {
+                let highest_score = incumbents.highest_score;
+                self.tie(
+                    &mut incumbents.ties,
+                    highest_score,
+                    score,
+                    &partial,
+                    &partial_ingredients,
+                );
+            }
+            let (bar_score, bar_len) = self.bar(
+                incumbents.highest_score,
+                &incumbents.highest_ingredients,
+                &incumbents.alternatives,
+                &incumbents.ties,
+            );
+            shared
+                .bar
+                .store(pack(bar_score, bar_len), Ordering::Release);
+        }
+        let bar = unpack(shared.bar.load(Ordering::Acquire));
+        let mut times = BoundTimes::default();
+        let explore = self.keep_exploring(
+            &cocktails,
+            &partial,
+            &partial_ingredients,
+            bar,
+            &unpack_order(shared.bound_order.load(Ordering::Relaxed)),
+            &mut times,
+        );
+        for (index, time) in times.into_iter().enumerate() {
+            if let Some(time) = time {
+                shared.bound_evaluations[index].fetch_add(1, Ordering::Relaxed);
+                shared.bound_nanos[index].fetch_add(time.as_nanos() as u64, Ordering::Relaxed);
+            }
+        }
+        if self.adaptive_bounds && nodes.is_multiple_of(TIME_CHECK_INTERVAL) {
+            let order = adapted_order(std::array::from_fn(|index| {
+                let reason = BOUND_FUNCTIONS[index].0;
+                (
+                    shared.bound_evaluations[index].load(Ordering::Relaxed),
+                    Duration::from_nanos(shared.bound_nanos[index].load(Ordering::Relaxed)),
+                    shared.prunes[reason as usize].load(Ordering::Relaxed),
+                )
+            }));
+            shared
+                .bound_order
+                .store(pack_order(&order), Ordering::Relaxed);
+        }
+        if let Err(reason) = explore {
+            shared.prunes[reason as usize].fetch_add(1, Ordering::Relaxed);
+            self.observe(|observer| observer.on_prune(reason));
+            return;
+        }
+        if self.max_depth.is_some_and(|max| depth >= max) {
+            shared.depth_truncations.fetch_add(1, Ordering::Relaxed);
+            abandon();
+            return;
+        }
+        let Some(best) = self.choose(&cocktails, &partial_ingredients, None) else {
+            return;
+        };
+        // the workers can't share scratch space, so each call has its own
+        let (covered, permitted) = self.include_branch(
+            &candidates,
+            &partial_ingredients,
+            &best,
+            Some(forbidden),
+            &mut Unmet::default(),
+        );
+        let best = self
+            .cocktails
+            .index_of(&best)
+            .expect("candidates are in the table");
+        let remaining = self.exclude_branch(&candidates, &partial_ingredients, best);
+        let included = &partial | &covered;
+        let included_ingredients = with_made(&partial_ingredients, &covered);
+        let mut new_forbidden = forbidden.clone();
+        new_forbidden.insert(best);
+        rayon::join(
+            || {
+                self.expand(
+                    shared,
+                    permitted,
+                    included,
+                    included_ingredients,
+                    forbidden,
+                    depth + 1,
+                )
+            },
+            || {
+                self.expand(
+                    shared,
+                    remaining,
+                    partial,
+                    partial_ingredients,
+                    &new_forbidden,
+                    depth + 1,
+                )
+            },
+        );
+    }
+}
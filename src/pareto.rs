@@ -0,0 +1,116 @@
+//! The trade-off between ingredients, cocktails and cost
+//!
+//! Rather than the single best set for one budget, [`BranchBound::pareto`] finds every
+//! set that no other beats on all three counts at once: none makes as many cocktails
+//! (as [`score`](BranchBound::score) counts them) with no more ingredients and at no
+//! greater cost. Read in order of size, they show where each extra bottle stops paying
+//! for itself.
+//!
+//! For each number of ingredients, the search first finds the best set at any price,
+//! then tightens the [spend budget](crate::BranchBoundBuilder::spend_budget) to just
+//! under that set's cost and searches again, until nothing is left that makes a
+//! cocktail. Sets beaten by another, from this size or any other, are then dropped.
+//! Without [prices](crate::BranchBoundBuilder::prices) every set is free, so this is
+//! a [`sweep`](BranchBound::sweep) without the sizes that add nothing.
+use crate::{Alternative, BranchBound, IngredientSeti, SearchStatus, COST_TOLERANCE};
+use rustc_hash::FxHashSet;
+use std::ops::RangeInclusive;
+
+/// An ingredient set on the Pareto frontier
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParetoPoint {
+    /// How many ingredients it takes, counting any needed to meet category minimums
+    pub ingredients: usize,
+    /// What its ingredients cost
+    pub cost: f64,
+    /// The set itself, and what its cocktails score
+    pub best: Alternative,
+}
+
+impl ParetoPoint {
+    /// Is this at least as good as `other` on every count, and better on one?
+    fn dominates(&self, other: &ParetoPoint) -> bool {
+        let no_worse = self.ingredients <= other.ingredients
+            && self.best.score >= other.best.score
+            && self.cost <= other.cost + COST_TOLERANCE;
+        let better = self.ingredients < other.ingredients
+            || self.best.score > other.best.score
+            || self.cost < other.cost - COST_TOLERANCE;
+        no_worse && better
+    }
+}
+
+impl BranchBound {
+    /// Find the Pareto frontier of ingredient sets of each size in `sizes`, trading the
+    /// number of ingredients against the cocktails they make and what they cost, ordered
+    /// by size and then cost
+    ///
+    /// The per-cocktail caches are only computed once, and each search gets the full
+    /// call budget, so an incomplete search may leave a point off the frontier, or
+    /// include one that isn't on it. [`status`](Self::status) is left as the first
+    /// incomplete search's, if any, and the spend budget as it was.
+    pub fn pareto(
+        &mut self,
+        candidates: &FxHashSet<IngredientSeti>,
+        sizes: RangeInclusive<usize>,
+    ) -> Vec<ParetoPoint> {
+        let mut candidates = candidates.clone();
+        let mut partial = FxHashSet::default();
+        let mut forbidden = None;
+        if self.initial {
+            // only drop the cocktails too large for every size
+            self.max_size = *sizes.end();
+            self.prepare(&mut candidates, &mut partial, &mut forbidden);
+        }
+        let calls = self.calls;
+        let budget = self.budget;
+        let mut status = SearchStatus::Complete;
+        let mut points = vec![];
+        for size in sizes {
+            self.budget = budget;
+            loop {
+                self.restart(size, calls);
+                self.highest.clear();
+                self.highest_ingredients.clone_from(&self.required);
+                self.highest_score = self.score(&self.highest);
+                self.seed_greedy(&(&candidates | &partial));
+                self.search_prepared(&candidates, &partial, &forbidden);
+                if status.completed() {
+                    status = self.status;
+                }
+                if self.highest_score == 0 {
+                    break;
+                }
+                // an empty sum is -0
+                let cost = self.cost(&self.highest_ingredients) + 0.0;
+                points.push(ParetoPoint {
+                    ingredients: self.padded_len(&self.highest_ingredients),
+                    cost,
+                    best: self.best(),
+                });
+                // anything cheaper has to cost less than this, beyond the tolerance
+                let cheaper = cost - 2.0 * COST_TOLERANCE;
+                if cheaper < 0.0 {
+                    break;
+                }
+                self.budget = Some(cheaper);
+            }
+        }
+        self.budget = budget;
+        self.status = status;
+        let mut frontier = points
+            .iter()
+            .filter(|point| !points.iter().any(|other| other.dominates(point)))
+            .cloned()
+            .collect::<Vec<ParetoPoint>>();
+        frontier.sort_by(|a, b| {
+            a.ingredients
+                .cmp(&b.ingredients)
+                .then(a.cost.total_cmp(&b.cost))
+                .then(a.best.ingredients.cmp(&b.best.ingredients))
+        });
+        // the same set may be the best for several sizes
+        frontier.dedup_by(|a, b| a.best.ingredients == b.best.ingredients);
+        frontier
+    }
+}
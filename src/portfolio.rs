@@ -0,0 +1,186 @@
+//! Racing several solvers against each other on one problem
+//!
+//! A [`Portfolio`] runs exact searches that break ties differently, alongside the greedy
+//! heuristic and, optionally, simulated annealing, each on a thread of its own. They
+//! share the best ingredient set found so far: an exact search adopts a better one
+//! found elsewhere as its own incumbent, so that it prunes as if it had found it itself.
+//! The race ends as soon as one exact search completes, proving its incumbent optimal,
+//! or when the time limit is reached.
+use crate::anneal::Annealing;
+use crate::problem::Problem;
+use crate::solution::Solution;
+use crate::solver::Solver;
+use crate::{Alternative, BranchBound, BranchBoundBuilder, Error, IngredientSeti};
+use rustc_hash::FxHashSet;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, PoisonError};
+use std::time::Instant;
+
+/// The incumbent shared between the members of a portfolio
+#[derive(Debug)]
+pub(crate) struct Shared {
+    /// The score of `best`, to check against without taking the lock
+    score: AtomicUsize,
+    best: Mutex<Option<Alternative>>,
+    /// Set once an exact search has completed
+    done: AtomicBool,
+    deadline: Option<Instant>,
+}
+
+impl Shared {
+    /// Has an exact search completed?
+    pub(crate) fn finished(&self) -> bool {
+        self.done.load(Ordering::Relaxed)
+    }
+
+    /// Has an exact search completed, or the time run out?
+    pub(crate) fn stopped(&self) -> bool {
+        self.finished()
+            || self
+                .deadline
+                .is_some_and(|deadline| Instant::now() >= deadline)
+    }
+
+    /// Share `cocktails`, made by `ingredients` and worth `score`, if `bb` judges them
+    /// better than the shared incumbent
+    pub(crate) fn offer(
+        &self,
+        bb: &BranchBound,
+        score: usize,
+        cocktails: &FxHashSet<IngredientSeti>,
+        ingredients: &IngredientSeti,
+    ) {
+        let mut best = self.best.lock().unwrap_or_else(PoisonError::into_inner);
+        let better = best.as_ref().is_none_or(|best| {
+            bb.improves(
+                score,
+                ingredients,
+                best.score,
+                bb.padded_len(&best.ingredients),
+            )
+        });
+        if better {
+            *best = Some(Alternative {
+                score,
+                cocktails: cocktails.clone(),
+                ingredients: ingredients.clone(),
+            });
+            self.score.fetch_max(score, Ordering::Relaxed);
+        }
+    }
+
+    /// The shared incumbent, if it scores more than `score`
+    pub(crate) fn better_than(&self, score: usize) -> Option<Alternative> {
+        if self.score.load(Ordering::Relaxed) <= score {
+            return None;
+        }
+        self.best
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone()
+            .filter(|best| best.score > score)
+    }
+}
+
+/// A [`Solver`] racing exact searches, each breaking ties with a different seed,
+/// against the heuristics
+#[derive(Debug, Clone)]
+pub struct Portfolio {
+    /// Configures each exact search, including its call budget and time limit
+    builder: BranchBoundBuilder,
+    /// One exact search is run for each seed
+    pub seeds: Vec<u64>,
+    /// Also run simulated annealing, if set
+    pub annealing: Option<Annealing>,
+}
+
+impl Portfolio {
+    /// Race exact searches configured by `builder`, one for each of `seeds`, against the
+    /// greedy heuristic. The builder must come from the problem's
+    /// [`builder`](Problem::builder), and shouldn't be [parallel](BranchBoundBuilder::parallel)
+    #[must_use]
+    pub fn new(builder: BranchBoundBuilder, seeds: impl IntoIterator<Item = u64>) -> Portfolio {
+        Portfolio {
+            builder,
+            seeds: seeds.into_iter().collect(),
+            annealing: None,
+        }
+    }
+
+    /// Race simulated annealing too
+    #[must_use]
+    pub fn with_annealing(mut self, annealing: Annealing) -> Portfolio {
+        self.annealing = Some(annealing);
+        self
+    }
+}
+
+impl Solver for Portfolio {
+    fn solve(&mut self, problem: &Problem) -> Result<Solution, Error> {
+        let shared = Arc::new(Shared {
+            score: AtomicUsize::new(0),
+            best: Mutex::new(None),
+            done: AtomicBool::new(false),
+            deadline: self.builder.time_limit.map(|limit| Instant::now() + limit),
+        });
+        // without any seeds, there's still the search breaking ties by iteration order
+        let seeds = if self.seeds.is_empty() {
+            vec![None]
+        } else {
+            self.seeds.iter().copied().map(Some).collect()
+        };
+        let mut searches = seeds
+            .into_iter()
+            .map(|seed| {
+                let builder = self.builder.clone();
+                let mut bb = match seed {
+                    Some(seed) => builder.with_seed(seed),
+                    None => builder,
+                }
+                .try_build()?;
+                bb.portfolio = Some(Arc::clone(&shared));
+                Ok(bb)
+            })
+            .collect::<Result<Vec<BranchBound>, Error>>()?;
+        let cocktails = problem.cocktail_sets();
+        std::thread::scope(|scope| {
+            for bb in &mut searches {
+                let (shared, cocktails) = (&shared, &cocktails);
+                scope.spawn(move || {
+                    bb.search(&mut cocktails.clone(), &mut FxHashSet::default(), &mut None);
+                    if bb.status.completed() {
+                        shared.done.store(true, Ordering::Relaxed);
+                    }
+                });
+            }
+            scope.spawn(|| {
+                let bb = self.builder.clone().build();
+                let greedy = bb.greedy(&cocktails);
+                shared.offer(&bb, greedy.score, &greedy.cocktails, &greedy.ingredients);
+            });
+            if let Some(annealing) = &self.annealing {
+                // a configuration error would already have stopped the exact searches
+                scope.spawn(|| annealing.anneal(problem, Some(&shared)).ok());
+            }
+        });
+        // every search's upper bound holds, so report the tightest
+        let mut bb = searches
+            .into_iter()
+            .min_by_key(BranchBound::upper_bound)
+            .expect("there's always at least one exact search");
+        let best = shared
+            .best
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .take();
+        if let Some(best) = best {
+            let highest_len = bb.padded_len(&bb.highest_ingredients);
+            if bb.improves(best.score, &best.ingredients, bb.highest_score, highest_len) {
+                bb.highest = best.cocktails;
+                bb.highest_score = best.score;
+                bb.highest_ingredients = best.ingredients;
+            }
+        }
+        Ok(problem.result(&bb, &bb.highest))
+    }
+}
@@ -0,0 +1,191 @@
+//! Dropping the cocktails that can never be made, before searching
+//!
+//! A cocktail whose ingredients, with the required ones, include an excluded
+//! ingredient or a forbidden combination, need more ingredients than the budget allows
+//! or more of a category than its maximum, or cost more than the spend budget, can
+//! never be part of a solution. Each of these carries over to every cocktail whose
+//! ingredients are a superset of its own, so a cocktail making another impossible
+//! often takes several with it. Keeping them as
+//! candidates costs the search nothing in correctness, but they skew the
+//! [amortized costs](crate::analysis::difficulty) that guide branching, and leave the
+//! bounds counting cocktails that can't be made. Otherwise a superset is never dropped
+//! for its own sake: making it always makes one more cocktail.
+//!
+//! Every solver starts from the same [`reduce`](BranchBound::reduce)d candidates. Two
+//! cocktails with exactly the same ingredients are already one candidate, since the
+//! candidates are a set; [`Problem::duplicates`](crate::problem::Problem::duplicates)
+//! names them.
+//!
+//! [Probing](crate::BranchBoundBuilder::with_probing) goes further, once the search
+//! has an incumbent: it forces each ingredient in, then out, and asks the bounds
+//! whether the result could still beat it. An ingredient no better set can include
+//! takes every cocktail needing it with it, and one every better set needs becomes
+//! required. Unlike the checks above, these depend on the incumbent, so they only hold
+//! for the search that found it.
+use crate::{BranchBound, IngredientSeti, COST_TOLERANCE};
+use rustc_hash::FxHashSet;
+use serde::{Deserialize, Serialize};
+
+/// Why a cocktail can never be made
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum Elimination {
+    /// It needs an excluded ingredient
+    Excluded,
+    /// Its ingredients, with the required ones, include a forbidden combination
+    Forbidden,
+    /// Its ingredients, with the required ones, exceed the ingredient budget
+    TooLarge,
+    /// Its ingredients, with the required ones, include more of a category than its
+    /// maximum allows
+    OverMaximum,
+    /// Its ingredients, with the required ones, cost more than the spend budget
+    OverSpend,
+    /// [Probing](crate::BranchBoundBuilder::with_probing) showed that no set making it
+    /// could beat the best found before searching
+    Probed,
+}
+
+impl Elimination {
+    /// A short description, e.g. for reports
+    #[must_use]
+    pub fn describe(self) -> &'static str {
+        match self {
+            Elimination::Excluded => "need an excluded ingredient",
+            Elimination::Forbidden => "need a forbidden combination",
+            Elimination::TooLarge => "need too many ingredients",
+            Elimination::OverMaximum => "need too many of a category",
+            Elimination::OverSpend => "cost too much",
+            Elimination::Probed => "can't be in a better set than the first found",
+        }
+    }
+}
+
+impl BranchBound {
+    /// Why `cocktail` can never be made within this search's constraints, if it can't
+    #[must_use]
+    pub fn unmakeable(&self, cocktail: &IngredientSeti) -> Option<Elimination> {
+        if !cocktail.is_disjoint(&self.excluded) {
+            return Some(Elimination::Excluded);
+        }
+        let ingredients = cocktail | &self.required;
+        if self
+            .forbidden_combinations
+            .iter()
+            .any(|combination| combination.is_subset(&ingredients))
+        {
+            return Some(Elimination::Forbidden);
+        }
+        if self.padded_len(&ingredients) > self.max_size {
+            return Some(Elimination::TooLarge);
+        }
+        if !self.within_maximums(&ingredients) {
+            return Some(Elimination::OverMaximum);
+        }
+        if self
+            .budget
+            .is_some_and(|budget| self.cost(&ingredients) > budget + COST_TOLERANCE)
+        {
+            return Some(Elimination::OverSpend);
+        }
+        None
+    }
+
+    /// Drop the cocktails among `candidates` that can never be made, returning them and
+    /// why
+    pub fn reduce(
+        &self,
+        candidates: &mut FxHashSet<IngredientSeti>,
+    ) -> Vec<(IngredientSeti, Elimination)> {
+        let mut eliminated = vec![];
+        candidates.retain(|cocktail| match self.unmakeable(cocktail) {
+            Some(reason) => {
+                eliminated.push((cocktail.clone(), reason));
+                false
+            }
+            None => true,
+        });
+        eliminated
+    }
+
+    /// Drop the cocktails among `candidates` needing an ingredient no set beating the
+    /// incumbent can include, and require the ingredients every such set needs, until
+    /// the bounds find no more of either. See
+    /// [`with_probing`](crate::BranchBoundBuilder::with_probing)
+    pub(crate) fn probe(
+        &mut self,
+        candidates: &mut FxHashSet<IngredientSeti>,
+        partial: &mut FxHashSet<IngredientSeti>,
+    ) {
+        loop {
+            let partial_ingredients = self.ingredients(partial);
+            let probed = candidates
+                .iter()
+                .flatten()
+                .filter(|ingredient| !partial_ingredients.contains(ingredient))
+                .copied()
+                .collect::<IngredientSeti>();
+            let mut ruled_out = IngredientSeti::new();
+            let mut needed = IngredientSeti::new();
+            for &ingredient in &probed {
+                // with it, whatever it completes is made, and the rest are still to play for
+                let mut with = partial_ingredients.clone();
+                with.insert(ingredient);
+                let (made, rest): (Vec<_>, Vec<_>) = candidates
+                    .iter()
+                    .partition(|cocktail| cocktail.is_subset(&with));
+                let made = made.into_iter().cloned().collect::<FxHashSet<_>>();
+                if !self.admits(&with)
+                    || self.bound_given(&rest, &(&*partial | &made), &with) < self.highest_score
+                {
+                    ruled_out.insert(ingredient);
+                    continue;
+                }
+                let without = candidates
+                    .iter()
+                    .filter(|cocktail| !cocktail.contains(&ingredient))
+                    .collect::<Vec<_>>();
+                if self.bound_given(&without, partial, &partial_ingredients) < self.highest_score {
+                    needed.insert(ingredient);
+                }
+            }
+            // an ingredient is only needed if some set beating the incumbent is
+            // acceptable, which with the others needed it may not be
+            let mut required = self.required.clone();
+            needed.retain(|&ingredient| {
+                required.insert(ingredient);
+                let admitted = self.admits(&required);
+                if !admitted {
+                    required.remove(&ingredient);
+                }
+                admitted
+            });
+            if ruled_out.is_empty() && needed.is_empty() {
+                break;
+            }
+            candidates.retain(|cocktail| {
+                let kept = cocktail.is_disjoint(&ruled_out);
+                if !kept {
+                    self.stats.eliminate(Elimination::Probed);
+                }
+                kept
+            });
+            if !needed.is_empty() {
+                self.stats.probed_required += needed.len() as u32;
+                self.required = required;
+                candidates.retain(|cocktail| {
+                    let covered = cocktail.is_subset(&self.required);
+                    if covered {
+                        partial.insert(cocktail.clone());
+                    }
+                    !covered
+                });
+                // with more required, more cocktails can never be made
+                for (_, reason) in self.reduce(candidates) {
+                    self.stats.eliminate(reason);
+                }
+                // the caches discount required ingredients, so are stale now
+                self.cache_difficulties(candidates);
+            }
+        }
+    }
+}
@@ -0,0 +1,1044 @@
+//! A cocktail dataset and its constraints, in terms of names
+use crate::analysis::{self, Difficulty};
+use crate::cocktail::Cocktail;
+use crate::constraints::{CategoryMaximum, CategoryMinimum, SoftLimit};
+use crate::ilp::IntegerProgram;
+use crate::mapper::IdMapper;
+use crate::pareto::ParetoPoint;
+use crate::preprocess::Elimination;
+use crate::quantity::QuantityParser;
+use crate::solution::Solution;
+use crate::{
+    Alternative, BranchBound, BranchBoundBuilder, Error, Ingredient, IngredientSet, IngredientSeti,
+    Ingredienti, COST_TOLERANCE,
+};
+use csv::ReaderBuilder;
+use rand::seq::SliceRandom;
+use rand::Rng;
+use rustc_hash::{FxHashMap, FxHashSet};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Read, Write};
+use std::ops::RangeInclusive;
+use std::path::Path;
+
+/// Marks a CSV cell holding a recipe rather than an ingredient
+const RECIPE: &str = "recipe:";
+/// Cocktail weights are scaled by this and rounded, as the search scores in whole numbers
+const WEIGHT_SCALE: f64 = 1000.0;
+
+/// Named cocktails, an ingredient budget, and any constraints on acceptable ingredient sets
+///
+/// Cocktails are encoded into ingredient-id sets as they're added, so a `Problem` can
+/// configure a [`BranchBound`] search and translate what comes back into names:
+///
+/// ```
+/// use branchbound::problem::Problem;
+///
+/// let mut problem = Problem::new(4);
+/// problem.add_cocktail("Negroni", ["Gin", "Campari", "Sweet vermouth"]);
+/// problem.add_cocktail("Americano", ["Campari", "Sweet vermouth", "Soda water"]);
+/// problem.add_cocktail("Gimlet", ["Gin", "Lime juice", "Simple syrup"]);
+/// let solution = problem.solve(10_000).unwrap();
+/// assert_eq!(solution.cocktails, ["Americano", "Negroni"]);
+/// assert!(problem.validate(&solution).is_ok());
+/// ```
+///
+/// A `Problem` serializes with everything set on it, so a dataset and its constraints
+/// can be saved, e.g. as JSON, and loaded again without re-reading the CSV files.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Problem {
+    pub max_size: usize,
+    mapper: IdMapper,
+    cocktails: Vec<Cocktail>,
+    required: IngredientSeti,
+    category_minimums: Vec<CategoryMinimum>,
+    category_maximums: Vec<CategoryMaximum>,
+    soft_limits: Vec<SoftLimit>,
+    variants: Vec<Vec<IngredientSeti>>,
+    house: Vec<IngredientSeti>,
+    prices: FxHashMap<Ingredienti, f64>,
+    budget: Option<f64>,
+    owned: IngredientSeti,
+    excluded: IngredientSeti,
+    must_make: Vec<IngredientSeti>,
+    forbidden: Vec<IngredientSeti>,
+}
+
+/// A way in which a [`Solution`] fails to solve a [`Problem`]
+#[derive(Debug, Clone, PartialEq)]
+pub enum Violation {
+    /// The solution lists an ingredient the problem doesn't use
+    UnknownIngredient(Ingredient),
+    /// The solution lists a cocktail the problem doesn't have
+    UnknownCocktail(String),
+    /// The solution uses more ingredients than the budget allows
+    OverBudget { ingredients: usize, max_size: usize },
+    /// A listed cocktail needs ingredients the solution doesn't include
+    NotMakeable {
+        cocktail: String,
+        missing: Vec<Ingredient>,
+    },
+    /// A required ingredient is missing
+    MissingRequired(Ingredient),
+    /// A cocktail that must be made isn't
+    NotMade(String),
+    /// The solution includes a forbidden combination of ingredients
+    Forbidden(Vec<Ingredient>),
+    /// Too few ingredients from a category
+    CategoryShortfall {
+        category: String,
+        minimum: usize,
+        found: usize,
+    },
+    /// Too many ingredients from a category
+    CategoryExcess {
+        category: String,
+        maximum: usize,
+        found: usize,
+    },
+    /// The solution's ingredients cost more than the spend budget
+    OverSpend { cost: f64, budget: f64 },
+    /// The solution includes an excluded ingredient
+    Excluded(Ingredient),
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Violation::UnknownIngredient(name) => write!(f, "unknown ingredient {name:?}"),
+            Violation::UnknownCocktail(name) => write!(f, "unknown cocktail {name:?}"),
+            Violation::OverBudget {
+                ingredients,
+                max_size,
+            } => write!(
+                f,
+                "{ingredients} ingredients used, but only {max_size} are allowed"
+            ),
+            Violation::NotMakeable { cocktail, missing } => {
+                write!(f, "{cocktail} can't be made without {missing:?}")
+            }
+            Violation::MissingRequired(name) => {
+                write!(f, "required ingredient {name:?} is missing")
+            }
+            Violation::NotMade(name) => write!(f, "{name} must be made, but isn't"),
+            Violation::Forbidden(names) => {
+                write!(f, "{names:?} are included together, but are forbidden")
+            }
+            Violation::CategoryShortfall {
+                category,
+                minimum,
+                found,
+            } => write!(
+                f,
+                "at least {minimum} {category} required, but only {found} included"
+            ),
+            Violation::CategoryExcess {
+                category,
+                maximum,
+                found,
+            } => write!(
+                f,
+                "at most {maximum} {category} allowed, but {found} included"
+            ),
+            Violation::Excluded(name) => write!(f, "excluded ingredient {name:?} is included"),
+            Violation::OverSpend { cost, budget } => {
+                write!(
+                    f,
+                    "the ingredients cost {cost:.2}, over the budget of {budget:.2}"
+                )
+            }
+        }
+    }
+}
+
+impl Problem {
+    /// An empty problem allowing `max_size` ingredients
+    #[must_use]
+    pub fn new(max_size: usize) -> Problem {
+        Problem {
+            max_size,
+            ..Default::default()
+        }
+    }
+
+    /// Add a cocktail, returning it so that its tags, weight and notes can be set.
+    /// Duplicate ingredients are ignored
+    pub fn add_cocktail<'a>(
+        &mut self,
+        name: &str,
+        ingredients: impl IntoIterator<Item = &'a str>,
+    ) -> &mut Cocktail {
+        let set = self.mapper.encode(ingredients);
+        self.cocktails.push(Cocktail::new(name, set));
+        self.cocktails.last_mut().unwrap()
+    }
+
+    /// Add the cocktails in a headerless CSV file with one cocktail per row: its name,
+    /// followed by its ingredients. Returns the number of cocktails added
+    ///
+    /// A cell starting with `recipe:` isn't an ingredient, but the cocktail's
+    /// [recipe](Cocktail::recipe), e.g. `"recipe: Shake with ice, and strain"`.
+    ///
+    /// Every cocktail is recorded as coming from `source`. Files can be merged by reading
+    /// them into the same problem: an ingredient matching an existing one apart from case
+    /// and spacing takes the existing spelling, and a cocktail matching an existing one
+    /// in the same way, with the same ingredients, only gains another source. If a file
+    /// lists the same ingredients more than once, the last name wins.
+    pub fn read_csv<R: Read>(
+        &mut self,
+        reader: R,
+        source: &str,
+        parser: Option<&QuantityParser>,
+    ) -> Result<usize, Error> {
+        let spellings = self
+            .mapper
+            .iter()
+            .map(|(_, name)| (normalize(name), name.clone()))
+            .collect::<FxHashMap<String, Ingredient>>();
+        let mut recipes: FxHashMap<IngredientSet, (String, Option<String>)> = FxHashMap::default();
+        for (row, record) in ReaderBuilder::new()
+            .flexible(true)
+            .has_headers(false)
+            .from_reader(reader)
+            .into_records()
+            .enumerate()
+        {
+            let record = record?;
+            let name = record.get(0).unwrap_or_default().trim();
+            if name.is_empty() {
+                return Err(Error::Malformed {
+                    row: row + 1,
+                    reason: "the cocktail has no name".to_owned(),
+                });
+            }
+            let mut recipe = None;
+            let ingredients = record
+                .iter()
+                .skip(1)
+                .filter(|cell| match cell.strip_prefix(RECIPE) {
+                    Some(text) => {
+                        recipe = Some(text.trim().to_owned());
+                        false
+                    }
+                    None => true,
+                })
+                .map(|cell| {
+                    let name = match parser {
+                        Some(parser) => parser.parse(cell).name,
+                        None => cell.to_owned(),
+                    };
+                    spellings.get(&normalize(&name)).cloned().unwrap_or(name)
+                })
+                .collect::<IngredientSet>();
+            recipes.insert(ingredients, (name.to_owned(), recipe));
+        }
+        // ids are assigned in the map's order, which is the order the solver has always
+        // seen the bundled dataset in, and so keeps its tie-breaking unchanged
+        let mut added = 0;
+        for (ingredients, (name, recipe)) in recipes {
+            let set = self.mapper.encode(ingredients.iter().map(String::as_str));
+            let key = normalize(&name);
+            match self
+                .cocktails
+                .iter_mut()
+                .find(|cocktail| cocktail.ingredients == set && normalize(&cocktail.name) == key)
+            {
+                Some(existing) => {
+                    existing.sources.insert(source.to_owned());
+                    if existing.recipe.is_none() {
+                        existing.recipe = recipe;
+                    }
+                }
+                None => {
+                    let mut cocktail = Cocktail::new(&name, set);
+                    cocktail.sources.insert(source.to_owned());
+                    cocktail.recipe = recipe;
+                    self.cocktails.push(cocktail);
+                    added += 1;
+                }
+            }
+        }
+        Ok(added)
+    }
+
+    /// Add the cocktails in the CSV file at `path`, recording the path as their source.
+    /// See [`read_csv`](Self::read_csv)
+    pub fn read_path(
+        &mut self,
+        path: impl AsRef<Path>,
+        parser: Option<&QuantityParser>,
+    ) -> Result<usize, Error> {
+        let path = path.as_ref();
+        let f = File::open(path).map_err(|source| Error::Io {
+            path: path.to_owned(),
+            source,
+        })?;
+        self.read_csv(BufReader::new(f), &path.to_string_lossy(), parser)
+    }
+
+    /// Require `ingredient` in every solution
+    pub fn require_ingredient(&mut self, ingredient: &str) {
+        let id = self.mapper.intern(ingredient);
+        self.required.insert(id);
+    }
+
+    /// Set the price of `ingredient`, for use with a [spend budget](Self::spend_budget).
+    /// Returns the name as the error if no cocktail uses it
+    pub fn set_price<'a>(&mut self, ingredient: &'a str, price: f64) -> Result<(), &'a str> {
+        let id = self.mapper.id(ingredient).ok_or(ingredient)?;
+        self.prices.insert(id, price);
+        Ok(())
+    }
+
+    /// Only accept ingredient sets costing at most `budget` in all, as well as
+    /// fitting in `max_size`. Unpriced ingredients are free
+    pub fn spend_budget(&mut self, budget: f64) {
+        self.budget = Some(budget);
+    }
+
+    /// The total price of `ingredients`, other than those already owned
+    #[must_use]
+    pub fn cost<'a>(&self, ingredients: impl IntoIterator<Item = &'a Ingredienti>) -> f64 {
+        ingredients
+            .into_iter()
+            .filter(|ingredient| !self.owned.contains(ingredient))
+            .filter_map(|ingredient| self.prices.get(ingredient))
+            .sum()
+    }
+
+    /// Include the named ingredients in every solution, as already owned: they're free,
+    /// and don't count against `max_size`
+    pub fn with_owned_ingredients(&mut self, names: &[&str]) {
+        for name in names {
+            let id = self.mapper.intern(name);
+            self.owned.insert(id);
+        }
+    }
+
+    #[must_use]
+    pub fn owned(&self) -> &IngredientSeti {
+        &self.owned
+    }
+
+    #[must_use]
+    pub fn required(&self) -> &IngredientSeti {
+        &self.required
+    }
+
+    #[must_use]
+    pub fn excluded(&self) -> &IngredientSeti {
+        &self.excluded
+    }
+
+    /// Never include `ingredient`, dropping every cocktail that needs it. Returns the
+    /// name as the error if no cocktail uses it
+    pub fn exclude_ingredient<'a>(&mut self, ingredient: &'a str) -> Result<(), &'a str> {
+        let id = self.mapper.id(ingredient).ok_or(ingredient)?;
+        self.excluded.insert(id);
+        Ok(())
+    }
+
+    /// Require at least `minimum` of the given ingredients, which make up `category`
+    pub fn category_minimum<'a>(
+        &mut self,
+        category: &str,
+        ingredients: impl IntoIterator<Item = &'a str>,
+        minimum: usize,
+    ) {
+        let ingredients = self.mapper.encode(ingredients);
+        self.category_minimums.push(CategoryMinimum {
+            category: category.to_owned(),
+            ingredients,
+            minimum,
+        });
+    }
+
+    /// Allow at most `maximum` of the given ingredients, which make up `category`
+    pub fn category_maximum<'a>(
+        &mut self,
+        category: &str,
+        ingredients: impl IntoIterator<Item = &'a str>,
+        maximum: usize,
+    ) {
+        let ingredients = self.mapper.encode(ingredients);
+        self.category_maximums.push(CategoryMaximum {
+            category: category.to_owned(),
+            ingredients,
+            maximum,
+        });
+    }
+
+    /// Prefer at most `limit` of the given ingredients, which make up `category`. Each
+    /// one over costs `penalty` cocktails
+    pub fn soft_limit<'a>(
+        &mut self,
+        category: &str,
+        ingredients: impl IntoIterator<Item = &'a str>,
+        limit: usize,
+        penalty: usize,
+    ) {
+        let ingredients = self.mapper.encode(ingredients);
+        self.soft_limits.push(SoftLimit {
+            category: category.to_owned(),
+            ingredients,
+            limit,
+            penalty,
+        });
+    }
+
+    /// Count the named cocktails, which must already have been added, as variants of
+    /// one drink. Returns the first unknown name as the error
+    pub fn variant_group<'a>(
+        &mut self,
+        names: impl IntoIterator<Item = &'a str>,
+    ) -> Result<(), &'a str> {
+        let group = self.named(names)?;
+        self.variants.push(group);
+        Ok(())
+    }
+
+    /// Make as many of the named cocktails, which must already have been added, as
+    /// possible before any others. Returns the first unknown name as the error
+    pub fn house_cocktails<'a>(
+        &mut self,
+        names: impl IntoIterator<Item = &'a str>,
+    ) -> Result<(), &'a str> {
+        let house = self.named(names)?;
+        self.house.extend(house);
+        Ok(())
+    }
+
+    /// Set the weight of every cocktail called `name`, which must already have been
+    /// added. Returns the name as the error if it's unknown
+    pub fn set_weight<'a>(&mut self, name: &'a str, weight: f64) -> Result<(), &'a str> {
+        let mut found = false;
+        for cocktail in self.cocktails.iter_mut().filter(|c| c.name == name) {
+            cocktail.weight = weight;
+            found = true;
+        }
+        found.then_some(()).ok_or(name)
+    }
+
+    /// Never include all of the named ingredients together. Returns the first name no
+    /// cocktail uses as the error, since a combination including it can't occur anyway
+    pub fn forbid_combination<'a>(&mut self, names: &[&'a str]) -> Result<(), &'a str> {
+        let combination = names
+            .iter()
+            .map(|name| self.mapper.id(name).ok_or(*name))
+            .collect::<Result<IngredientSeti, &str>>()?;
+        self.forbidden.push(combination);
+        Ok(())
+    }
+
+    /// Only accept solutions making every one of the named cocktails, which must
+    /// already have been added, by requiring their ingredients. Returns the first
+    /// unknown name as the error
+    pub fn must_make<'a>(
+        &mut self,
+        names: impl IntoIterator<Item = &'a str>,
+    ) -> Result<(), &'a str> {
+        let cocktails = self.named(names)?;
+        self.must_make.extend(cocktails);
+        Ok(())
+    }
+
+    /// Every cocktail with one of `names`
+    fn named<'a>(
+        &self,
+        names: impl IntoIterator<Item = &'a str>,
+    ) -> Result<Vec<IngredientSeti>, &'a str> {
+        let mut cocktails = vec![];
+        for name in names {
+            let before = cocktails.len();
+            cocktails.extend(
+                self.cocktails
+                    .iter()
+                    .filter(|cocktail| cocktail.name == name)
+                    .map(|cocktail| cocktail.ingredients.clone()),
+            );
+            if cocktails.len() == before {
+                return Err(name);
+            }
+        }
+        Ok(cocktails)
+    }
+
+    #[must_use]
+    pub fn mapper(&self) -> &IdMapper {
+        &self.mapper
+    }
+
+    /// Every cocktail, in the order they were added
+    #[must_use]
+    pub fn cocktails(&self) -> &[Cocktail] {
+        &self.cocktails
+    }
+
+    /// The first cocktail with exactly these ingredients
+    #[must_use]
+    pub fn cocktail(&self, ingredients: &IngredientSeti) -> Option<&Cocktail> {
+        self.cocktails
+            .iter()
+            .find(|cocktail| &cocktail.ingredients == ingredients)
+    }
+
+    /// The first cocktail called `name`, ignoring case and spacing
+    #[must_use]
+    pub fn find(&self, name: &str) -> Option<&Cocktail> {
+        let name = normalize(name);
+        self.cocktails
+            .iter()
+            .find(|cocktail| normalize(&cocktail.name) == name)
+    }
+
+    /// The distinct encoded cocktails, ready to search
+    #[must_use]
+    pub fn cocktail_sets(&self) -> FxHashSet<IngredientSeti> {
+        self.cocktails
+            .iter()
+            .map(|cocktail| cocktail.ingredients.clone())
+            .collect()
+    }
+
+    /// The names of cocktails sharing exactly the same ingredients, which the search
+    /// treats as one candidate, each group sorted
+    #[must_use]
+    pub fn duplicates(&self) -> Vec<Vec<&str>> {
+        let mut groups = BTreeMap::<&IngredientSeti, Vec<&str>>::new();
+        for cocktail in &self.cocktails {
+            groups
+                .entry(&cocktail.ingredients)
+                .or_default()
+                .push(cocktail.name.as_str());
+        }
+        let mut duplicates = groups
+            .into_values()
+            .filter(|names| names.len() > 1)
+            .map(|mut names| {
+                names.sort_unstable();
+                names
+            })
+            .collect::<Vec<Vec<&str>>>();
+        duplicates.sort_unstable();
+        duplicates
+    }
+
+    /// The cocktails `bb` can never make, by name, and why. See
+    /// [`preprocess`](crate::preprocess)
+    #[must_use]
+    pub fn unmakeable(&self, bb: &BranchBound) -> Vec<(&str, Elimination)> {
+        self.cocktails
+            .iter()
+            .filter_map(|cocktail| {
+                bb.unmakeable(&cocktail.ingredients)
+                    .map(|reason| (cocktail.name.as_str(), reason))
+            })
+            .collect()
+    }
+
+    /// How hard each cocktail is to justify, hardest (highest amortized cost) first
+    #[must_use]
+    pub fn difficulty(&self) -> Vec<(&str, Difficulty)> {
+        // the solver sees each distinct ingredient set once, so score them the same way
+        let sets = self.cocktail_sets();
+        let difficulty = analysis::difficulty(sets.iter());
+        let mut scored = self
+            .cocktails
+            .iter()
+            .map(|cocktail| (cocktail.name.as_str(), difficulty[&cocktail.ingredients]))
+            .collect::<Vec<(&str, Difficulty)>>();
+        scored.sort_unstable_by(|a, b| {
+            b.1.amortized_cost
+                .total_cmp(&a.1.amortized_cost)
+                .then(a.0.cmp(b.0))
+        });
+        scored
+    }
+
+    /// A search builder configured with this problem's budget, constraints and cocktail weights
+    #[must_use]
+    pub fn builder(&self, max_calls: i32) -> BranchBoundBuilder {
+        let mut builder = BranchBoundBuilder::new(max_calls, self.max_size)
+            .required_ingredients(self.required.clone())
+            .owned_ingredients(self.owned.iter().copied())
+            .excluded_ingredients(self.excluded.iter().copied())
+            .must_make(self.must_make.iter().cloned());
+        for combination in &self.forbidden {
+            builder = builder.add_forbidden(combination.clone());
+        }
+        for minimum in &self.category_minimums {
+            builder = builder.category_minimum(minimum.clone());
+        }
+        for maximum in &self.category_maximums {
+            builder = builder.category_maximum(maximum.clone());
+        }
+        for group in &self.variants {
+            builder = builder.variant_group(group.iter().cloned());
+        }
+        // only weigh cocktails if some weight differs, to keep the unweighted search fast
+        let weighted = self.cocktails.iter().any(|cocktail| cocktail.weight != 1.0);
+        if weighted {
+            let mut weights = FxHashMap::default();
+            for cocktail in &self.cocktails {
+                *weights.entry(cocktail.ingredients.clone()).or_insert(0) +=
+                    (cocktail.weight.max(0.0) * WEIGHT_SCALE).round() as usize;
+            }
+            builder = builder.cocktail_weights(weights);
+        }
+        for limit in &self.soft_limits {
+            let mut limit = limit.clone();
+            // penalties are in cocktails, which weighted scores count in thousandths
+            if weighted {
+                limit.penalty *= WEIGHT_SCALE as usize;
+            }
+            builder = builder.soft_limit(limit);
+        }
+        builder = builder.prices(self.prices.clone());
+        if let Some(budget) = self.budget {
+            builder = builder.spend_budget(budget, []);
+        }
+        builder.house_cocktails(self.house.iter().cloned())
+    }
+
+    /// Start `builder`'s search from the ingredients `names`, once they've been
+    /// [validated](Self::validate) as a solution to this problem. See
+    /// [`BranchBoundBuilder::with_initial_solution`]
+    pub fn with_initial_solution(
+        &self,
+        builder: BranchBoundBuilder,
+        names: &[&str],
+    ) -> Result<BranchBoundBuilder, Vec<Violation>> {
+        let ingredients = names
+            .iter()
+            .filter_map(|name| self.mapper.id(name))
+            .collect::<IngredientSeti>();
+        let cocktails = self
+            .cocktail_sets()
+            .into_iter()
+            .filter(|cocktail| cocktail.is_subset(&ingredients))
+            .collect();
+        let mut solution = self.solution(&cocktails, &ingredients);
+        // keep unknown names, so that they're reported
+        solution.ingredients = names.iter().map(|&name| name.to_owned()).collect();
+        self.validate(&solution)?;
+        let ingredients = ingredients.into_iter().collect::<Vec<Ingredienti>>();
+        Ok(builder.with_initial_solution(&ingredients))
+    }
+
+    /// Find the best solution, giving up after `max_calls` search rounds
+    pub fn solve(&self, max_calls: i32) -> Result<Solution, Error> {
+        let mut bb = self.builder(max_calls).try_build()?;
+        self.search(&mut bb)
+    }
+
+    /// Find the best solution for each number of ingredients in `sizes`, giving each
+    /// search up after `max_calls` rounds. See [`BranchBound::sweep`]
+    pub fn sweep(
+        &self,
+        sizes: RangeInclusive<usize>,
+        max_calls: i32,
+        warm_start: bool,
+    ) -> Result<Vec<(usize, Solution)>, Error> {
+        // the budget is checked against the largest size; smaller ones just find less
+        let widest = Problem {
+            max_size: *sizes.end(),
+            ..self.clone()
+        };
+        let mut bb = widest.builder(max_calls).try_build()?;
+        Ok(self.name_sweep(bb.sweep(&self.cocktail_sets(), sizes, warm_start)))
+    }
+
+    /// Find the Pareto frontier of ingredient sets of each size in `sizes`, trading
+    /// ingredients against cocktails and cost, giving each search up after `max_calls`
+    /// rounds, and name each set. See [`BranchBound::pareto`]
+    pub fn pareto(
+        &self,
+        sizes: RangeInclusive<usize>,
+        max_calls: i32,
+    ) -> Result<Vec<(ParetoPoint, Solution)>, Error> {
+        let widest = Problem {
+            max_size: *sizes.end(),
+            ..self.clone()
+        };
+        let mut bb = widest.builder(max_calls).try_build()?;
+        Ok(self.name_pareto(bb.pareto(&self.cocktail_sets(), sizes)))
+    }
+
+    /// Name the sets on a [`BranchBound::pareto`] frontier
+    #[must_use]
+    pub fn name_pareto(&self, points: Vec<ParetoPoint>) -> Vec<(ParetoPoint, Solution)> {
+        points
+            .into_iter()
+            .map(|point| {
+                let solution = self.solution(&point.best.cocktails, &point.best.ingredients);
+                (point, solution)
+            })
+            .collect()
+    }
+
+    /// Find the fewest ingredients making at least `target` cocktails, or cocktails
+    /// weighing at least `target` in all if any are weighted, and name them. `None` if no
+    /// ingredient set can. `bb` must have been configured from [`builder`](Self::builder),
+    /// though its ingredient budget is ignored. See [`BranchBound::min_ingredients_for`]
+    #[must_use]
+    pub fn min_ingredients_for(&self, bb: &mut BranchBound, target: usize) -> Option<Solution> {
+        let target = if bb.weights.is_empty() {
+            target
+        } else {
+            (target as f64 * WEIGHT_SCALE) as usize
+        };
+        bb.min_ingredients_for(&self.cocktail_sets(), target)
+            .map(|best| self.solution(&best.cocktails, &best.ingredients))
+    }
+
+    /// Name the results of a [`BranchBound::sweep`]
+    #[must_use]
+    pub fn name_sweep(&self, results: Vec<(usize, Alternative)>) -> Vec<(usize, Solution)> {
+        results
+            .into_iter()
+            .map(|(size, best)| (size, self.solution(&best.cocktails, &best.ingredients)))
+            .collect()
+    }
+
+    /// Write the problem to `path` as an [integer program](crate::ilp) in CPLEX LP
+    /// format, for another solver to read. Comments at the top name what each
+    /// ingredient and cocktail variable stands for. Fails if the problem uses variant
+    /// groups or soft limits
+    pub fn export_lp(&self, path: impl AsRef<Path>) -> Result<(), Error> {
+        self.export(path.as_ref(), |program, writer, comments| {
+            program.write_lp(writer, comments)
+        })
+    }
+
+    /// Write the problem to `path` as an [integer program](crate::ilp) in free MPS
+    /// format. See [`export_lp`](Self::export_lp)
+    pub fn export_mps(&self, path: impl AsRef<Path>) -> Result<(), Error> {
+        self.export(path.as_ref(), |program, writer, comments| {
+            program.write_mps(writer, comments)
+        })
+    }
+
+    fn export(
+        &self,
+        path: &Path,
+        write: impl Fn(&IntegerProgram, &mut BufWriter<File>, &[String]) -> std::io::Result<()>,
+    ) -> Result<(), Error> {
+        let mut bb = self.builder(1).try_build()?;
+        let program = bb.integer_program(&self.cocktail_sets())?;
+        let comments = program
+            .variables
+            .iter()
+            .enumerate()
+            .filter_map(|(index, variable)| {
+                let meaning = if let Some(ingredient) = program.ingredient(index) {
+                    format!("buy {}", self.mapper.name(ingredient)?)
+                } else {
+                    let cocktail = program.cocktail(index)?;
+                    let names = self
+                        .cocktails
+                        .iter()
+                        .filter(|candidate| candidate.ingredients == *cocktail)
+                        .map(|candidate| candidate.name.as_str())
+                        .collect::<Vec<&str>>();
+                    format!("make {}", names.join(" / "))
+                };
+                Some(format!("{}: {meaning}", variable.name))
+            })
+            .collect::<Vec<String>>();
+        let io_error = |source| Error::Io {
+            path: path.to_owned(),
+            source,
+        };
+        let mut writer = BufWriter::new(File::create(path).map_err(io_error)?);
+        write(&program, &mut writer, &comments).map_err(io_error)?;
+        writer.flush().map_err(io_error)
+    }
+
+    /// Run a search configured from [`builder`](Self::builder), and name its result.
+    /// `bb` is left holding the search's statistics. Fails if `bb` has already searched
+    /// a different problem
+    pub fn search(&self, bb: &mut BranchBound) -> Result<Solution, Error> {
+        let best = bb.try_search(
+            &mut self.cocktail_sets(),
+            &mut FxHashSet::default(),
+            &mut None,
+        )?;
+        Ok(self.result(bb, &best))
+    }
+
+    /// Carry on a search [loaded from a checkpoint](BranchBound::resume_from), and name
+    /// its result. Fails if the checkpoint was saved searching a different problem
+    pub fn resume(&self, bb: &mut BranchBound) -> Result<Solution, Error> {
+        let cocktails = self.cocktail_sets();
+        if let Some(unknown) = bb
+            .min_cover
+            .keys()
+            .find(|cocktail| !cocktails.contains(*cocktail))
+        {
+            return Err(Error::Inconsistent(format!(
+                "the checkpoint has a cocktail of {}, which isn't in this problem",
+                self.mapper.display(unknown)
+            )));
+        }
+        let best = bb.resume();
+        Ok(self.result(bb, &best))
+    }
+
+    /// Settle for a [greedy](BranchBound::greedy) solution rather than searching, and
+    /// name it. Its [gap](Solution::gap) says how far from optimal it may be
+    #[must_use]
+    pub fn greedy(&self, bb: &mut BranchBound) -> Solution {
+        let best = bb.greedy_search(&self.cocktail_sets());
+        self.result(bb, &best)
+    }
+
+    /// Name the best cocktails found by `bb`, and its other results
+    pub(crate) fn result(&self, bb: &BranchBound, best: &FxHashSet<IngredientSeti>) -> Solution {
+        let mut solution = self.solution(best, &bb.highest_ingredients);
+        solution.runners_up = bb
+            .alternatives
+            .iter()
+            .filter(|alternative| alternative.ingredients != bb.highest_ingredients)
+            .take(bb.top_k.saturating_sub(1))
+            .map(|alternative| self.solution(&alternative.cocktails, &alternative.ingredients))
+            .collect();
+        let mut ties = bb
+            .ties
+            .iter()
+            .filter(|tie| {
+                tie.score == bb.highest_score && tie.ingredients != bb.highest_ingredients
+            })
+            .collect::<Vec<&Alternative>>();
+        ties.sort_by_key(|tie| (tie.ingredients.len(), &tie.ingredients));
+        solution.ties = ties
+            .into_iter()
+            .map(|tie| self.solution(&tie.cocktails, &tie.ingredients))
+            .collect();
+        solution.gap = bb.gap();
+        solution.stats = Some(bb.stats.clone());
+        solution
+    }
+
+    /// Name the cocktails made by, and the ingredients in, a search result, adding any
+    /// ingredients needed to meet the [category minimums](Self::category_minimum)
+    #[must_use]
+    pub fn solution(
+        &self,
+        cocktails: &FxHashSet<IngredientSeti>,
+        ingredients: &IngredientSeti,
+    ) -> Solution {
+        let mut names = self
+            .cocktails
+            .iter()
+            .filter(|cocktail| cocktails.contains(&cocktail.ingredients))
+            .map(|cocktail| cocktail.name.clone())
+            .collect::<Vec<String>>();
+        names.sort_unstable();
+        let sources = self
+            .cocktails
+            .iter()
+            .filter(|cocktail| {
+                cocktails.contains(&cocktail.ingredients) && !cocktail.sources.is_empty()
+            })
+            .map(|cocktail| {
+                (
+                    cocktail.name.clone(),
+                    cocktail.sources.iter().cloned().collect(),
+                )
+            })
+            .collect::<BTreeMap<String, Vec<String>>>();
+        // what's bought only to meet the category minimums is on the list too
+        let mut ingredients = ingredients.clone();
+        for minimum in &self.category_minimums {
+            ingredients.extend(minimum.padding(&ingredients).collect::<Vec<Ingredienti>>());
+        }
+        Solution {
+            ingredients: self
+                .mapper
+                .decode(&ingredients)
+                .into_iter()
+                .cloned()
+                .collect(),
+            cocktails: names,
+            sources,
+            runners_up: vec![],
+            ties: vec![],
+            gap: None,
+            stats: None,
+        }
+    }
+
+    /// Check that `solution` respects the budget and constraints, and that every cocktail
+    /// it lists can really be made from its ingredients
+    ///
+    /// This doesn't check that the solution is optimal, or that it lists every cocktail
+    /// its ingredients make.
+    pub fn validate(&self, solution: &Solution) -> Result<(), Vec<Violation>> {
+        let mut violations = vec![];
+        let mut ingredients = IngredientSeti::new();
+        for name in &solution.ingredients {
+            match self.mapper.id(name) {
+                Some(id) => {
+                    ingredients.insert(id);
+                }
+                None => violations.push(Violation::UnknownIngredient(name.clone())),
+            }
+        }
+        // unknown ingredients still take up space, but owned ones don't
+        let used = ingredients.difference(&self.owned).count()
+            + violations
+                .iter()
+                .filter(|v| matches!(v, Violation::UnknownIngredient(_)))
+                .count();
+        if used > self.max_size {
+            violations.push(Violation::OverBudget {
+                ingredients: used,
+                max_size: self.max_size,
+            });
+        }
+        for name in &solution.cocktails {
+            let matching = self
+                .cocktails
+                .iter()
+                .filter(|cocktail| &cocktail.name == name)
+                .collect::<Vec<&Cocktail>>();
+            if matching.is_empty() {
+                violations.push(Violation::UnknownCocktail(name.clone()));
+            } else if !matching
+                .iter()
+                .any(|cocktail| cocktail.makeable_from(&ingredients))
+            {
+                // if several cocktails share a name, report what the first one lacks
+                let missing = self
+                    .mapper
+                    .decode(&(&matching[0].ingredients - &ingredients));
+                violations.push(Violation::NotMakeable {
+                    cocktail: name.clone(),
+                    missing: missing.into_iter().cloned().collect(),
+                });
+            }
+        }
+        for name in self.mapper.decode(&(&self.required - &ingredients)) {
+            violations.push(Violation::MissingRequired(name.clone()));
+        }
+        for combination in &self.forbidden {
+            if combination.is_subset(&ingredients) {
+                let names = self
+                    .mapper
+                    .decode(combination)
+                    .into_iter()
+                    .cloned()
+                    .collect();
+                violations.push(Violation::Forbidden(names));
+            }
+        }
+        for cocktail in &self.must_make {
+            if !cocktail.is_subset(&ingredients) {
+                let name = self.cocktail(cocktail).map_or("?", |c| c.name.as_str());
+                violations.push(Violation::NotMade(name.to_owned()));
+            }
+        }
+        for minimum in &self.category_minimums {
+            if minimum.deficit(&ingredients) > 0 {
+                violations.push(Violation::CategoryShortfall {
+                    category: minimum.category.clone(),
+                    minimum: minimum.minimum,
+                    found: minimum.ingredients.intersection(&ingredients).count(),
+                });
+            }
+        }
+        for maximum in &self.category_maximums {
+            if maximum.excess(&ingredients) > 0 {
+                violations.push(Violation::CategoryExcess {
+                    category: maximum.category.clone(),
+                    maximum: maximum.maximum,
+                    found: maximum.count(&ingredients),
+                });
+            }
+        }
+        for name in self.mapper.decode(&(&ingredients & &self.excluded)) {
+            violations.push(Violation::Excluded(name.clone()));
+        }
+        if let Some(budget) = self.budget {
+            let cost = self.cost(&ingredients);
+            if cost > budget + COST_TOLERANCE {
+                violations.push(Violation::OverSpend { cost, budget });
+            }
+        }
+        if violations.is_empty() {
+            Ok(())
+        } else {
+            Err(violations)
+        }
+    }
+
+    /// Generate a random problem, for testing solvers
+    ///
+    /// Draws `cocktails` cocktails from a pool of `ingredients` ingredients (named
+    /// `i0`, `i1`, …), each using a number of distinct ingredients in `sizes`.
+    /// Cocktails are named `c0`, `c1`, …, and duplicate ingredient sets are possible.
+    pub fn random<R: Rng>(
+        rng: &mut R,
+        ingredients: usize,
+        cocktails: usize,
+        sizes: RangeInclusive<usize>,
+        max_size: usize,
+    ) -> Problem {
+        let pool = (0..ingredients)
+            .map(|i| format!("i{i}"))
+            .collect::<Vec<String>>();
+        let mut problem = Problem::new(max_size);
+        for c in 0..cocktails {
+            let size = rng.gen_range(sizes.clone()).min(ingredients);
+            let chosen = pool.choose_multiple(rng, size).map(String::as_str);
+            problem.add_cocktail(&format!("c{c}"), chosen);
+        }
+        problem
+    }
+}
+
+/// Lowercase, with runs of whitespace collapsed to a single space
+fn normalize(name: &str) -> String {
+    name.split_whitespace()
+        .collect::<Vec<&str>>()
+        .join(" ")
+        .to_lowercase()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn merges_files_recording_each_source() {
+        let mut problem = Problem::new(4);
+        let first = "Negroni,Gin,Campari,Sweet Vermouth\nGimlet,Gin,Lime\n";
+        let second = "NEGRONI,gin,campari,sweet  vermouth\nDaiquiri,rum,lime,sugar\n";
+        assert_eq!(
+            problem.read_csv(first.as_bytes(), "first", None).unwrap(),
+            2
+        );
+        assert_eq!(
+            problem.read_csv(second.as_bytes(), "second", None).unwrap(),
+            1
+        );
+        assert_eq!(problem.cocktails().len(), 3);
+        // the second file's spellings give way to the first's
+        assert_eq!(problem.mapper().len(), 6);
+        let sources = |name| {
+            problem
+                .find(name)
+                .unwrap()
+                .sources
+                .iter()
+                .cloned()
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(sources("Negroni"), ["first", "second"]);
+        assert_eq!(sources("Gimlet"), ["first"]);
+        assert_eq!(sources("Daiquiri"), ["second"]);
+    }
+}
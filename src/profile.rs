@@ -0,0 +1,108 @@
+//! Recording where a search spends its time
+//!
+//! A [`Profile`] accumulates the time spent in each phase of each search node, keyed by
+//! the node's depth and the branch that led to it, and writes it out as "folded stacks":
+//! one `frame;frame;… count` line per stack, as consumed by
+//! [inferno](https://github.com/jonhoo/inferno) or `flamegraph.pl`. Each depth is a
+//! frame, so the width of `depth 3` in the resulting flame graph is the total time spent
+//! three levels below the initial call.
+use rustc_hash::FxHashMap;
+use serde::{Deserialize, Serialize};
+use std::io::{self, Write};
+use std::time::Duration;
+
+/// Which branch of its parent a search node is on
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum Branch {
+    /// The initial call
+    Root,
+    /// The branch that adds the chosen cocktail
+    Include,
+    /// The branch that forbids the chosen cocktail
+    Exclude,
+}
+
+/// The work done at a single search node
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Phase {
+    /// Scoring the partial solution and updating the incumbent
+    Prepare,
+    /// Evaluating the bound functions
+    Bounds,
+    /// Choosing a cocktail and building the candidate sets of both branches
+    Branch,
+}
+
+impl Branch {
+    fn name(self) -> &'static str {
+        match self {
+            Branch::Root => "root",
+            Branch::Include => "include",
+            Branch::Exclude => "exclude",
+        }
+    }
+}
+
+impl Phase {
+    fn name(self) -> &'static str {
+        match self {
+            Phase::Prepare => "prepare",
+            Phase::Bounds => "bounds",
+            Phase::Branch => "branch",
+        }
+    }
+}
+
+/// Time spent per (depth, branch, phase), excluding time spent in child nodes
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Profile {
+    samples: FxHashMap<(usize, Branch, Phase), Duration>,
+}
+
+impl Profile {
+    #[must_use]
+    pub fn new() -> Profile {
+        Profile::default()
+    }
+
+    pub fn record(&mut self, depth: usize, branch: Branch, phase: Phase, elapsed: Duration) {
+        *self.samples.entry((depth, branch, phase)).or_default() += elapsed;
+    }
+
+    /// The time spent in `phase` at `depth`, across both branches
+    #[must_use]
+    pub fn time(&self, depth: usize, phase: Phase) -> Duration {
+        self.samples
+            .iter()
+            .filter(|((d, _, p), _)| *d == depth && *p == phase)
+            .map(|(_, elapsed)| *elapsed)
+            .sum()
+    }
+
+    /// The total time recorded
+    #[must_use]
+    pub fn total(&self) -> Duration {
+        self.samples.values().sum()
+    }
+
+    /// Write folded stacks such as `search;depth 0;depth 1;include;bounds 1234`,
+    /// where the count is in nanoseconds
+    pub fn write_folded<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        let mut samples = self.samples.iter().collect::<Vec<_>>();
+        samples.sort_unstable_by_key(|(key, _)| **key);
+        for ((depth, branch, phase), elapsed) in samples {
+            let mut stack = String::from("search");
+            for d in 0..=*depth {
+                stack.push_str(&format!(";depth {d}"));
+            }
+            writeln!(
+                writer,
+                "{stack};{};{} {}",
+                branch.name(),
+                phase.name(),
+                elapsed.as_nanos()
+            )?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,267 @@
+//! Stripping quantities and units from ingredient cells
+//!
+//! Many recipe files list ingredients as e.g. `2 oz Gin` or `45 ml lime juice`. Treating
+//! those cells as ingredient names would make every measure a distinct ingredient, so this
+//! recovers the bare name, keeping the parsed amount in case it's needed later.
+
+/// Units recognised by [`QuantityParser::default`]. Matching is case-insensitive, and a
+/// trailing `.` (as in `tsp.`) is ignored
+pub const DEFAULT_UNITS: &[&str] = &[
+    "oz",
+    "fl oz",
+    "ounce",
+    "ounces",
+    "ml",
+    "cl",
+    "dl",
+    "l",
+    "litre",
+    "liter",
+    "tsp",
+    "tbsp",
+    "teaspoon",
+    "teaspoons",
+    "tablespoon",
+    "tablespoons",
+    "barspoon",
+    "barspoons",
+    "cup",
+    "cups",
+    "part",
+    "parts",
+    "shot",
+    "shots",
+    "jigger",
+    "jiggers",
+    "dash",
+    "dashes",
+    "drop",
+    "drops",
+    "splash",
+    "splashes",
+    "pinch",
+    "pinches",
+    "slice",
+    "slices",
+    "wedge",
+    "wedges",
+    "sprig",
+    "sprigs",
+    "twist",
+    "twists",
+    "piece",
+    "pieces",
+];
+
+/// An amount parsed from the start of an ingredient cell
+#[derive(Debug, Clone, PartialEq)]
+pub struct Quantity {
+    pub amount: f64,
+    /// The unit as it appeared in the cell (lowercased), or `None` for bare counts like `2 eggs`
+    pub unit: Option<String>,
+}
+
+/// The result of parsing an ingredient cell
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParsedIngredient {
+    pub name: String,
+    pub quantity: Option<Quantity>,
+}
+
+/// Splits ingredient cells into a quantity and a bare ingredient name
+#[derive(Debug, Clone)]
+pub struct QuantityParser {
+    /// Lowercased units, longest first so that e.g. `fl oz` wins over `oz`
+    units: Vec<String>,
+}
+
+impl Default for QuantityParser {
+    fn default() -> Self {
+        Self::new(DEFAULT_UNITS.iter().copied())
+    }
+}
+
+impl QuantityParser {
+    #[must_use]
+    pub fn new<'a>(units: impl IntoIterator<Item = &'a str>) -> Self {
+        let mut units = units
+            .into_iter()
+            .map(|unit| unit.trim().to_lowercase())
+            .filter(|unit| !unit.is_empty())
+            .collect::<Vec<String>>();
+        units.sort_unstable_by(|a, b| b.len().cmp(&a.len()).then(a.cmp(b)));
+        units.dedup();
+        QuantityParser { units }
+    }
+
+    /// Parse a cell such as `1 1/2 oz Gin`, `45ml lime juice` or `½ tsp. sugar`
+    ///
+    /// Cells that don't start with a number are returned unchanged, with no quantity.
+    /// A cell consisting only of a quantity is also returned unchanged, since there's no
+    /// name left to recover.
+    #[must_use]
+    pub fn parse(&self, cell: &str) -> ParsedIngredient {
+        let cell = cell.trim();
+        let unparsed = || ParsedIngredient {
+            name: cell.to_owned(),
+            quantity: None,
+        };
+        let Some((amount, rest)) = parse_amount(cell) else {
+            return unparsed();
+        };
+        let rest = rest.trim_start();
+        let (unit, rest) = match self.match_unit(rest) {
+            Some((unit, rest)) => (Some(unit), rest),
+            None => (None, rest),
+        };
+        let rest = rest.trim_start();
+        let name = rest.strip_prefix("of ").unwrap_or(rest).trim().to_owned();
+        if name.is_empty() {
+            return unparsed();
+        }
+        ParsedIngredient {
+            name,
+            quantity: Some(Quantity { amount, unit }),
+        }
+    }
+
+    /// If `text` starts with a known unit followed by a word boundary, split it off
+    fn match_unit<'t>(&self, text: &'t str) -> Option<(String, &'t str)> {
+        let lower = text.to_lowercase();
+        self.units.iter().find_map(|unit| {
+            // lowercasing can change byte lengths outside ASCII, so only match on
+            // prefixes that are still char boundaries in the original text
+            if !lower.starts_with(unit.as_str()) || !text.is_char_boundary(unit.len()) {
+                return None;
+            }
+            let rest = &text[unit.len()..];
+            let rest = rest.strip_prefix('.').unwrap_or(rest);
+            let boundary = rest.chars().next().is_none_or(|c| !c.is_alphanumeric());
+            boundary.then(|| (unit.clone(), rest))
+        })
+    }
+}
+
+/// Value of a single vulgar-fraction character
+fn vulgar_fraction(c: char) -> Option<f64> {
+    Some(match c {
+        '½' => 1.0 / 2.0,
+        '⅓' => 1.0 / 3.0,
+        '⅔' => 2.0 / 3.0,
+        '¼' => 1.0 / 4.0,
+        '¾' => 3.0 / 4.0,
+        '⅛' => 1.0 / 8.0,
+        _ => return None,
+    })
+}
+
+/// Parse a leading amount: an integer or decimal, a fraction (`1/2`), a mixed number
+/// (`1 1/2`, `1½`), or a range (`2-3`, of which the upper end is kept)
+fn parse_amount(text: &str) -> Option<(f64, &str)> {
+    let (mut amount, mut rest) = parse_simple_amount(text)?;
+    // mixed number, e.g. "1 1/2" or "1 ½"
+    if let Some((fraction, after)) = parse_simple_amount(rest.trim_start()) {
+        if fraction < 1.0 && amount.fract() == 0.0 {
+            amount += fraction;
+            rest = after;
+        }
+    }
+    // range, e.g. "2-3 dashes": be generous and keep the upper end
+    if let Some(after) = rest.trim_start().strip_prefix('-') {
+        if let Some((upper, after)) = parse_simple_amount(after.trim_start()) {
+            amount = upper;
+            rest = after;
+        }
+    }
+    Some((amount, rest))
+}
+
+fn parse_simple_amount(text: &str) -> Option<(f64, &str)> {
+    let first = text.chars().next()?;
+    if let Some(value) = vulgar_fraction(first) {
+        return Some((value, &text[first.len_utf8()..]));
+    }
+    let end = text
+        .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+        .unwrap_or(text.len());
+    let number = text[..end].parse::<f64>().ok()?;
+    let rest = &text[end..];
+    if let Some(c) = rest.chars().next() {
+        if let Some(value) = vulgar_fraction(c) {
+            return Some((number + value, &rest[c.len_utf8()..]));
+        }
+    }
+    if let Some(denominator) = rest.strip_prefix('/') {
+        let end = denominator
+            .find(|c: char| !c.is_ascii_digit())
+            .unwrap_or(denominator.len());
+        let value = denominator[..end].parse::<f64>().ok()?;
+        if value == 0.0 {
+            return None;
+        }
+        return Some((number / value, &denominator[end..]));
+    }
+    Some((number, rest))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parsed(cell: &str) -> (String, Option<f64>, Option<String>) {
+        let parsed = QuantityParser::default().parse(cell);
+        let (amount, unit) = parsed.quantity.map_or((None, None), |quantity| {
+            (Some(quantity.amount), quantity.unit)
+        });
+        (parsed.name, amount, unit)
+    }
+
+    #[test]
+    fn strips_quantities_and_units() {
+        let unit = |unit: &str| Some(unit.to_owned());
+        assert_eq!(
+            parsed("2 oz Gin"),
+            ("Gin".to_owned(), Some(2.0), unit("oz"))
+        );
+        // the longest unit wins
+        assert_eq!(
+            parsed("1 1/2 fl oz Rye"),
+            ("Rye".to_owned(), Some(1.5), unit("fl oz"))
+        );
+        assert_eq!(
+            parsed("45ml lime juice"),
+            ("lime juice".to_owned(), Some(45.0), unit("ml"))
+        );
+        assert_eq!(
+            parsed("½ tsp. sugar"),
+            ("sugar".to_owned(), Some(0.5), unit("tsp"))
+        );
+        assert_eq!(
+            parsed("2-3 Dashes Angostura"),
+            ("Angostura".to_owned(), Some(3.0), unit("dashes"))
+        );
+        assert_eq!(
+            parsed("1 cup of ice"),
+            ("ice".to_owned(), Some(1.0), unit("cup"))
+        );
+    }
+
+    #[test]
+    fn keeps_names_that_only_start_like_units() {
+        // "l" is a unit, but not at the start of "limes"
+        assert_eq!(parsed("3 limes"), ("limes".to_owned(), Some(3.0), None));
+        assert_eq!(parsed(" Gin "), ("Gin".to_owned(), None, None));
+        // nothing would be left of the name
+        assert_eq!(parsed("2 oz"), ("2 oz".to_owned(), None, None));
+        assert_eq!(parsed("1/0 oz Gin"), ("1/0 oz Gin".to_owned(), None, None));
+    }
+
+    #[test]
+    fn recognises_only_the_units_given() {
+        let parser = QuantityParser::new(["Glug", " "]);
+        let parsed = parser.parse("2 glug rum");
+        assert_eq!(parsed.name, "rum");
+        assert_eq!(parsed.quantity.unwrap().unit.as_deref(), Some("glug"));
+        assert_eq!(parser.parse("2 oz rum").name, "oz rum");
+    }
+}
@@ -0,0 +1,82 @@
+//! Making a few short passes over the tree before searching it in earnest
+//!
+//! A depth-first search commits early: the first candidates it branches on decide
+//! which part of the tree it spends its calls in, and a poor start can leave it a long
+//! way from a good incumbent for a long time. [With
+//! restarts](crate::BranchBoundBuilder::with_restarts), the search first makes a few
+//! short passes, each with a small share of the call budget, breaking ties between
+//! candidates to branch on at random and differently each time. Candidates costing
+//! nearly as little as the cheapest count as tied, so the passes head into different
+//! parts of the tree even where exact ties are rare. The best set any pass finds is
+//! kept as the incumbent, and the full search starts from it, so it prunes from the
+//! outset.
+//!
+//! Every pass is an exact search cut short, so one that finishes within its calls has
+//! proved its set optimal, and the full search isn't needed. The passes are always
+//! sequential and depth-first, whatever the configured [order](crate::frontier), and
+//! what's left of the call budget goes to the full search.
+use crate::{BranchBound, IngredientSeti, SearchStatus};
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use rustc_hash::FxHashSet;
+
+/// How far above the cheapest candidate's cost, relative to it, another's can be and
+/// still count as tied during a pass
+pub(crate) const TIE_SLACK: f64 = 0.05;
+
+/// The short passes to make before the full search
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct Restarts {
+    pub(crate) passes: usize,
+    /// The most calls each pass may use
+    pub(crate) calls: i32,
+    /// Seeds the first pass's tie-breaking, and each later one's with one more
+    pub(crate) seed: u64,
+}
+
+impl BranchBound {
+    /// Make the short passes, once the search has been [prepared](Self::prepare),
+    /// leaving the best set any finds as the incumbent and the rest of the call budget
+    /// for the full search. Returns whether that's all the searching to do, because a
+    /// pass finished, timed out or was aborted
+    pub(crate) fn run_restarts(
+        &mut self,
+        restarts: Restarts,
+        candidates: &FxHashSet<IngredientSeti>,
+        partial: &FxHashSet<IngredientSeti>,
+        forbidden: &Option<FxHashSet<IngredientSeti>>,
+    ) -> bool {
+        let mut calls = self.calls;
+        let random = self.random.take();
+        let mut done = false;
+        for pass in 0..restarts.passes {
+            let budget = restarts.calls.min(calls);
+            if budget <= 0 {
+                break;
+            }
+            self.restart(self.max_size, budget);
+            self.random = Some(StdRng::seed_from_u64(
+                restarts.seed.wrapping_add(pass as u64),
+            ));
+            self.tie_slack = TIE_SLACK;
+            self.search(
+                &mut candidates.clone(),
+                &mut partial.clone(),
+                &mut forbidden.clone(),
+            );
+            calls -= budget - self.calls;
+            if self.status != SearchStatus::OutOfCalls {
+                done = true;
+                break;
+            }
+        }
+        self.tie_slack = 0.0;
+        self.random = random;
+        if done {
+            self.calls = calls;
+        } else {
+            self.restart(self.max_size, calls);
+        }
+        done
+    }
+}
@@ -0,0 +1,60 @@
+//! Search results in terms of names rather than numeric ids
+use crate::stats::SearchStats;
+use crate::Ingredient;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+
+/// An ingredient set and the cocktails it makes, identified by name
+///
+/// Because it uses names rather than the numeric ids assigned when loading a dataset,
+/// a saved `Solution` stays meaningful after the dataset is reordered or extended,
+/// so it can seed a later search. Both lists are sorted.
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct Solution {
+    pub ingredients: Vec<Ingredient>,
+    pub cocktails: Vec<String>,
+    /// Where each cocktail's recipe came from, if known
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub sources: BTreeMap<String, Vec<String>>,
+    /// The next best distinct ingredient sets, best first, if the search
+    /// [kept them](crate::BranchBoundBuilder::with_top_k)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub runners_up: Vec<Solution>,
+    /// Other ingredient sets making cocktails worth as much as this, if the search
+    /// [collected them](crate::BranchBoundBuilder::with_ties), fewest ingredients first
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub ties: Vec<Solution>,
+    /// How far this may be from optimal, if the search stopped before proving it
+    /// optimal
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub gap: Option<Gap>,
+    /// How the search that found this went
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stats: Option<SearchStats>,
+}
+
+/// The range the optimal score lies in, from a search that didn't prove its result
+/// optimal. Scores are as [`BranchBound::score`](crate::BranchBound::score) counts them:
+/// cocktails made, unless they're weighted
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Gap {
+    /// The score of the result
+    pub lower: usize,
+    /// The highest score the search couldn't rule out
+    pub upper: usize,
+}
+
+impl fmt::Display for Gap {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "≥ {}, ≤ {}", self.lower, self.upper)
+    }
+}
+
+impl Solution {
+    /// This solution followed by its runners-up: the best distinct ingredient sets
+    /// found, best first
+    pub fn alternatives(&self) -> impl Iterator<Item = &Solution> {
+        std::iter::once(self).chain(&self.runners_up)
+    }
+}
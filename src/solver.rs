@@ -0,0 +1,38 @@
+//! A common interface to the algorithms that solve a [`Problem`]
+//!
+//! [`BranchBound`] finds the optimum, given enough calls. [`Greedy`] and
+//! [`Annealing`](crate::anneal::Annealing) settle for a good answer quickly, which helps
+//! when the dataset is too large to search exactly.
+//! [`BruteForce`](crate::brute::BruteForce) is optimal without any cleverness, for
+//! datasets small enough to try every combination of ingredients.
+use crate::problem::Problem;
+use crate::solution::Solution;
+use crate::{BranchBound, Error};
+
+/// An algorithm for finding a good ingredient set for a [`Problem`]
+pub trait Solver {
+    /// Find the best ingredient set this algorithm can for `problem`, within its
+    /// budget and constraints
+    fn solve(&mut self, problem: &Problem) -> Result<Solution, Error>;
+}
+
+/// The exact search. It must have been configured from the problem's
+/// [`builder`](Problem::builder)
+impl Solver for BranchBound {
+    fn solve(&mut self, problem: &Problem) -> Result<Solution, Error> {
+        problem.search(self)
+    }
+}
+
+/// The [greedy](BranchBound::greedy) heuristic: fast, but with no guarantee of
+/// finding the optimum. The solution's [gap](Solution::gap) gives the most it could be
+/// short by
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Greedy;
+
+impl Solver for Greedy {
+    fn solve(&mut self, problem: &Problem) -> Result<Solution, Error> {
+        let mut bb = problem.builder(1).try_build()?;
+        Ok(problem.greedy(&mut bb))
+    }
+}
@@ -0,0 +1,120 @@
+//! Statistics on how a search went, for comparing configurations
+//!
+//! Every search keeps a [`SearchStats`] in [`BranchBound::stats`](crate::BranchBound::stats),
+//! which is also returned with its [`Solution`](crate::solution::Solution). The
+//! per-cocktail caches are filled before the search starts, so every lookup hits; what's
+//! worth comparing is how much of the tree each configuration expands and prunes, and
+//! how soon it finds its best set.
+use crate::observer::Prune;
+use crate::preprocess::Elimination;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::time::{Duration, Instant};
+
+/// What a search did, as opposed to what it found
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct SearchStats {
+    /// Nodes expanded
+    pub nodes: u32,
+    /// Nodes pruned, by the bound that pruned them
+    pub prunes: BTreeMap<Prune, u32>,
+    /// Each better ingredient set the search adopted, in the order it did
+    pub improvements: Vec<Improvement>,
+    /// The deepest node expanded. The search is depth-first, so this is also the most
+    /// branches it ever had pending at once
+    pub peak_depth: usize,
+    /// Cocktails dropped before searching, as they can never be made, by why. See
+    /// [`preprocess`](crate::preprocess)
+    #[serde(default)]
+    pub eliminated: BTreeMap<Elimination, u32>,
+    /// Ingredients [probing](crate::BranchBoundBuilder::with_probing) showed every
+    /// better set needs, and so required
+    #[serde(default)]
+    pub probed_required: u32,
+    /// How much each bound function was used, by the reason it prunes with
+    #[serde(default)]
+    pub bounds: BTreeMap<Prune, BoundUsage>,
+    #[serde(skip)]
+    started: Option<Instant>,
+}
+
+/// How much use a search made of one bound function
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct BoundUsage {
+    /// How many nodes it was evaluated at. A bound is only evaluated if the ones
+    /// before it didn't prune
+    pub evaluated: u32,
+    /// The time spent evaluating it, in all
+    pub time: Duration,
+}
+
+/// A better ingredient set, as adopted by a search
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Improvement {
+    pub score: usize,
+    /// How many ingredients it needs
+    pub ingredients: usize,
+    /// How many nodes the search had expanded by then
+    pub node: u32,
+    /// How long after the search started
+    pub elapsed: Duration,
+}
+
+impl SearchStats {
+    /// All the nodes pruned, by any bound
+    #[must_use]
+    pub fn pruned(&self) -> u32 {
+        self.prunes.values().sum()
+    }
+
+    /// How long after the search started its final result was found, if it found one
+    #[must_use]
+    pub fn time_to_best(&self) -> Option<Duration> {
+        self.improvements
+            .last()
+            .map(|improvement| improvement.elapsed)
+    }
+
+    /// Start the clock, unless it's already running
+    pub(crate) fn start(&mut self) {
+        self.started.get_or_insert_with(Instant::now);
+    }
+
+    /// An improvement to `score` with `ingredients`, `node` nodes in
+    pub(crate) fn improvement(&self, score: usize, ingredients: usize, node: u32) -> Improvement {
+        Improvement {
+            score,
+            ingredients,
+            node,
+            elapsed: self
+                .started
+                .map_or(Duration::ZERO, |started| started.elapsed()),
+        }
+    }
+
+    pub(crate) fn improved(&mut self, score: usize, ingredients: usize, node: u32) {
+        let improvement = self.improvement(score, ingredients, node);
+        self.improvements.push(improvement);
+    }
+
+    pub(crate) fn prune(&mut self, reason: Prune) {
+        *self.prunes.entry(reason).or_insert(0) += 1;
+    }
+
+    /// Count an evaluation of the bound pruning with `reason`, taking `time`
+    pub(crate) fn evaluate(&mut self, reason: Prune, time: Duration) {
+        let usage = self.bounds.entry(reason).or_default();
+        usage.evaluated += 1;
+        usage.time += time;
+    }
+
+    pub(crate) fn eliminate(&mut self, reason: Elimination) {
+        *self.eliminated.entry(reason).or_insert(0) += 1;
+    }
+
+    /// Count a node at `depth` as expanded
+    pub(crate) fn expand(&mut self, depth: usize) {
+        self.nodes += 1;
+        self.peak_depth = self.peak_depth.max(depth);
+    }
+}
@@ -0,0 +1,99 @@
+//! Driving the search a node at a time
+//!
+//! [`BranchBound::search`] keeps its own frontier and decides which node to expand
+//! next. A driver that wants to own the frontier instead, to farm nodes out, draw the
+//! tree, or explore it in an order of its own, asks for the [`root`](BranchBound::root)
+//! node and then [`step`](BranchBound::step)s through whichever nodes it likes. Each
+//! step bounds the node, adopts it as the incumbent if it's better, and hands back its
+//! include and exclude branches; the incumbent, call budget, time limit, statistics and
+//! observer are all the search's, just as if it had chosen the node itself. Any order
+//! that eventually steps through every child it's given is exact.
+//!
+//! ```
+//! use branchbound::{BranchBound, IngredientSeti};
+//! use rustc_hash::FxHashSet;
+//!
+//! let cocktails = FxHashSet::from_iter([
+//!     IngredientSeti::from([0, 1]),
+//!     IngredientSeti::from([1, 2]),
+//!     IngredientSeti::from([3]),
+//! ]);
+//! let mut bb = BranchBound::new(1000, 2);
+//! // a queue rather than a stack, so breadth-first
+//! let mut queue = std::collections::VecDeque::from([bb.root(&cocktails)]);
+//! while let Some(node) = queue.pop_front() {
+//!     let Some(expansion) = bb.step(&node) else {
+//!         break;
+//!     };
+//!     queue.extend(expansion.children);
+//! }
+//! assert_eq!(bb.highest_score, 1);
+//! ```
+//!
+//! Stepping doesn't add to a [subtree cache](crate::subtree), since only the built-in
+//! depth-first search knows when a subtree is finished, and doesn't
+//! [checkpoint](crate::checkpoint).
+use crate::checkpoint::Node;
+use crate::{Alternative, BranchBound, IngredientSeti};
+use rustc_hash::FxHashSet;
+
+/// What stepping through a node found
+#[derive(Debug, Clone, PartialEq)]
+pub struct Expansion {
+    /// The node's include and exclude branches, include first. Empty if the node was
+    /// pruned, or there's nothing left to branch on
+    pub children: Vec<Node>,
+    /// The new incumbent, if the node improved on the last
+    pub incumbent: Option<Alternative>,
+}
+
+impl BranchBound {
+    /// The initial node of a search for the best cocktails among `candidates`. The
+    /// first time, this prepares the search as [`search`](Self::search) would,
+    /// including any [probing](crate::BranchBoundBuilder::with_probing)
+    pub fn root(&mut self, candidates: &FxHashSet<IngredientSeti>) -> Node {
+        let mut candidates = candidates.clone();
+        let mut partial = FxHashSet::default();
+        let mut forbidden = None;
+        if self.initial {
+            self.prepare(&mut candidates, &mut partial, &mut forbidden);
+            if self.probing && self.top_k == 1 {
+                self.probe(&mut candidates, &mut partial);
+            }
+        }
+        let forbidden =
+            forbidden.unwrap_or_else(|| self.forbidden_combinations.iter().cloned().collect());
+        self.root_node(&candidates, &partial, &forbidden)
+    }
+
+    /// Expand `node`, returning its branches and any better incumbent it makes, or
+    /// `None` if the search has to stop, out of calls or time or because it was
+    /// aborted. [`status`](Self::status) then says why
+    pub fn step(&mut self, node: &Node) -> Option<Expansion> {
+        let (score, len) = (self.highest_score, self.highest_ingredients.len());
+        let children = self.expand_node(node);
+        // the driver, not the search, knows when the node's subtree is finished
+        self.open_subtrees.clear();
+        let children = children?;
+        let improved = (self.highest_score, self.highest_ingredients.len()) != (score, len);
+        Some(Expansion {
+            children,
+            incumbent: improved.then(|| self.best()),
+        })
+    }
+
+    /// The most `node` and the cocktails it could go on to make are worth, by the
+    /// tightest bound, e.g. to order a frontier by
+    #[must_use]
+    pub fn bound(&self, node: &Node) -> usize {
+        let candidates = self.cocktails.resolve(&node.candidates);
+        let partial_ingredients = &node.partial_ingredients | &node.bought;
+        self.node_bound(&candidates, &node.partial, &partial_ingredients)
+    }
+
+    /// The cocktails that could still be made below `node`
+    #[must_use]
+    pub fn candidates(&self, node: &Node) -> Vec<&IngredientSeti> {
+        self.cocktails.resolve(&node.candidates)
+    }
+}
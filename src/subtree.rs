@@ -0,0 +1,127 @@
+//! Remembering how much the subtrees already searched could add, so that repeats are
+//! looked up rather than searched again
+//!
+//! Below a node, all that matters is what's left: what each candidate is worth and the
+//! ingredients it still needs, the same for the cocktails branched away from that the
+//! candidates could still complete, and how many more ingredients the budget allows.
+//! Two nodes reached by different branches often agree on all of these while having
+//! bought different ingredients, e.g. when the ingredients they differ by are needed by
+//! nothing left. A [`SubtreeCache`] keeps a fingerprint of what's left at each node
+//! whose subtree the search has finished, along with the most that subtree could add to
+//! the node's score: everything in it was either explored, so scored no better than the
+//! incumbent, or pruned by a bound no better than the incumbent. When another node
+//! leaves the same problem, it can't beat the incumbent if its score plus that much
+//! doesn't.
+//!
+//! That only holds if a cocktail's worth and an ingredient's cost don't depend on what
+//! else is bought, so the cache isn't consulted with
+//! [variant groups](crate::BranchBoundBuilder::variant_group),
+//! [soft limits](crate::BranchBoundBuilder::soft_limit), category minimums or maximums,
+//! or a spend budget. Nor is it when the search has to keep sets worse than the
+//! incumbent, is [approximate](crate::BranchBoundBuilder::approximation) or limited in
+//! depth. Only the sequential depth-first search uses one, and it lasts between
+//! searches with the same [`BranchBound`](crate::BranchBound), as what it records
+//! doesn't depend on the incumbent.
+//!
+//! Like the [transposition table](crate::transposition), it holds as many entries as
+//! fit in its memory cap, then evicts the oldest to make room.
+use crate::IngredientSeti;
+use rustc_hash::FxHashMap;
+use std::collections::hash_map::RandomState;
+use std::collections::VecDeque;
+use std::hash::{BuildHasher, Hash};
+
+/// Roughly how much memory each entry takes, counting the map and the eviction order
+const ENTRY_BYTES: usize = 64;
+
+/// The most each finished subtree could add, by what's left at its root
+#[derive(Debug, Clone)]
+pub struct SubtreeCache {
+    /// The most entries to hold
+    capacity: usize,
+    /// The most each subtree could add, by fingerprint
+    entries: FxHashMap<u128, usize>,
+    /// Every entry, oldest first
+    order: VecDeque<u128>,
+    /// A pair of hashers, making up a 128-bit fingerprint between them
+    keys: [RandomState; 2],
+    /// How many entries have been evicted
+    pub evictions: u64,
+}
+
+impl SubtreeCache {
+    /// A cache using at most about `max_bytes` of memory
+    #[must_use]
+    pub fn new(max_bytes: usize) -> SubtreeCache {
+        SubtreeCache {
+            capacity: max_bytes / ENTRY_BYTES,
+            entries: FxHashMap::default(),
+            order: VecDeque::new(),
+            keys: [RandomState::new(), RandomState::new()],
+            evictions: 0,
+        }
+    }
+
+    /// How many subtrees the cache holds
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Forget every subtree
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.order.clear();
+    }
+
+    /// The fingerprint of what's left at a node: the candidates, as what each is worth
+    /// and the ingredients it still needs, the ingredients each cocktail branched away
+    /// from still needs, and how many more ingredients can be bought
+    pub(crate) fn fingerprint(
+        &self,
+        candidates: impl Iterator<Item = (usize, IngredientSeti)>,
+        forbidden: impl Iterator<Item = IngredientSeti>,
+        remaining: usize,
+    ) -> u128 {
+        // sets of cocktails iterate in no particular order, so sum their hashes
+        let candidates = candidates.fold(0u128, |sum, candidate| {
+            sum.wrapping_add(self.hash(&candidate))
+        });
+        let forbidden = forbidden.fold(0u128, |sum, unmet| sum.wrapping_add(self.hash(&unmet)));
+        self.hash(&(candidates, forbidden, remaining))
+    }
+
+    /// The most the subtree with `fingerprint` could add, if it's been searched
+    pub(crate) fn get(&self, fingerprint: u128) -> Option<usize> {
+        self.entries.get(&fingerprint).copied()
+    }
+
+    /// Record that the subtree with `fingerprint` could add at most `gain`, evicting
+    /// the oldest entry if the cache is full
+    pub(crate) fn insert(&mut self, fingerprint: u128, gain: usize) {
+        if self.capacity == 0 {
+            return;
+        }
+        if let Some(known) = self.entries.get_mut(&fingerprint) {
+            *known = (*known).min(gain);
+            return;
+        }
+        if self.entries.len() >= self.capacity {
+            if let Some(evicted) = self.order.pop_front() {
+                self.entries.remove(&evicted);
+                self.evictions += 1;
+            }
+        }
+        self.order.push_back(fingerprint);
+        self.entries.insert(fingerprint, gain);
+    }
+
+    fn hash(&self, value: &impl Hash) -> u128 {
+        u128::from(self.keys[0].hash_one(value)) << 64 | u128::from(self.keys[1].hash_one(value))
+    }
+}
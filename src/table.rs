@@ -0,0 +1,142 @@
+//! Numbering the cocktails a search meets, so that its nodes hold sets of numbers
+//!
+//! Every candidate and forbidden cocktail a search starts from is
+//! [interned](CocktailTable::intern) in the search's [`CocktailTable`], and keeps its
+//! index for as long as the [`BranchBound`](crate::BranchBound) lasts. A node's
+//! candidates and forbidden cocktails are then [`CocktailSet`]s of those indices, which
+//! branching copies and filters a word at a time, without cloning any cocktail's
+//! ingredients.
+use crate::IngredientSeti;
+use rustc_hash::FxHashMap;
+use serde::{Deserialize, Serialize};
+use std::ops::Index;
+
+/// The cocktails a search has met, by index
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub(crate) struct CocktailTable {
+    cocktails: Vec<IngredientSeti>,
+    indices: FxHashMap<IngredientSeti, usize>,
+}
+
+impl CocktailTable {
+    /// How many cocktails the table holds
+    pub(crate) fn len(&self) -> usize {
+        self.cocktails.len()
+    }
+
+    /// The index of `cocktail`, numbering it if it's new
+    pub(crate) fn intern(&mut self, cocktail: &IngredientSeti) -> usize {
+        if let Some(&index) = self.indices.get(cocktail) {
+            return index;
+        }
+        let index = self.cocktails.len();
+        self.cocktails.push(cocktail.clone());
+        self.indices.insert(cocktail.clone(), index);
+        index
+    }
+
+    /// `cocktails` as a set of indices, numbering any that are new
+    pub(crate) fn intern_all<'a>(
+        &mut self,
+        cocktails: impl IntoIterator<Item = &'a IngredientSeti>,
+    ) -> CocktailSet {
+        cocktails
+            .into_iter()
+            .map(|cocktail| self.intern(cocktail))
+            .collect()
+    }
+
+    /// The index of `cocktail`, if it's been numbered
+    pub(crate) fn index_of(&self, cocktail: &IngredientSeti) -> Option<usize> {
+        self.indices.get(cocktail).copied()
+    }
+
+    /// The cocktails in `set`, in index order
+    pub(crate) fn resolve<'a>(&'a self, set: &CocktailSet) -> Vec<&'a IngredientSeti> {
+        set.iter().map(|index| &self.cocktails[index]).collect()
+    }
+}
+
+impl Index<usize> for CocktailTable {
+    type Output = IngredientSeti;
+
+    fn index(&self, index: usize) -> &IngredientSeti {
+        &self.cocktails[index]
+    }
+}
+
+/// A set of indices into a [`CocktailTable`], as a bitset. Trailing empty words are
+/// always trimmed, so equal sets compare and hash equal
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub(crate) struct CocktailSet {
+    words: Vec<u64>,
+}
+
+impl CocktailSet {
+    pub(crate) fn len(&self) -> usize {
+        self.words
+            .iter()
+            .map(|word| word.count_ones() as usize)
+            .sum()
+    }
+
+    pub(crate) fn insert(&mut self, index: usize) {
+        let word = index / 64;
+        if word >= self.words.len() {
+            self.words.resize(word + 1, 0);
+        }
+        self.words[word] |= 1 << (index % 64);
+    }
+
+    pub(crate) fn remove(&mut self, index: usize) {
+        if let Some(word) = self.words.get_mut(index / 64) {
+            *word &= !(1 << (index % 64));
+            self.trim();
+        }
+    }
+
+    /// Keep only the indices `f` accepts
+    pub(crate) fn retain(&mut self, mut f: impl FnMut(usize) -> bool) {
+        for (position, word) in self.words.iter_mut().enumerate() {
+            let mut bits = *word;
+            while bits != 0 {
+                let bit = bits.trailing_zeros() as usize;
+                bits &= bits - 1;
+                if !f(position * 64 + bit) {
+                    *word &= !(1 << bit);
+                }
+            }
+        }
+        self.trim();
+    }
+
+    /// The indices in the set, in ascending order
+    pub(crate) fn iter(&self) -> impl Iterator<Item = usize> + '_ {
+        self.words.iter().enumerate().flat_map(|(position, &word)| {
+            let mut bits = word;
+            std::iter::from_fn(move || {
+                (bits != 0).then(|| {
+                    let bit = bits.trailing_zeros() as usize;
+                    bits &= bits - 1;
+                    position * 64 + bit
+                })
+            })
+        })
+    }
+
+    fn trim(&mut self) {
+        while self.words.last() == Some(&0) {
+            self.words.pop();
+        }
+    }
+}
+
+impl FromIterator<usize> for CocktailSet {
+    fn from_iter<I: IntoIterator<Item = usize>>(indices: I) -> CocktailSet {
+        let mut set = CocktailSet::default();
+        for index in indices {
+            set.insert(index);
+        }
+        set
+    }
+}
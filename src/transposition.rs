@@ -0,0 +1,178 @@
+//! Remembering the nodes already searched, so that repeats are pruned
+//!
+//! A node is the ingredients bought so far, the candidates left, and the cocktails
+//! branched away from. A [`TranspositionTable`] keeps a fingerprint of each node the
+//! search has branched on, along with its upper bound. When a node turns up again,
+//! everything below it has already been searched against an incumbent no better than
+//! the current one, so it's pruned.
+//!
+//! A single search never meets the same node twice: each branch decides one cocktail
+//! both ways, so no two nodes share a solution. Repeats come from searching again with
+//! the same [`BranchBound`](crate::BranchBound), e.g. a caller exploring overlapping
+//! parts of a problem with [`search`](crate::BranchBound::search) itself, and the table
+//! lasts between searches for that. It's cleared whenever the ingredient budget changes.
+//!
+//! Fingerprints are 128-bit hashes, so two different nodes sharing one is vanishingly
+//! unlikely. The table holds as many as fit in its memory cap, and then makes room by
+//! evicting according to its [`Eviction`] policy. Only the sequential searches use one:
+//! a parallel search, and a search resumed from a checkpoint, start without.
+use crate::table::CocktailSet;
+use crate::IngredientSeti;
+use rustc_hash::FxHashMap;
+use std::collections::hash_map::RandomState;
+use std::collections::BTreeSet;
+use std::hash::{BuildHasher, Hash};
+
+/// Roughly how much memory each entry takes, counting the map and the eviction order
+const ENTRY_BYTES: usize = 80;
+
+/// Which entry a full [`TranspositionTable`] evicts to make room
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Eviction {
+    /// The one recorded first
+    #[default]
+    Oldest,
+    /// The one with the lowest upper bound, which the bounds are likeliest to prune
+    /// anyway if it turns up again. Ties go to the oldest
+    LowestBound,
+}
+
+/// The fingerprints of the nodes a search has branched on
+#[derive(Debug, Clone)]
+pub struct TranspositionTable {
+    /// The most entries to hold
+    capacity: usize,
+    eviction: Eviction,
+    /// The upper bound of each node, by fingerprint
+    entries: FxHashMap<u128, usize>,
+    /// Every entry, the next to be evicted first
+    order: BTreeSet<(usize, u64, u128)>,
+    /// Entries recorded so far, to break ties in the eviction order
+    sequence: u64,
+    /// A pair of hashers, making up a 128-bit fingerprint between them
+    keys: [RandomState; 2],
+    /// How many entries have been evicted
+    pub evictions: u64,
+}
+
+impl TranspositionTable {
+    /// A table using at most about `max_bytes` of memory, evicting by `eviction` once
+    /// it's full
+    #[must_use]
+    pub fn new(max_bytes: usize, eviction: Eviction) -> TranspositionTable {
+        TranspositionTable {
+            capacity: max_bytes / ENTRY_BYTES,
+            eviction,
+            entries: FxHashMap::default(),
+            order: BTreeSet::new(),
+            sequence: 0,
+            keys: [RandomState::new(), RandomState::new()],
+            evictions: 0,
+        }
+    }
+
+    /// How many nodes the table holds
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Forget every node, e.g. before searching for a different number of ingredients
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.order.clear();
+    }
+
+    /// The fingerprint of a node. Its depth only matters if the search is
+    /// [limited](crate::BranchBoundBuilder::max_depth), so it's `None` otherwise
+    pub(crate) fn fingerprint(
+        &self,
+        partial_ingredients: &IngredientSeti,
+        candidates: &CocktailSet,
+        forbidden: &CocktailSet,
+        depth: Option<usize>,
+    ) -> u128 {
+        // cocktails keep their numbers between searches, so the sets of them can be
+        // hashed as they are
+        self.hash(&(partial_ingredients, candidates, forbidden, depth))
+    }
+
+    /// Has the node with `fingerprint` been recorded?
+    pub(crate) fn contains(&self, fingerprint: u128) -> bool {
+        self.entries.contains_key(&fingerprint)
+    }
+
+    /// Record the node with `fingerprint`, whose upper bound is `bound`, evicting
+    /// another if the table is full
+    pub(crate) fn insert(&mut self, fingerprint: u128, bound: usize) {
+        if self.capacity == 0 || self.entries.contains_key(&fingerprint) {
+            return;
+        }
+        if self.entries.len() >= self.capacity {
+            if let Some((_, _, evicted)) = self.order.pop_first() {
+                self.entries.remove(&evicted);
+                self.evictions += 1;
+            }
+        }
+        self.sequence += 1;
+        let rank = match self.eviction {
+            Eviction::Oldest => 0,
+            Eviction::LowestBound => bound,
+        };
+        self.order.insert((rank, self.sequence, fingerprint));
+        self.entries.insert(fingerprint, bound);
+    }
+
+    fn hash(&self, value: &impl Hash) -> u128 {
+        u128::from(self.keys[0].hash_one(value)) << 64 | u128::from(self.keys[1].hash_one(value))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn records_a_node() {
+        let mut table = TranspositionTable::new(1 << 10, Eviction::Oldest);
+        let partial = IngredientSeti::from([0, 1]);
+        let candidates = [2, 3].into_iter().collect::<CocktailSet>();
+        let node = table.fingerprint(&partial, &candidates, &CocktailSet::default(), None);
+        assert!(!table.contains(node));
+        table.insert(node, 5);
+        assert!(table.contains(node));
+        // a different node, or the same one at a depth, is a miss
+        let other = table.fingerprint(&partial, &candidates, &candidates, None);
+        let deeper = table.fingerprint(&partial, &candidates, &CocktailSet::default(), Some(1));
+        assert!(!table.contains(other));
+        assert!(!table.contains(deeper));
+        assert_eq!(table.len(), 1);
+    }
+
+    #[test]
+    fn evicts_by_policy_once_full() {
+        for (eviction, kept) in [(Eviction::Oldest, [2, 3]), (Eviction::LowestBound, [1, 3])] {
+            let mut table = TranspositionTable::new(2 * ENTRY_BYTES, eviction);
+            table.insert(1, 10);
+            table.insert(2, 5);
+            table.insert(3, 7);
+            assert_eq!(table.len(), 2);
+            assert_eq!(table.evictions, 1);
+            assert!(kept
+                .into_iter()
+                .all(|fingerprint| table.contains(fingerprint)));
+        }
+    }
+
+    #[test]
+    fn records_nothing_without_room() {
+        let mut table = TranspositionTable::new(0, Eviction::Oldest);
+        table.insert(1, 10);
+        assert!(table.is_empty());
+    }
+}
@@ -0,0 +1,174 @@
+//! Deriving per-cocktail weights from a log of what was actually made
+//!
+//! The log is a headerless CSV with one row per drink made: the cocktail's name, then the
+//! date it was made as `YYYY-MM-DD`. Each entry contributes a weight that halves every
+//! `half_life_days`, so frequently *and* recently made cocktails end up heaviest.
+use rustc_hash::FxHashMap;
+use std::fmt;
+use std::io::Read;
+
+/// A single row of a consumption log
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LogEntry {
+    pub cocktail: String,
+    /// Days since 1970-01-01
+    pub day: i64,
+}
+
+#[derive(Debug)]
+pub enum LogError {
+    Csv(csv::Error),
+    /// A row was missing its name or date, or the date wasn't `YYYY-MM-DD`
+    Malformed {
+        row: usize,
+        value: String,
+    },
+}
+
+impl fmt::Display for LogError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LogError::Csv(e) => write!(f, "couldn't read consumption log: {e}"),
+            LogError::Malformed { row, value } => {
+                write!(f, "row {row} of consumption log is malformed: {value:?}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for LogError {}
+
+impl From<csv::Error> for LogError {
+    fn from(e: csv::Error) -> Self {
+        LogError::Csv(e)
+    }
+}
+
+/// Parse a `YYYY-MM-DD` date into days since 1970-01-01
+///
+/// Uses Howard Hinnant's `days_from_civil`, so it's valid for any proleptic Gregorian date.
+#[must_use]
+pub fn parse_date(date: &str) -> Option<i64> {
+    let mut parts = date.trim().splitn(3, '-');
+    let y = parts.next()?.parse::<i64>().ok()?;
+    let m = parts.next()?.parse::<i64>().ok()?;
+    let d = parts.next()?.parse::<i64>().ok()?;
+    if !(1..=12).contains(&m) || !(1..=31).contains(&d) {
+        return None;
+    }
+    let y = if m <= 2 { y - 1 } else { y };
+    let era = y.div_euclid(400);
+    let yoe = y - era * 400;
+    let doy = (153 * (if m > 2 { m - 3 } else { m + 9 }) + 2) / 5 + d - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    Some(era * 146_097 + doe - 719_468)
+}
+
+/// Read a consumption log
+pub fn read_log<R: Read>(reader: R) -> Result<Vec<LogEntry>, LogError> {
+    let csvr = csv::ReaderBuilder::new()
+        .flexible(true)
+        .has_headers(false)
+        .trim(csv::Trim::All)
+        .from_reader(reader);
+    csvr.into_records()
+        .enumerate()
+        .map(|(i, record)| {
+            let r = record?;
+            let malformed = || LogError::Malformed {
+                row: i + 1,
+                value: r.iter().collect::<Vec<_>>().join(","),
+            };
+            let cocktail = r
+                .get(0)
+                .filter(|name| !name.is_empty())
+                .ok_or_else(malformed)?;
+            let day = r.get(1).and_then(parse_date).ok_or_else(malformed)?;
+            Ok(LogEntry {
+                cocktail: cocktail.to_owned(),
+                day,
+            })
+        })
+        .collect()
+}
+
+/// Derive a weight for every cocktail in `log`
+///
+/// Each entry is worth `0.5^(age / half_life_days)`, where the age is measured from
+/// `today` (days since 1970-01-01), or from the most recent entry if that's `None`, so the
+/// same log always produces the same weights. Entries dated after `today` count in full.
+/// Cocktails absent from the log get no weight; it's up to the caller to pick a default.
+#[must_use]
+pub fn derive_weights(
+    log: &[LogEntry],
+    half_life_days: f64,
+    today: Option<i64>,
+) -> FxHashMap<String, f64> {
+    let mut weights = FxHashMap::default();
+    let Some(today) = today.or_else(|| log.iter().map(|entry| entry.day).max()) else {
+        return weights;
+    };
+    for entry in log {
+        let age = (today - entry.day).max(0) as f64;
+        *weights.entry(entry.cocktail.clone()).or_insert(0.0) += 0.5f64.powf(age / half_life_days);
+    }
+    weights
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_dates() {
+        assert_eq!(parse_date("1970-01-01"), Some(0));
+        // past a leap day
+        assert_eq!(parse_date(" 2000-03-01 "), Some(11_017));
+        assert_eq!(parse_date("1969-12-31"), Some(-1));
+        assert_eq!(parse_date("2024-13-01"), None);
+        assert_eq!(parse_date("2024-01"), None);
+        assert_eq!(parse_date("yesterday"), None);
+    }
+
+    #[test]
+    fn reads_a_log() {
+        let log = read_log("Negroni, 1970-01-03\nGimlet,1970-01-01\n".as_bytes()).unwrap();
+        assert_eq!(
+            log,
+            [
+                LogEntry {
+                    cocktail: "Negroni".to_owned(),
+                    day: 2
+                },
+                LogEntry {
+                    cocktail: "Gimlet".to_owned(),
+                    day: 0
+                }
+            ]
+        );
+        let error = read_log("Negroni,1970-01-03\nGimlet\n".as_bytes()).unwrap_err();
+        assert!(matches!(error, LogError::Malformed { row: 2, .. }));
+    }
+
+    #[test]
+    fn halves_weights_every_half_life() {
+        let entry = |cocktail: &str, day| LogEntry {
+            cocktail: cocktail.to_owned(),
+            day,
+        };
+        let log = [
+            entry("Negroni", 10),
+            entry("Negroni", 8),
+            entry("Gimlet", 6),
+        ];
+        // aged from the latest entry
+        let weights = derive_weights(&log, 2.0, None);
+        assert_eq!(weights["Negroni"], 1.5);
+        assert_eq!(weights["Gimlet"], 0.25);
+        // an entry after today counts in full
+        let weights = derive_weights(&log, 2.0, Some(8));
+        assert_eq!(weights["Negroni"], 2.0);
+        assert_eq!(weights["Gimlet"], 0.5);
+        assert!(derive_weights(&[], 2.0, None).is_empty());
+    }
+}
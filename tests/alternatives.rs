@@ -0,0 +1,30 @@
+use branchbound::{BranchBound, BranchBoundBuilder, IngredientSeti};
+use rustc_hash::FxHashSet;
+
+fn cocktails(sets: &[&[i32]]) -> FxHashSet<IngredientSeti> {
+    sets.iter()
+        .map(|set| set.iter().copied().collect())
+        .collect()
+}
+
+fn search(bb: &mut BranchBound, candidates: &FxHashSet<IngredientSeti>) {
+    bb.search(
+        &mut candidates.clone(),
+        &mut FxHashSet::default(),
+        &mut None,
+    );
+    assert!(bb.status.completed());
+}
+
+#[test]
+fn top_k_leaves_out_sets_making_nothing() {
+    // only two sets of two ingredients make anything, so there are fewer than k to keep
+    let candidates = cocktails(&[&[0, 1], &[2, 3], &[4, 5, 6]]);
+    let mut bb = BranchBoundBuilder::new(10_000, 2).with_top_k(5).build();
+    search(&mut bb, &candidates);
+    assert_eq!(bb.alternatives.len(), 2);
+    assert!(bb
+        .alternatives
+        .iter()
+        .all(|alternative| alternative.score > 0));
+}
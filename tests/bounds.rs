@@ -0,0 +1,78 @@
+use branchbound::observer::Prune;
+use branchbound::{BranchBound, BranchBoundBuilder, ConfigError, IngredientSeti};
+use rustc_hash::FxHashSet;
+
+fn cocktails(sets: &[&[i32]]) -> FxHashSet<IngredientSeti> {
+    sets.iter()
+        .map(|set| set.iter().copied().collect())
+        .collect()
+}
+
+fn search(bb: &mut BranchBound, candidates: &FxHashSet<IngredientSeti>) {
+    bb.search(
+        &mut candidates.clone(),
+        &mut FxHashSet::default(),
+        &mut None,
+    );
+    assert!(bb.status.completed());
+}
+
+#[test]
+fn required_ingredients_filling_the_budget() {
+    let candidates = cocktails(&[&[0], &[0, 1], &[1, 2], &[2, 3], &[3]]);
+    let mut bb = BranchBoundBuilder::new(10_000, 2)
+        .required_ingredients(IngredientSeti::from([0, 1]))
+        .with_expensive_bounds()
+        .build();
+    search(&mut bb, &candidates);
+    assert_eq!(bb.highest_score, 2);
+    assert_eq!(bb.highest_ingredients, IngredientSeti::from([0, 1]));
+}
+
+#[test]
+fn owned_ingredients_beyond_the_budget() {
+    let candidates = cocktails(&[&[0, 1, 2], &[0, 3], &[1, 4], &[5, 6]]);
+    let mut bb = BranchBoundBuilder::new(10_000, 1)
+        .owned_ingredients([0, 1, 2])
+        .with_expensive_bounds()
+        .build();
+    search(&mut bb, &candidates);
+    assert_eq!(bb.highest_score, 2);
+}
+
+#[test]
+fn empty_budget() {
+    let candidates = cocktails(&[&[0], &[0, 1], &[2]]);
+    let mut bb = BranchBoundBuilder::new(10_000, 0)
+        .with_expensive_bounds()
+        .build();
+    search(&mut bb, &candidates);
+    assert_eq!(bb.highest_score, 0);
+}
+
+#[test]
+fn weights_beyond_i32() {
+    // scores past i32::MAX mustn't wrap round when compared with the bounds
+    let heavy = 1_500_000_000;
+    let candidates = cocktails(&[&[0], &[1], &[2, 3], &[4]]);
+    let mut bb = BranchBoundBuilder::new(10_000, 2)
+        .cocktail_weights([
+            (IngredientSeti::from([0]), heavy),
+            (IngredientSeti::from([1]), heavy),
+            (IngredientSeti::from([4]), heavy + 1),
+        ])
+        .build();
+    search(&mut bb, &candidates);
+    assert_eq!(bb.highest_score, 2 * heavy + 1);
+}
+
+#[test]
+fn rejects_reasons_that_arent_bounds() {
+    let built = BranchBoundBuilder::new(10_000, 2)
+        .bounds([Prune::Total, Prune::Transposition])
+        .try_build();
+    assert!(matches!(
+        built,
+        Err(ConfigError::NotABound(Prune::Transposition))
+    ));
+}
@@ -0,0 +1,859 @@
+use branchbound::anneal::Annealing;
+use branchbound::branching::MostCommonIngredient;
+use branchbound::brute::{BruteForce, MAX_INGREDIENTS};
+use branchbound::distributed::{WorkResult, WorkUnit};
+use branchbound::frontier::SearchOrder;
+use branchbound::observer::Prune;
+use branchbound::portfolio::Portfolio;
+use branchbound::problem::Problem;
+use branchbound::solution::Solution;
+use branchbound::solver::{Greedy, Solver};
+use branchbound::transposition::Eviction;
+use branchbound::{BranchBound, BranchBoundBuilder, ConfigError, Error, IngredientSeti};
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+use rustc_hash::FxHashSet;
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
+use std::ops::RangeInclusive;
+
+/// Search `problem` exactly, with calls to spare, and by brute force, returning the
+/// scores and ingredient counts of each
+fn both(problem: &Problem) -> ((usize, usize), (usize, usize)) {
+    let mut bb = problem.builder(1_000_000).build();
+    bb.search(
+        &mut problem.cocktail_sets(),
+        &mut Default::default(),
+        &mut None,
+    );
+    assert!(bb.status.completed());
+    let mut brute = problem.builder(1).build();
+    brute.brute_force(&problem.cocktail_sets()).unwrap();
+    (
+        (bb.highest_score, bb.highest_ingredients.len()),
+        (brute.highest_score, brute.highest_ingredients.len()),
+    )
+}
+
+/// `n` random problems from `seed`, small enough to brute force
+fn random_problems(seed: u64, n: usize) -> impl Iterator<Item = Problem> {
+    let mut rng = StdRng::seed_from_u64(seed);
+    (0..n).map(move |_| {
+        let ingredients = rng.gen_range(4..=14);
+        let cocktails = rng.gen_range(1..=25);
+        let max_size = rng.gen_range(1..=ingredients);
+        Problem::random(&mut rng, ingredients, cocktails, 1..=4, max_size)
+    })
+}
+
+/// The score and ingredient count of the best set brute force finds for `problem`
+fn brute_force(problem: &Problem) -> (usize, usize) {
+    let mut brute = problem.builder(1).build();
+    brute.brute_force(&problem.cocktail_sets()).unwrap();
+    (brute.highest_score, brute.highest_ingredients.len())
+}
+
+/// The score of `solution` to `problem`, as a search counts it, and its ingredient count
+fn scored(problem: &Problem, solution: &Solution) -> (usize, usize) {
+    let cocktails = solution
+        .cocktails
+        .iter()
+        .map(|name| problem.find(name).unwrap().ingredients.clone())
+        .collect::<FxHashSet<IngredientSeti>>();
+    (
+        problem.builder(1).build().score(&cocktails),
+        solution.ingredients.len(),
+    )
+}
+
+/// Weigh every cocktail of `problem` at random
+fn weigh(problem: &mut Problem, rng: &mut StdRng) {
+    for c in 0..problem.cocktails().len() {
+        problem
+            .set_weight(&format!("c{c}"), rng.gen_range(0.0..5.0))
+            .unwrap();
+    }
+}
+
+/// Price every ingredient of `problem` at random, and give it a spend budget
+fn price(problem: &mut Problem, rng: &mut StdRng) {
+    // names that no cocktail uses are rejected, which is fine
+    for i in 0..14 {
+        problem
+            .set_price(&format!("i{i}"), rng.gen_range(0.5..5.0))
+            .ok();
+    }
+    problem.spend_budget(rng.gen_range(2.0..20.0));
+}
+
+/// Up to `count` of the ingredients `problem` uses, at random
+fn some_ingredients(
+    problem: &Problem,
+    rng: &mut StdRng,
+    count: RangeInclusive<usize>,
+) -> Vec<String> {
+    let names = problem
+        .mapper()
+        .iter()
+        .map(|(_, name)| name.clone())
+        .collect::<Vec<_>>();
+    let count = rng.gen_range(count);
+    names.choose_multiple(rng, count).cloned().collect()
+}
+
+/// Every set of at most `problem.max_size` of the ingredients its cocktails use
+fn every_set(problem: &Problem) -> impl Iterator<Item = IngredientSeti> {
+    let pool = problem
+        .cocktail_sets()
+        .iter()
+        .flatten()
+        .copied()
+        .collect::<IngredientSeti>()
+        .into_iter()
+        .collect::<Vec<_>>();
+    let max_size = problem.max_size;
+    (0..1u32 << pool.len())
+        .filter(move |chosen| chosen.count_ones() as usize <= max_size)
+        .map(move |chosen| {
+            (0..pool.len())
+                .filter(|bit| chosen & 1 << bit != 0)
+                .map(|bit| pool[bit])
+                .collect()
+        })
+}
+
+/// The best score among `problem`'s cocktails, and every distinct set of the
+/// ingredients needed to make cocktails worth it, by trying every combination
+fn optima(problem: &Problem) -> (usize, BTreeSet<IngredientSeti>) {
+    let cocktails = problem.cocktail_sets();
+    let (mut score, mut optima) = (0, BTreeSet::new());
+    for ingredients in every_set(problem) {
+        let made = cocktails
+            .iter()
+            .filter(|cocktail| cocktail.is_subset(&ingredients))
+            .collect::<Vec<_>>();
+        if made.is_empty() || made.len() < score {
+            continue;
+        }
+        if made.len() > score {
+            score = made.len();
+            optima.clear();
+        }
+        // any other ingredients only pad the set out
+        optima.insert(made.into_iter().flatten().copied().collect());
+    }
+    (score, optima)
+}
+
+/// Search each of `problems`, with calls to spare, as `configure` sets the search up,
+/// asserting that it finishes with a valid solution as good as brute force's. Returns
+/// the searches, for any further checks
+fn check_against_oracle(
+    problems: impl IntoIterator<Item = Problem>,
+    mut configure: impl FnMut(BranchBoundBuilder) -> BranchBoundBuilder,
+) -> Vec<BranchBound> {
+    let mut searches = vec![];
+    for problem in problems {
+        // constraints that can't all be met are rejected, which is fine
+        let Ok(mut bb) = configure(problem.builder(1_000_000)).try_build() else {
+            continue;
+        };
+        let solution = problem.search(&mut bb).unwrap();
+        assert!(bb.status.completed());
+        assert_eq!(problem.validate(&solution), Ok(()));
+        assert_eq!(
+            (bb.highest_score, bb.highest_ingredients.len()),
+            brute_force(&problem)
+        );
+        searches.push(bb);
+    }
+    searches
+}
+
+#[test]
+fn search_matches_brute_force() {
+    check_against_oracle(random_problems(1775, 100), |builder| builder);
+}
+
+#[test]
+fn search_matches_brute_force_with_constraints() {
+    let mut rng = StdRng::seed_from_u64(17750);
+    let problems = random_problems(1775, 200).map(|mut problem| {
+        let [required, excluded] = [(); 2].map(|_| some_ingredients(&problem, &mut rng, 0..=1));
+        for name in required {
+            problem.require_ingredient(&name);
+        }
+        for name in excluded {
+            problem.exclude_ingredient(&name).unwrap();
+        }
+        for _ in 0..rng.gen_range(1..=3) {
+            let combination = some_ingredients(&problem, &mut rng, 2..=3);
+            let combination = combination.iter().map(String::as_str).collect::<Vec<_>>();
+            problem.forbid_combination(&combination).unwrap();
+        }
+        let count = rng.gen_range(0..=1);
+        let (names, must_make): (Vec<_>, Vec<_>) = problem
+            .cocktails()
+            .choose_multiple(&mut rng, count)
+            .map(|cocktail| (cocktail.name.clone(), cocktail.ingredients.clone()))
+            .unzip();
+        problem.must_make(names.iter().map(String::as_str)).unwrap();
+        (problem, must_make)
+    });
+    for (problem, must_make) in problems {
+        // a search rejected as unable to make them all is skipped
+        for bb in check_against_oracle([problem], |builder| builder) {
+            assert!(must_make
+                .iter()
+                .all(|cocktail| bb.highest.contains(cocktail)));
+        }
+    }
+}
+
+#[test]
+fn weighted_search_matches_brute_force() {
+    let mut rng = StdRng::seed_from_u64(17590);
+    let problems = random_problems(1759, 100).map(|mut problem| {
+        weigh(&mut problem, &mut rng);
+        problem
+    });
+    check_against_oracle(problems, |builder| builder);
+}
+
+#[test]
+fn priced_search_matches_brute_force() {
+    let mut rng = StdRng::seed_from_u64(17600);
+    let problems = random_problems(1760, 100).map(|mut problem| {
+        price(&mut problem, &mut rng);
+        problem
+    });
+    check_against_oracle(problems, |builder| builder);
+}
+
+#[test]
+fn search_matches_brute_force_with_owned_ingredients() {
+    let mut rng = StdRng::seed_from_u64(17610);
+    let problems = random_problems(1761, 100).map(|mut problem| {
+        let owned = some_ingredients(&problem, &mut rng, 1..=3);
+        problem.with_owned_ingredients(&owned.iter().map(String::as_str).collect::<Vec<_>>());
+        problem
+    });
+    check_against_oracle(problems, |builder| builder);
+}
+
+#[test]
+fn soft_limits_match_brute_force() {
+    let mut rng = StdRng::seed_from_u64(17840);
+    let problems = random_problems(1784, 100).map(|mut problem| {
+        for category in ["spirits", "vermouths"] {
+            let ingredients = some_ingredients(&problem, &mut rng, 1..=4);
+            let (limit, penalty) = (rng.gen_range(0..=2), rng.gen_range(1..=3));
+            problem.soft_limit(
+                category,
+                ingredients.iter().map(String::as_str),
+                limit,
+                penalty,
+            );
+        }
+        // weighted scores count penalties in thousandths too
+        if rng.gen_bool(0.5) {
+            weigh(&mut problem, &mut rng);
+        }
+        problem
+    });
+    check_against_oracle(problems, |builder| builder);
+}
+
+#[test]
+fn category_minimums_match_brute_force() {
+    let mut rng = StdRng::seed_from_u64(17350);
+    let problems = random_problems(1735, 100).map(|mut problem| {
+        let ingredients = some_ingredients(&problem, &mut rng, 1..=4);
+        let minimum = rng.gen_range(1..=ingredients.len());
+        problem.category_minimum("spirits", ingredients.iter().map(String::as_str), minimum);
+        problem
+    });
+    check_against_oracle(problems, |builder| builder);
+}
+
+#[test]
+fn category_maximums_match_brute_force() {
+    let mut rng = StdRng::seed_from_u64(17850);
+    let problems = random_problems(1785, 100).map(|mut problem| {
+        // categories are disjoint, or one holds another
+        let ingredients = some_ingredients(&problem, &mut rng, 1..=8);
+        let (spirits, mixers) = ingredients.split_at(ingredients.len() / 2);
+        for (category, ingredients) in [("spirits", spirits), ("mixers", mixers)] {
+            if ingredients.is_empty() {
+                continue;
+            }
+            let maximum = rng.gen_range(0..=3);
+            problem.category_maximum(category, ingredients.iter().map(String::as_str), maximum);
+            // a minimum within the category leaves less room for the rest of it
+            if rng.gen_bool(0.3) {
+                let some = &ingredients[..rng.gen_range(1..=ingredients.len())];
+                let minimum = rng.gen_range(1..=some.len());
+                problem.category_minimum(category, some.iter().map(String::as_str), minimum);
+            }
+        }
+        problem
+    });
+    check_against_oracle(problems, |builder| builder);
+}
+
+#[test]
+fn ties_match_brute_force() {
+    for problem in random_problems(1787, 100) {
+        let (score, optima) = optima(&problem);
+        let mut bb = problem.builder(1_000_000).with_ties(10_000).build();
+        let solution = problem.search(&mut bb).unwrap();
+        assert!(bb.status.completed());
+        assert_eq!(bb.highest_score, score);
+        assert!(bb.ties.iter().all(|tie| tie.score == score));
+        let ties = bb
+            .ties
+            .iter()
+            .map(|tie| tie.ingredients.clone())
+            .collect::<BTreeSet<_>>();
+        assert_eq!(ties.len(), bb.ties.len(), "ties aren't distinct");
+        assert_eq!(ties, optima);
+        // the solution lists the others, fewest ingredients first
+        assert_eq!(solution.ties.len(), optima.len().saturating_sub(1));
+        assert!(solution
+            .ties
+            .windows(2)
+            .all(|pair| pair[0].ingredients.len() <= pair[1].ingredients.len()));
+        assert!(solution
+            .ties
+            .iter()
+            .all(|tie| problem.validate(tie).is_ok()));
+        // up to a cap, any of them will do
+        let mut bb = problem.builder(1_000_000).with_ties(2).build();
+        problem.search(&mut bb).unwrap();
+        assert_eq!(bb.ties.len(), optima.len().min(2));
+        assert!(bb.ties.iter().all(|tie| optima.contains(&tie.ingredients)));
+    }
+}
+
+#[test]
+fn optima_count_matches_brute_force() {
+    for problem in random_problems(1788, 100) {
+        let (score, optima) = optima(&problem);
+        let mut by_size = BTreeMap::new();
+        for optimum in &optima {
+            *by_size.entry(optimum.len()).or_insert(0) += 1;
+        }
+        let mut bb = problem.builder(1_000_000).build();
+        let count = bb.count_optima(&problem.cocktail_sets());
+        assert!(bb.status.completed());
+        assert_eq!(count.score, score);
+        assert_eq!(count.by_size, by_size);
+        assert_eq!(count.total(), optima.len() as u64);
+    }
+}
+
+#[test]
+fn search_matches_brute_force_with_many_candidates() {
+    let mut rng = StdRng::seed_from_u64(1793);
+    for _ in 0..5 {
+        let max_size = rng.gen_range(3..=6);
+        let problem = Problem::random(&mut rng, 14, 200, 2..=4, max_size);
+        assert!(problem.cocktail_sets().len() > 128);
+        let (search, brute) = both(&problem);
+        assert_eq!(search, brute);
+    }
+}
+
+#[test]
+fn refuses_large_problems() {
+    let mut rng = StdRng::seed_from_u64(1);
+    let problem = Problem::random(&mut rng, MAX_INGREDIENTS + 10, 200, 2..=2, 5);
+    assert!(matches!(
+        BruteForce.solve(&problem),
+        Err(Error::TooLarge { .. })
+    ));
+}
+
+#[test]
+fn best_first_matches_brute_force() {
+    check_against_oracle(random_problems(1778, 100), |builder| {
+        builder.search_order(SearchOrder::BestFirst)
+    });
+}
+
+#[test]
+fn parallel_matches_brute_force() {
+    for threads in [1, 4] {
+        check_against_oracle(random_problems(1753, 100), |builder| {
+            builder.parallel(threads)
+        });
+    }
+}
+
+#[test]
+fn parallel_out_of_calls_is_valid() {
+    let mut rng = StdRng::seed_from_u64(17530);
+    let mut stopped = 0;
+    for problem in random_problems(1753, 100) {
+        let mut bb = problem.builder(rng.gen_range(1..=50)).parallel(4).build();
+        let solution = problem.search(&mut bb).unwrap();
+        assert_eq!(problem.validate(&solution), Ok(()));
+        let (score, len) = brute_force(&problem);
+        if bb.status.completed() {
+            assert_eq!(
+                (bb.highest_score, bb.highest_ingredients.len()),
+                (score, len)
+            );
+        } else {
+            assert!(bb.highest_score <= score);
+            stopped += 1;
+        }
+    }
+    assert!(stopped > 0);
+}
+
+#[test]
+fn each_bound_alone_matches_brute_force() {
+    for reason in Prune::ALL
+        .into_iter()
+        .filter(|reason| reason.cost().is_some())
+    {
+        check_against_oracle(random_problems(1801, 30), |builder| {
+            builder.bounds([reason])
+        });
+    }
+}
+
+#[test]
+fn probing_matches_brute_force() {
+    check_against_oracle(random_problems(1803, 100), BranchBoundBuilder::with_probing);
+}
+
+#[test]
+fn probing_matches_brute_force_with_constraints() {
+    let mut rng = StdRng::seed_from_u64(18030);
+    let problems = random_problems(1803, 500).map(|mut problem| {
+        // names that no cocktail uses are rejected, which is fine
+        for _ in 0..3 {
+            let (a, b) = (rng.gen_range(0..14), rng.gen_range(0..14));
+            if a != b {
+                problem
+                    .forbid_combination(&[&format!("i{a}"), &format!("i{b}")])
+                    .ok();
+            }
+        }
+        if rng.gen_bool(0.3) {
+            problem
+                .exclude_ingredient(&format!("i{}", rng.gen_range(0..14)))
+                .ok();
+        }
+        if rng.gen_bool(0.3) {
+            price(&mut problem, &mut rng);
+        }
+        problem
+    });
+    check_against_oracle(problems, BranchBoundBuilder::with_probing);
+}
+
+#[test]
+fn subtree_cache_matches_brute_force() {
+    check_against_oracle(random_problems(1805, 100), |builder| {
+        builder.subtree_cache(1 << 20)
+    });
+}
+
+#[test]
+fn ingredient_branching_matches_brute_force() {
+    for order in [SearchOrder::DepthFirst, SearchOrder::BestFirst] {
+        check_against_oracle(random_problems(1806, 100), |builder| {
+            builder.branching(MostCommonIngredient).search_order(order)
+        });
+    }
+}
+
+#[test]
+fn restarts_match_brute_force() {
+    let mut rng = StdRng::seed_from_u64(18070);
+    check_against_oracle(random_problems(1807, 100), |builder| {
+        builder
+            .with_seed(rng.gen())
+            .with_restarts(3, rng.gen_range(1..=20))
+    });
+}
+
+#[test]
+fn transposition_table_matches_brute_force() {
+    // the smallest table holds one node, so is evicting all the time
+    for (max_bytes, eviction) in [
+        (1 << 20, Eviction::Oldest),
+        (1 << 20, Eviction::LowestBound),
+        (100, Eviction::LowestBound),
+    ] {
+        check_against_oracle(random_problems(1781, 100), |builder| {
+            builder.transposition_table(max_bytes, eviction)
+        });
+    }
+}
+
+#[test]
+fn transposition_table_prunes_a_repeated_search() {
+    let mut repeated = 0;
+    for problem in random_problems(1781, 100) {
+        let mut bb = problem
+            .builder(1_000_000)
+            .transposition_table(1 << 20, Eviction::Oldest)
+            .build();
+        // as prepared for the first search, which the second starts from too
+        let (mut candidates, mut partial) = (problem.cocktail_sets(), Default::default());
+        let first = bb.search(&mut candidates, &mut partial, &mut None);
+        let second = bb.search(&mut candidates, &mut partial, &mut None);
+        assert!(bb.status.completed());
+        assert_eq!(second, first);
+        assert_eq!(
+            (bb.highest_score, bb.highest_ingredients.len()),
+            brute_force(&problem)
+        );
+        // unless the first incumbent was already optimal, the first search branched
+        // on the root, so the second stops there
+        if bb.stats.nodes > 2 {
+            assert_eq!(bb.stats.prunes.get(&Prune::Transposition), Some(&1));
+            repeated += 1;
+        }
+    }
+    assert!(repeated > 0);
+}
+
+#[test]
+fn limited_discrepancy_matches_brute_force() {
+    let searches = check_against_oracle(random_problems(1808, 100), |builder| {
+        builder.search_order(SearchOrder::LimitedDiscrepancy)
+    });
+    assert!(searches.iter().all(|bb| bb.gap().is_none()));
+}
+
+#[test]
+fn stepping_matches_brute_force() {
+    for problem in random_problems(1809, 100) {
+        let mut bb = problem.builder(1_000_000).build();
+        // breadth-first, which the search itself never does
+        let mut queue = VecDeque::from([bb.root(&problem.cocktail_sets())]);
+        let mut last = None;
+        while let Some(node) = queue.pop_front() {
+            let expansion = bb.step(&node).unwrap();
+            last = expansion.incumbent.or(last);
+            queue.extend(expansion.children);
+        }
+        if let Some(last) = last {
+            assert_eq!(last, bb.best());
+        }
+        assert_eq!(
+            (bb.highest_score, bb.highest_ingredients.len()),
+            brute_force(&problem)
+        );
+    }
+}
+
+#[test]
+fn split_and_merge_matches_brute_force() {
+    let mut rng = StdRng::seed_from_u64(18100);
+    let path = std::env::temp_dir().join(format!("work-unit-{}", std::process::id()));
+    for problem in random_problems(1810, 100) {
+        let mut bb = problem.builder(1_000_000).build();
+        let units = bb.split(&problem.cocktail_sets(), rng.gen_range(1..=8));
+        for unit in units {
+            // as if on another machine
+            unit.save(&path).unwrap();
+            let result = WorkUnit::load(&path).unwrap().solve();
+            result.save(&path).unwrap();
+            bb.merge(&WorkResult::load(&path).unwrap());
+        }
+        assert!(bb.status.completed());
+        assert_eq!(
+            (bb.highest_score, bb.highest_ingredients.len()),
+            brute_force(&problem)
+        );
+    }
+    std::fs::remove_file(path).ok();
+}
+
+#[test]
+fn resumed_checkpoints_match_brute_force() {
+    let mut rng = StdRng::seed_from_u64(17680);
+    let path = std::env::temp_dir().join(format!("checkpoint-{}", std::process::id()));
+    let mut interrupted = 0;
+    for problem in random_problems(1768, 100) {
+        // so a search saving nothing can't resume the last one's checkpoint
+        std::fs::remove_file(&path).ok();
+        let mut bb = problem
+            .builder(rng.gen_range(1..=20))
+            .checkpoint(&path, rng.gen_range(1..=5))
+            .build();
+        problem.search(&mut bb).unwrap();
+        if bb.status.completed() {
+            continue;
+        }
+        interrupted += 1;
+        // as if after a restart, with calls to spare this time
+        let mut resumed = BranchBound::resume_from(&path).unwrap();
+        resumed.calls = 1_000_000;
+        let solution = problem.resume(&mut resumed).unwrap();
+        assert!(resumed.status.completed());
+        assert_eq!(problem.validate(&solution), Ok(()));
+        let mut uninterrupted = problem.builder(1_000_000).build();
+        problem.search(&mut uninterrupted).unwrap();
+        assert_eq!(resumed.highest_score, uninterrupted.highest_score);
+        assert_eq!(
+            (resumed.highest_score, resumed.highest_ingredients.len()),
+            brute_force(&problem)
+        );
+    }
+    assert!(interrupted > 0);
+    std::fs::remove_file(path).ok();
+}
+
+#[test]
+fn only_depth_first_searches_checkpoint() {
+    for order in [
+        SearchOrder::BestFirst,
+        SearchOrder::Beam(4),
+        SearchOrder::LimitedDiscrepancy,
+    ] {
+        let built = BranchBoundBuilder::new(10_000, 3)
+            .search_order(order)
+            .checkpoint("unwritten", 1)
+            .try_build();
+        assert!(matches!(
+            built,
+            Err(ConfigError::UncheckpointedOrder(rejected)) if rejected == order
+        ));
+    }
+}
+
+#[cfg(feature = "ilp")]
+#[test]
+fn integer_program_matches_brute_force() {
+    for problem in random_problems(1811, 50) {
+        let mut bb = problem.builder(1).build();
+        bb.solve_integer_program(&problem.cocktail_sets()).unwrap();
+        assert_eq!(
+            (bb.highest_score, bb.highest_ingredients.len()),
+            brute_force(&problem)
+        );
+    }
+    let mut rng = StdRng::seed_from_u64(18110);
+    for _ in 0..50 {
+        let ingredients = rng.gen_range(6..=14);
+        let mut problem = Problem::random(&mut rng, ingredients, 20, 2..=4, 6);
+        problem.require_ingredient(&format!("i{}", rng.gen_range(0..ingredients)));
+        let (a, b) = (rng.gen_range(0..ingredients), rng.gen_range(0..ingredients));
+        if a != b {
+            problem
+                .forbid_combination(&[&format!("i{a}"), &format!("i{b}")])
+                .ok();
+        }
+        let Ok(mut bb) = problem.builder(1).try_build() else {
+            continue;
+        };
+        bb.solve_integer_program(&problem.cocktail_sets()).unwrap();
+        let mut brute = problem.builder(1).build();
+        brute.brute_force(&problem.cocktail_sets()).unwrap();
+        assert_eq!(bb.highest_score, brute.highest_score);
+    }
+}
+
+#[test]
+fn fewest_ingredients_match_brute_force() {
+    let mut rng = StdRng::seed_from_u64(17730);
+    for problem in random_problems(1773, 100) {
+        let sizes = 1..=problem.mapper().len();
+        let scores = sizes
+            .clone()
+            .map(|max_size| {
+                let mut problem = problem.clone();
+                problem.max_size = max_size;
+                brute_force(&problem).0
+            })
+            .collect::<Vec<_>>();
+        let target = rng.gen_range(1..=*scores.last().unwrap());
+        let fewest = sizes
+            .zip(&scores)
+            .find(|&(_, &score)| score >= target)
+            .unwrap()
+            .0;
+        let mut bb = problem.builder(1_000_000).build();
+        let solution = problem.min_ingredients_for(&mut bb, target).unwrap();
+        assert!(bb.status.completed());
+        assert!(solution.cocktails.len() >= target);
+        assert_eq!(solution.ingredients.len(), fewest);
+
+        let unreachable = problem.cocktails().len() + 1;
+        let mut bb = problem.builder(1_000_000).build();
+        assert_eq!(problem.min_ingredients_for(&mut bb, unreachable), None);
+    }
+}
+
+#[test]
+fn no_target_needs_only_the_required_ingredients() {
+    let mut rng = StdRng::seed_from_u64(17731);
+    for mut problem in random_problems(1773, 50) {
+        let required = some_ingredients(&problem, &mut rng, 0..=2);
+        for name in &required {
+            problem.require_ingredient(name);
+        }
+        let mut bb = problem.builder(1_000_000).build();
+        let solution = problem.min_ingredients_for(&mut bb, 0).unwrap();
+        assert_eq!(
+            solution.ingredients.iter().collect::<BTreeSet<_>>(),
+            required.iter().collect()
+        );
+    }
+}
+
+#[test]
+fn pareto_frontier_matches_brute_force() {
+    let mut rng = StdRng::seed_from_u64(17830);
+    for mut problem in random_problems(1783, 60) {
+        price(&mut problem, &mut rng);
+        let mut bb = problem.builder(1_000_000).build();
+        let frontier = bb.pareto(&problem.cocktail_sets(), 1..=problem.max_size);
+        assert!(bb.status.completed());
+        // (ingredients, score, cost) of every affordable set making something
+        let cocktails = problem.cocktail_sets();
+        let points = every_set(&problem)
+            .map(|ingredients| {
+                let made = cocktails
+                    .iter()
+                    .filter(|cocktail| cocktail.is_subset(&ingredients))
+                    .count();
+                (ingredients.len(), made, bb.cost(&ingredients))
+            })
+            .filter(|&(_, made, cost)| made > 0 && cost <= bb.budget.unwrap() + 1e-9)
+            .collect::<Vec<_>>();
+        let dominated = |(ingredients, score, cost): (usize, usize, f64)| {
+            points.iter().any(|&(i, s, c)| {
+                i <= ingredients
+                    && s >= score
+                    && c <= cost + 1e-9
+                    && (i < ingredients || s > score || c < cost - 1e-9)
+            })
+        };
+        for point in &frontier {
+            assert!(!dominated((
+                point.ingredients,
+                point.best.score,
+                point.cost
+            )));
+        }
+        for &(ingredients, score, cost) in &points {
+            if !dominated((ingredients, score, cost)) {
+                assert!(frontier.iter().any(|point| point.ingredients == ingredients
+                    && point.best.score == score
+                    && (point.cost - cost).abs() <= 1e-9));
+            }
+        }
+    }
+}
+
+#[test]
+fn sweep_matches_brute_force() {
+    for warm_start in [false, true] {
+        for problem in random_problems(1765, 60) {
+            let mut bb = problem.builder(1_000_000).build();
+            let results = bb.sweep(&problem.cocktail_sets(), 1..=problem.max_size, warm_start);
+            assert!(bb.status.completed());
+            assert_eq!(results.len(), problem.max_size);
+            for (size, best) in results {
+                let mut problem = problem.clone();
+                problem.max_size = size;
+                assert_eq!((best.score, best.ingredients.len()), brute_force(&problem));
+                assert_eq!(bb.score(&best.cocktails), best.score);
+            }
+        }
+    }
+}
+
+#[test]
+fn portfolio_matches_brute_force() {
+    for anneal in [false, true] {
+        for problem in random_problems(1772, 60) {
+            let mut portfolio = Portfolio::new(problem.builder(1_000_000), [1, 2, 3]);
+            if anneal {
+                portfolio = portfolio.with_annealing(Annealing::new(1_000).with_seed(1772));
+            }
+            let solution = portfolio.solve(&problem).unwrap();
+            assert_eq!(problem.validate(&solution), Ok(()));
+            assert_eq!(scored(&problem, &solution), brute_force(&problem));
+        }
+    }
+}
+
+#[test]
+fn annealing_is_reproducible_and_no_worse_than_greedy() {
+    for problem in random_problems(1770, 100) {
+        let mut annealing = Annealing::new(2_000).with_seed(1770);
+        let solution = annealing.solve(&problem).unwrap();
+        // the statistics time the run, so only compare the sets
+        let again = annealing.solve(&problem).unwrap();
+        assert_eq!(
+            (&again.ingredients, &again.cocktails),
+            (&solution.ingredients, &solution.cocktails)
+        );
+        assert_eq!(problem.validate(&solution), Ok(()));
+        let greedy = Greedy.solve(&problem).unwrap();
+        assert!(scored(&problem, &solution).0 >= scored(&problem, &greedy).0);
+    }
+}
+
+#[test]
+fn depth_limited_search_bounds_brute_force() {
+    let (mut truncated, mut gaps) = (0, 0);
+    for problem in random_problems(1737, 100) {
+        let mut bb = problem.builder(1_000_000).max_depth(1).build();
+        let solution = problem.search(&mut bb).unwrap();
+        assert_eq!(problem.validate(&solution), Ok(()));
+        let (best, _) = brute_force(&problem);
+        assert!(bb.highest_score <= best);
+        if bb.depth_truncations == 0 {
+            continue;
+        }
+        truncated += 1;
+        match bb.gap() {
+            Some(gap) => {
+                gaps += 1;
+                assert!(gap.upper >= best);
+            }
+            None => assert_eq!(bb.highest_score, best),
+        }
+    }
+    assert!(truncated > 0 && gaps > 0);
+}
+
+#[test]
+fn seeded_searches_are_reproducible() {
+    // larger than brute force allows, for more ties to break
+    let mut rng = StdRng::seed_from_u64(1754);
+    for seed in 0..10 {
+        let problem = Problem::random(&mut rng, 20, 40, 2..=4, 6);
+        let run = || {
+            let mut bb = problem.builder(5_000).with_seed(seed).build();
+            problem.search(&mut bb).unwrap();
+            (bb.counter, bb.stats.nodes, bb.highest_ingredients)
+        };
+        assert_eq!(run(), run());
+    }
+}
+
+#[test]
+fn approximation_stays_within_its_factor_of_brute_force() {
+    for epsilon in [0.1, 0.5, 1.0] {
+        for problem in random_problems(1740, 100) {
+            let mut bb = problem.builder(1_000_000).approximation(epsilon).build();
+            let solution = problem.search(&mut bb).unwrap();
+            assert!(bb.status.completed());
+            assert_eq!(problem.validate(&solution), Ok(()));
+            let (best, _) = brute_force(&problem);
+            assert!(bb.highest_score as f64 >= best as f64 / (1.0 + epsilon));
+            assert!(bb.upper_bound() >= best);
+        }
+    }
+}
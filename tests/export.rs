@@ -0,0 +1,65 @@
+use branchbound::problem::Problem;
+use branchbound::Error;
+
+fn bar() -> Problem {
+    let mut problem = Problem::new(2);
+    problem.add_cocktail("Gimlet", ["gin", "lime"]);
+    // needs two more ingredients, so it can't be made and has no variable
+    problem.add_cocktail("Gin Rickey", ["gin", "lime", "soda"]);
+    problem.add_cocktail("Daiquiri", ["rum", "lime"]);
+    problem.require_ingredient("lime");
+    problem
+}
+
+#[test]
+fn exports_the_integer_program() {
+    let path = std::env::temp_dir().join(format!("program-{}", std::process::id()));
+    bar().export_lp(&path).unwrap();
+    let lp = std::fs::read_to_string(&path).unwrap();
+    assert_eq!(
+        lp,
+        "\\ buy_0: buy gin
+\\ buy_3: buy rum
+\\ make_0: make Gimlet
+\\ make_1: make Daiquiri
+Maximize
+ obj: - buy_0 - buy_3 + 3 make_0 + 3 make_1
+Subject To
+ needs_0_0: + make_0 - buy_0 <= 0
+ needs_1_3: + make_1 - buy_3 <= 0
+ size: + buy_0 + buy_3 <= 1
+Bounds
+Binaries
+ buy_0
+ buy_3
+ make_0
+ make_1
+Generals
+End
+"
+    );
+    bar().export_mps(&path).unwrap();
+    let mps = std::fs::read_to_string(&path).unwrap();
+    std::fs::remove_file(&path).ok();
+    for line in [
+        " L size",
+        "    make_0 obj 3",
+        "    buy_3 needs_1_3 -1",
+        "    RHS size 1",
+        " BV BND make_1",
+    ] {
+        assert!(mps.lines().any(|l| l == line), "{line}");
+    }
+    assert!(mps.ends_with("ENDATA\n"));
+}
+
+#[test]
+fn refuses_variant_groups() {
+    let mut problem = bar();
+    problem.variant_group(["Gimlet", "Daiquiri"]).unwrap();
+    let path = std::env::temp_dir().join(format!("variants-{}", std::process::id()));
+    assert!(matches!(
+        problem.export_lp(&path),
+        Err(Error::Unsupported(_))
+    ));
+}
@@ -0,0 +1,78 @@
+use branchbound::problem::{Problem, Violation};
+use branchbound::solution::Solution;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+
+#[test]
+fn solver_output_validates() {
+    let mut rng = StdRng::seed_from_u64(1743);
+    for _ in 0..50 {
+        let problem = Problem::random(&mut rng, 12, 20, 2..=4, 5);
+        let mut bb = problem.builder(10_000).build();
+        let best = bb.search(
+            &mut problem.cocktail_sets(),
+            &mut Default::default(),
+            &mut None,
+        );
+        let solution = problem.solution(&best, &bb.highest_ingredients);
+        assert_eq!(problem.validate(&solution), Ok(()));
+    }
+}
+
+#[test]
+fn reports_violations() {
+    let mut problem = Problem::new(2);
+    problem.add_cocktail("Negroni", ["gin", "campari", "vermouth"]);
+    problem.add_cocktail("Gimlet", ["gin", "lime"]);
+    problem.require_ingredient("lime");
+    let solution = Solution {
+        ingredients: vec!["gin".into(), "campari".into(), "soda".into()],
+        cocktails: vec!["Negroni".into(), "Americano".into()],
+        ..Default::default()
+    };
+    let violations = problem.validate(&solution).unwrap_err();
+    assert!(violations.contains(&Violation::UnknownIngredient("soda".into())));
+    assert!(violations.contains(&Violation::OverBudget {
+        ingredients: 3,
+        max_size: 2
+    }));
+    assert!(violations.contains(&Violation::NotMakeable {
+        cocktail: "Negroni".into(),
+        missing: vec!["vermouth".into()]
+    }));
+    assert!(violations.contains(&Violation::UnknownCocktail("Americano".into())));
+    assert!(violations.contains(&Violation::MissingRequired("lime".into())));
+}
+
+#[test]
+fn problem_round_trips_through_json() {
+    let mut rng = StdRng::seed_from_u64(1818);
+    let mut problem = Problem::random(&mut rng, 12, 20, 2..=4, 5);
+    problem.require_ingredient("i3");
+    problem.set_price("i1", 2.5).unwrap();
+    let json = serde_json::to_string(&problem).unwrap();
+    let loaded: Problem = serde_json::from_str(&json).unwrap();
+    let (a, b) = (
+        loaded.solve(10_000).unwrap(),
+        problem.solve(10_000).unwrap(),
+    );
+    assert_eq!((a.ingredients, a.cocktails), (b.ingredients, b.cocktails));
+    assert_eq!(loaded.required(), problem.required());
+}
+
+#[test]
+fn displays_ingredient_sets_by_name() {
+    let mut problem = Problem::new(2);
+    problem.add_cocktail("Gimlet", ["gin", "lime juice"]);
+    let gimlet = &problem.find("Gimlet").unwrap().ingredients;
+    let mut set = gimlet.clone();
+    set.insert(99);
+    assert_eq!(
+        problem.mapper().display(&set).to_string(),
+        "{#99, gin, lime juice}"
+    );
+    assert_eq!(
+        format!("{:?}", problem.mapper().display(gimlet)),
+        r#"{"gin", "lime juice"}"#
+    );
+}